@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BackupFile } from "./BackupFile";
+
+export type BackupManifest = { format: string, version: number, app_version: string, created_at: string, 
+/**
+ * 备份时的数据目录，恢复到其他位置时用于改写事件内容路径
+ */
+data_dir: string, files: Array<BackupFile>, };
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CacheStats = { name: string, size: number, capacity: number, hits: number, misses: number, evictions: number, };
\ No newline at end of file
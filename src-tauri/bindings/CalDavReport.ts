@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type CalDavReport = { uploaded: number, downloaded: number, deleted_local: number, deleted_remote: number, conflicts: Array<string>, errors: Array<string>, };
\ No newline at end of file
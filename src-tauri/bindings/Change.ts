@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeKind } from "./ChangeKind";
+import type { EntityKind } from "./EntityKind";
+
+export type Change = { entity: EntityKind, kind: ChangeKind, id: string, 
+/**
+ * 变更前后实体所属的分组（事件所属的列表ID），用于按分组失效缓存
+ */
+scopes: Array<string>, };
\ No newline at end of file
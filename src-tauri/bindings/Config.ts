@@ -1,7 +1,9 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Backup } from "./Backup";
+import type { CalDav } from "./CalDav";
 import type { Info } from "./Info";
 import type { Model } from "./Model";
 import type { Theme } from "./Theme";
 import type { WebDav } from "./WebDav";
 
-export type Config = { theme: Theme, info: Info, model: Model, webdav: WebDav, };
+export type Config = { theme: Theme, info: Info, model: Model, webdav: WebDav, backup: Backup, caldav: CalDav, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { CsvColumn } from "./CsvColumn";
+
+export type CsvPreview = { headers: Array<string>, rows: Array<Array<string>>, 
+/**
+ * 根据表头推测的映射，与 `headers` 一一对应，None 表示忽略该列
+ */
+mapping: Array<CsvColumn | null>, };
\ No newline at end of file
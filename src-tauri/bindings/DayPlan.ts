@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlanItem } from "./PlanItem";
+
+/**
+ * 当日计划建议，`items` 按建议的执行顺序排列
+ */
+export type DayPlan = { date: string, items: Array<PlanItem>, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type EventSelector = "All" | { "Ids": Array<string> } | { "List": string } | { "Status": boolean } | { "DateRange": { start: number, end: number, } } | { "Filter": string } | { "Search": string };
\ No newline at end of file
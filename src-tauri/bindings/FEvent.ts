@@ -1,4 +1,4 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { Priority } from "./Priority";
 
-export type FEvent = { id: string, listid: string, tag: Array<string> | null, title: string, create: string, ddl: string, finished: boolean, priority: Priority, icon: string, color: string, };
+export type FEvent = { id: string, listid: string, tag: Array<string> | null, title: string, create: string, ddl: string, finished: boolean, priority: Priority, icon: string, color: string, blocked_by: Array<string>, recurrence: string | null, estimate: number | null, readonly: boolean, };
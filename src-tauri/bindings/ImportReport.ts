@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportedItem } from "./ImportedItem";
+import type { SkippedItem } from "./SkippedItem";
+
+export type ImportReport = { created: Array<ImportedItem>, updated: Array<ImportedItem>, skipped: Array<SkippedItem>, };
\ No newline at end of file
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PlanAction } from "./PlanAction";
+import type { Priority } from "./Priority";
+
+export type PlanItem = { id: string, title: string, action: PlanAction, 
+/**
+ * 建议的时间，毫秒时间戳字符串
+ */
+task_time: string, priority: Priority, reason: string, };
@@ -0,0 +1,20 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Priority } from "./Priority";
+
+export type QuickAddPreview = { title: string, 
+/**
+ * 截止时间，毫秒时间戳字符串，未解析到时为空字符串
+ */
+ddl: string, 
+/**
+ * RFC 5545 RRULE
+ */
+recurrence: string | null, tags: Array<string>, priority: Priority, 
+/**
+ * 输入中 `@` 后的列表名
+ */
+list_name: string | null, 
+/**
+ * 按名称匹配到的列表ID，未找到时为 None
+ */
+listid: string | null, };
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FEvent } from "./FEvent";
+
+export type SearchHit = { event: FEvent, 
+/**
+ * 余弦相似度，越接近 1 越相似
+ */
+score: number, };
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type Subscription = { uuid: string, name: string, source: string, color: string, refresh: number, last_refreshed: number | null, last_attempt: number | null, error: string | null, };
\ No newline at end of file
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type SubtaskProposal = { title: string, 
+/**
+ * 预计耗时（分钟）
+ */
+estimate: number | null, };
@@ -1,13 +1,20 @@
+pub mod dependency;
+pub mod embedding;
 pub mod event;
 pub mod list;
+pub mod subscription;
 pub mod tag;
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use tokio::sync::Mutex;
 
 use anyhow::{Ok, Result};
+use redb::backends::InMemoryBackend;
 use redb::{self, Database, ReadableTable, TableDefinition};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::bus::{self, Change, ChangeKind, EntityKind};
 use crate::utils::path::AppPaths;
 
 pub use event::{Event, FEvent};
@@ -19,11 +26,38 @@ type Table = TableDefinition<'static, &'static [u8], &'static [u8]>;
 const EVENT_TABLE: Table = TableDefinition::new("events");
 const LIST_TABLE: Table = TableDefinition::new("lists");
 const TAG_TABLE: Table = TableDefinition::new("tag");
+const EMBEDDING_TABLE: Table = TableDefinition::new("embeddings");
+const SUBSCRIPTION_TABLE: Table = TableDefinition::new("subscriptions");
+const SUBSCRIPTION_ITEM_TABLE: Table = TableDefinition::new("subscription_items");
 
 pub trait Entity: Clone + Serialize + for<'de> Deserialize<'de> {
     fn table_def() -> TableDefinition<'static, &'static [u8], &'static [u8]>;
     fn id_bytes(&self) -> Vec<u8>;
     fn value(&self) -> Vec<u8>;
+    fn kind() -> EntityKind;
+    /// 实体所属的分组（如事件所属的列表），变更通知据此失效分组缓存
+    fn scope(&self) -> Option<String> {
+        None
+    }
+}
+
+// 合并变更前后的分组并发布变更通知
+fn notify<T: Entity>(kind: ChangeKind, id: &[u8], before: Option<T>, after: Option<&T>) {
+    let mut scopes: Vec<String> = before
+        .and_then(|entity| entity.scope())
+        .into_iter()
+        .collect();
+    if let Some(scope) = after.and_then(|entity| entity.scope()) {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    bus::publish(&Change {
+        entity: T::kind(),
+        kind,
+        id: String::from_utf8_lossy(id).to_string(),
+        scopes,
+    });
 }
 
 pub trait Repository<T: Entity> {
@@ -52,6 +86,78 @@ impl Storage {
         let db = connect_to_db()?;
         Ok(Self { db })
     }
+
+    /// 数据库文件路径
+    pub fn db_path() -> PathBuf {
+        AppPaths::data_dir().join(DB_NAME)
+    }
+
+    /// 重新打开数据库文件，用于从备份恢复、数据库文件被替换之后
+    pub fn reload(&mut self) -> Result<()> {
+        // 先释放旧的文件句柄，否则无法再次打开同一文件
+        self.close()?;
+        self.db = connect_to_db()?;
+        Ok(())
+    }
+
+    /// 释放数据库文件以便替换，之后需调用 `reload` 重新打开
+    pub fn close(&mut self) -> Result<()> {
+        self.db = Database::builder().create_with_backend(InMemoryBackend::new())?;
+        Ok(())
+    }
+
+    /// 记录事件、列表与标签的当前内容
+    pub fn snapshot(&self) -> Result<Snapshot> {
+        let txn = self.db.begin_read()?;
+        let mut rows = HashMap::new();
+        for (kind, table) in [
+            (EntityKind::Event, EVENT_TABLE),
+            (EntityKind::List, LIST_TABLE),
+            (EntityKind::Tag, TAG_TABLE),
+        ] {
+            let t = match txn.open_table(table) {
+                Err(redb::TableError::TableDoesNotExist(_)) => continue,
+                t => t?,
+            };
+            for entry in t.iter()? {
+                let (key, value) = entry?;
+                let id = String::from_utf8_lossy(key.value()).to_string();
+                rows.insert((kind, id), value.value().to_vec());
+            }
+        }
+        Ok(Snapshot(rows))
+    }
+}
+
+/// 数据库内容的快照，用于找出绕过仓库写入（如从备份恢复数据库）造成的变化
+pub struct Snapshot(HashMap<(EntityKind, String), Vec<u8>>);
+
+impl Snapshot {
+    /// 与之后的快照比较，返回其间发生的变化
+    pub fn changes(&self, after: &Snapshot) -> Vec<Change> {
+        let change = |(entity, id): &(EntityKind, String), kind| Change {
+            entity: *entity,
+            kind,
+            id: id.clone(),
+            scopes: Vec::new(),
+        };
+        let mut changes: Vec<Change> = after
+            .0
+            .iter()
+            .filter_map(|(key, value)| match self.0.get(key) {
+                None => Some(change(key, ChangeKind::Created)),
+                Some(old) if old != value => Some(change(key, ChangeKind::Updated)),
+                Some(_) => None,
+            })
+            .collect();
+        changes.extend(
+            self.0
+                .keys()
+                .filter(|key| !after.0.contains_key(*key))
+                .map(|key| change(key, ChangeKind::Deleted)),
+        );
+        changes
+    }
 }
 
 pub struct App<R = tauri::Wry>
@@ -75,25 +181,38 @@ impl<T: Entity> Repository<T> for Storage {
     fn add(&self, entity: &T) -> Result<()> {
         let txn = self.db.begin_write()?;
         let table = T::table_def();
-        {
+        let key = entity.id_bytes();
+        let before: Option<Option<T>> = {
             let mut t = txn.open_table(table)?;
-            let key = entity.id_bytes();
             let value = entity.value();
-            t.insert(&key[..], &value[..])?;
-        }
+            let old = t.insert(&key[..], &value[..])?;
+            old.map(|old| serde_json::from_slice(old.value()).ok())
+        };
         txn.commit()?;
+
+        let kind = if before.is_some() {
+            ChangeKind::Updated
+        } else {
+            ChangeKind::Created
+        };
+        notify(kind, &key, before.flatten(), Some(entity));
         Ok(())
     }
 
     fn delete(&self, name: &str) -> Result<()> {
         let txn = self.db.begin_write()?;
         let table = T::table_def();
-        {
+        let key = name.as_bytes();
+        let before: Option<Option<T>> = {
             let mut t = txn.open_table(table)?;
-            let key = name.as_bytes();
-            t.remove(key)?;
-        }
+            let old = t.remove(key)?;
+            old.map(|old| serde_json::from_slice(old.value()).ok())
+        };
         txn.commit()?;
+
+        if let Some(before) = before {
+            notify(ChangeKind::Deleted, key, before, None);
+        }
         Ok(())
     }
 
@@ -154,6 +273,9 @@ fn connect_to_db() -> Result<Database> {
         let _ = txn.open_table(EVENT_TABLE)?;
         let _ = txn.open_table(LIST_TABLE)?;
         let _ = txn.open_table(TAG_TABLE)?;
+        let _ = txn.open_table(EMBEDDING_TABLE)?;
+        let _ = txn.open_table(SUBSCRIPTION_TABLE)?;
+        let _ = txn.open_table(SUBSCRIPTION_ITEM_TABLE)?;
     }
     txn.commit()?;
     Ok(db)
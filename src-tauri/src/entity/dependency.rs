@@ -0,0 +1,224 @@
+use anyhow::anyhow;
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+use tauri::State;
+
+use super::{Event, FEvent, Repository, Storage, StorageState};
+use crate::error::ErrorKind;
+
+/// 判断添加 `event` 被 `blocker` 阻塞的关系后是否会形成环
+///
+/// `graph` 为事件UUID到其阻塞者列表的映射。若从 `blocker` 沿阻塞关系
+/// 能够到达 `event`，则新增的这条边会闭合一个环。
+pub fn creates_cycle(graph: &HashMap<String, Vec<String>>, event: &str, blocker: &str) -> bool {
+    if event == blocker {
+        return true;
+    }
+    let mut stack = vec![blocker];
+    let mut visited = HashSet::new();
+    while let Some(current) = stack.pop() {
+        if current == event {
+            return true;
+        }
+        if !visited.insert(current) {
+            continue;
+        }
+        if let Some(next) = graph.get(current) {
+            stack.extend(next.iter().map(|id| id.as_str()));
+        }
+    }
+    false
+}
+
+/// 判断事件是否被阻塞
+///
+/// 只要存在一个尚未完成的阻塞者，事件即处于阻塞状态。
+/// `unfinished` 为所有未完成事件的UUID集合，已删除的阻塞者会被忽略。
+pub fn is_blocked(event: &Event, unfinished: &HashSet<String>) -> bool {
+    event
+        .metadata
+        .blocked_by
+        .iter()
+        .any(|blocker| unfinished.contains(blocker))
+}
+
+/// 收集所有未完成事件的UUID
+pub fn unfinished_ids(storage: &Storage) -> anyhow::Result<HashSet<String>> {
+    let events = Repository::<Event>::filter(storage, |event| !event.finished)?;
+    Ok(events
+        .into_iter()
+        .map(|event| event.metadata.uuid)
+        .collect())
+}
+
+/// 查找因 `blocker` 完成而解除阻塞的事件
+///
+/// 返回阻塞者中包含 `blocker`、自身尚未完成且已没有其他未完成阻塞者的事件。
+pub fn unblocked_by(storage: &Storage, blocker: &str) -> anyhow::Result<Vec<Event>> {
+    let unfinished = unfinished_ids(storage)?;
+    Repository::<Event>::filter(storage, |event| {
+        !event.finished
+            && event.metadata.blocked_by.iter().any(|id| id == blocker)
+            && !is_blocked(event, &unfinished)
+    })
+}
+
+/// 从所有事件的阻塞者中移除指定事件，用于删除事件后的清理
+pub fn detach_blocker(storage: &Storage, blocker: &str) -> anyhow::Result<()> {
+    let dependents = Repository::<Event>::filter(storage, |event| {
+        event.metadata.blocked_by.iter().any(|id| id == blocker)
+    })?;
+    for dependent in dependents {
+        Repository::<Event>::update(storage, &dependent.metadata.uuid, |event: &mut Event| {
+            event.metadata.blocked_by.retain(|id| id != blocker);
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+fn dependency_graph(storage: &Storage) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let events = Repository::<Event>::get_all(storage)?;
+    Ok(events
+        .into_iter()
+        .map(|event| (event.metadata.uuid, event.metadata.blocked_by))
+        .collect())
+}
+
+/// 添加阻塞关系
+///
+/// 将 `blocker` 记为 `uuid` 的前置任务，`uuid` 在 `blocker` 完成前处于阻塞状态。
+/// 插入前会检查依赖图，如果新关系会形成环则拒绝。
+///
+/// # 参数
+/// * `state` - 应用状态，包含数据库访问权限
+/// * `uuid` - 被阻塞事件的唯一标识符
+/// * `blocker` - 阻塞事件的唯一标识符
+///
+/// # 返回
+/// * `Result<(), ErrorKind>` - 成功时返回空元组，事件不存在时返回 `NotFound`，
+///   形成环时返回 `InvalidInput`
+#[tauri::command]
+pub async fn add_blocker(
+    state: State<'_, StorageState>,
+    uuid: &str,
+    blocker: &str,
+) -> Result<(), ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let graph = dependency_graph(storage)?;
+    if !graph.contains_key(uuid) || !graph.contains_key(blocker) {
+        return Err(ErrorKind::NotFound);
+    }
+    if graph[uuid].iter().any(|id| id == blocker) {
+        return Ok(());
+    }
+    if creates_cycle(&graph, uuid, blocker) {
+        log::warn!("Rejected dependency {} -> {}: cycle detected", uuid, blocker);
+        return Err(ErrorKind::InvalidInput);
+    }
+
+    Repository::<Event>::update(storage, uuid, |event: &mut Event| {
+        event.metadata.blocked_by.push(blocker.to_string());
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// 移除阻塞关系
+///
+/// # 参数
+/// * `state` - 应用状态，包含数据库访问权限
+/// * `uuid` - 被阻塞事件的唯一标识符
+/// * `blocker` - 要移除的阻塞事件的唯一标识符
+///
+/// # 返回
+/// * `Result<(), ErrorKind>` - 成功时返回空元组，事件不存在时返回 `NotFound`
+#[tauri::command]
+pub async fn remove_blocker(
+    state: State<'_, StorageState>,
+    uuid: &str,
+    blocker: &str,
+) -> Result<(), ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    Repository::<Event>::update(storage, uuid, |event: &mut Event| {
+        event.metadata.blocked_by.retain(|id| id != blocker);
+        Ok(())
+    })
+    .map_err(|_| ErrorKind::NotFound)?;
+    Ok(())
+}
+
+/// 获取阻塞指定事件的未完成事件
+///
+/// # 参数
+/// * `state` - 应用状态，包含数据库访问权限
+/// * `uuid` - 事件的唯一标识符
+///
+/// # 返回
+/// * `Result<Vec<FEvent>, ErrorKind>` - 仍在阻塞该事件的前端事件列表
+#[tauri::command]
+pub async fn event_blockers(
+    state: State<'_, StorageState>,
+    uuid: &str,
+) -> Result<Vec<FEvent>, ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let event = Repository::<Event>::get_by_name(storage, uuid)?
+        .ok_or_else(|| anyhow!("Event not found"))?;
+    let mut blockers = Vec::new();
+    for id in &event.metadata.blocked_by {
+        if let Some(blocker) = Repository::<Event>::get_by_name(storage, id)? {
+            if !blocker.finished {
+                blockers.push(FEvent::from(blocker));
+            }
+        }
+    }
+    Ok(blockers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(id, blockers)| {
+                (
+                    id.to_string(),
+                    blockers.iter().map(|b| b.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_creates_cycle() {
+        // a 被 b 阻塞，b 被 c 阻塞
+        let g = graph(&[("a", &["b"]), ("b", &["c"]), ("c", &[]), ("d", &[])]);
+
+        assert!(creates_cycle(&g, "a", "a"), "自身依赖应视为环");
+        assert!(creates_cycle(&g, "c", "a"), "c 被 a 阻塞会闭合 a->b->c->a");
+        assert!(creates_cycle(&g, "b", "a"));
+        assert!(!creates_cycle(&g, "a", "c"), "冗余的传递依赖不构成环");
+        assert!(!creates_cycle(&g, "d", "a"));
+        assert!(!creates_cycle(&g, "a", "d"));
+    }
+
+    #[test]
+    fn test_is_blocked() {
+        let blocker = Event::new("Blocker", "");
+        let mut event = Event::new("Event", "");
+        event.metadata.blocked_by = vec![blocker.metadata.uuid.clone(), "deleted".to_string()];
+
+        let mut unfinished = HashSet::new();
+        unfinished.insert(blocker.metadata.uuid.clone());
+        assert!(is_blocked(&event, &unfinished));
+
+        // 阻塞者完成后（不再位于未完成集合中）解除阻塞，已删除的阻塞者被忽略
+        unfinished.clear();
+        assert!(!is_blocked(&event, &unfinished));
+    }
+}
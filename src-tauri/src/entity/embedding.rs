@@ -0,0 +1,119 @@
+use redb::{self, TableDefinition};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::Entity;
+use crate::utils::bus::EntityKind;
+
+type Table = TableDefinition<'static, &'static [u8], &'static [u8]>;
+
+const EMBEDDING_TABLE: Table = TableDefinition::new("embeddings");
+
+/// 事件文本的向量，以事件UUID为键
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Embedding {
+    pub uuid: String,
+    pub digest: u64, // 生成向量时模型与文本的摘要，用于判断向量是否过期
+    pub vector: Vec<f32>,
+}
+
+impl Entity for Embedding {
+    fn table_def() -> Table {
+        EMBEDDING_TABLE
+    }
+    fn id_bytes(&self) -> Vec<u8> {
+        self.uuid.as_bytes().to_vec()
+    }
+    fn value(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+    fn kind() -> EntityKind {
+        EntityKind::Embedding
+    }
+}
+
+/// 计算模型与文本的摘要，取SHA-256的前8个字节，保存在数据库中因此必须跨版本稳定
+pub fn digest(model: &str, text: &str) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    hasher.update([0]);
+    hasher.update(text.as_bytes());
+    let hash = hasher.finalize();
+    u64::from_be_bytes(hash[..8].try_into().unwrap_or_default())
+}
+
+/// 余弦相似度，维度不一致或存在零向量时返回 0
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// 查找与 `query` 最相近的 `k` 个向量，返回 (事件UUID, 相似度)，相似度从高到低
+pub fn nearest<'a>(
+    query: &[f32],
+    embeddings: impl IntoIterator<Item = &'a Embedding>,
+    k: usize,
+    exclude: Option<&str>,
+) -> Vec<(String, f32)> {
+    let mut scored: Vec<(String, f32)> = embeddings
+        .into_iter()
+        .filter(|embedding| Some(embedding.uuid.as_str()) != exclude)
+        .map(|embedding| (embedding.uuid.clone(), cosine(query, &embedding.vector)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn embedding(uuid: &str, vector: &[f32]) -> Embedding {
+        Embedding {
+            uuid: uuid.to_string(),
+            digest: 0,
+            vector: vector.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_cosine() {
+        assert!((cosine(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine(&[1.0], &[1.0, 0.0]), 0.0, "维度不一致");
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), 0.0, "零向量");
+    }
+
+    #[test]
+    fn test_digest() {
+        assert_eq!(digest("model", "text"), digest("model", "text"));
+        assert_ne!(digest("model", "text"), digest("other", "text"));
+        assert_ne!(digest("ab", "c"), digest("a", "bc"), "模型与文本之间有分隔");
+    }
+
+    #[test]
+    fn test_nearest() {
+        let embeddings = vec![
+            embedding("invoice", &[1.0, 0.1]),
+            embedding("billing", &[0.9, 0.2]),
+            embedding("garden", &[0.0, 1.0]),
+        ];
+
+        let hits = nearest(&[1.0, 0.0], &embeddings, 2, None);
+        let ids: Vec<&str> = hits.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["invoice", "billing"]);
+
+        // 查找相似事件时排除自身
+        let hits = nearest(&[1.0, 0.1], &embeddings, 1, Some("invoice"));
+        assert_eq!(hits[0].0, "billing");
+    }
+}
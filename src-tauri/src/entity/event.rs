@@ -3,18 +3,22 @@ use chrono::Utc;
 use redb::{self, TableDefinition};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io;
 use std::ops::DerefMut;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::State;
 use ts_rs::TS;
 use uuid::Uuid;
 
-use super::{Entity, Repository, StorageState};
+use super::embedding::Embedding;
+use super::subscription::{self, OverlayItem};
+use super::{dependency, Entity, Repository, Storage, StorageState};
 use crate::error::ErrorKind;
-use crate::filter::{map_filter, Filter};
+use crate::filter::{is_date_filter, map_filter, Filter};
+use crate::function::aigc::search;
 use crate::function::gen_tag;
-use crate::utils::{AppPaths, EVENT_CONTENT_CACHE, EVENT_LIST_CACHE};
-use tauri::{AppHandle};
+use crate::utils::bus::{self, Change, ChangeKind, EntityKind};
+use crate::utils::{AppPaths, EVENT_CONTENT_CACHE};
 
 type Table = TableDefinition<'static, &'static [u8], &'static [u8]>;
 
@@ -26,6 +30,10 @@ pub struct EventMetadata {
     pub timestamp: u64,
     pub list: Option<String>,
     pub tag: Option<Vec<String>>,
+    #[serde(default)]
+    pub blocked_by: Vec<String>, // 阻塞当前事件的事件UUID
+    #[serde(default)]
+    pub external_id: Option<String>, // 导入来源中的唯一标识（如ICS的UID），重复导入时据此去重
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -41,11 +49,13 @@ impl EventMetadata {
             timestamp: Utc::now().timestamp_millis() as u64,
             tag: None,
             list: None,
+            blocked_by: Vec::new(),
+            external_id: None,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, TS, Clone)]
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
 pub enum Priority {
     Low,
     Medium,
@@ -63,6 +73,10 @@ pub struct Event {
     pub priority: Priority,
     pub icon: String,
     pub color: String,
+    #[serde(default)]
+    pub recurrence: Option<String>, // RFC 5545 RRULE，例如 "FREQ=MONTHLY;BYMONTHDAY=1"
+    #[serde(default)]
+    pub estimate: Option<u32>, // 预计耗时（分钟）
 }
 
 #[cfg(test)]
@@ -78,6 +92,8 @@ impl Event {
             priority: Priority::Undefined,
             icon: "default".to_string(),
             color: "default".to_string(),
+            recurrence: None,
+            estimate: None,
         }
     }
 }
@@ -95,6 +111,14 @@ pub struct FEvent {
     pub priority: Priority,
     pub icon: String,
     pub color: String,
+    #[serde(default)]
+    pub blocked_by: Vec<String>, // 只读，依赖关系通过 add_blocker/remove_blocker 修改
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    #[serde(default)]
+    pub estimate: Option<u32>,
+    #[serde(default)]
+    pub readonly: bool, // 来自订阅日历的事件不可编辑
 }
 
 impl From<Event> for FEvent {
@@ -110,12 +134,16 @@ impl From<Event> for FEvent {
                 Some(time) => time.to_string(),
             },
             tag: event.metadata.tag,
+            blocked_by: event.metadata.blocked_by,
             title: event.title,
             create: event.metadata.timestamp.to_string(),
             finished: event.finished,
             priority: event.priority,
             color: event.color,
             icon: event.icon,
+            recurrence: event.recurrence,
+            estimate: event.estimate,
+            readonly: false,
         }
     }
 }
@@ -130,6 +158,12 @@ impl Entity for Event {
     fn value(&self) -> Vec<u8> {
         serde_json::to_vec(self).unwrap_or_default() // the default value is empty
     }
+    fn kind() -> EntityKind {
+        EntityKind::Event
+    }
+    fn scope(&self) -> Option<String> {
+        self.metadata.list.clone()
+    }
 }
 
 /// 添加新事件
@@ -148,12 +182,26 @@ impl Entity for Event {
 /// * `Result<Event, ErrorKind>` - 成功时返回创建的事件对象，失败时返回错误
 #[tauri::command]
 pub async fn add_event(
-    app: AppHandle,
     state: State<'_, StorageState>,
     title: &str,
     listid: Option<&str>,
     priority: Priority,
     ddl: &str,
+) -> Result<Event, ErrorKind> {
+    let new_event = prepare_event(state.clone(), title, listid, priority, ddl).await?;
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    Repository::<Event>::add(storage, &new_event)?;
+
+    Ok(new_event)
+}
+
+/// 构建新事件并创建空的Markdown内容文件，不生成标签也不保存
+pub fn new_event(
+    title: &str,
+    listid: Option<&str>,
+    priority: Priority,
+    ddl: &str,
 ) -> Result<Event, ErrorKind> {
     let mut metadata = EventMetadata::new();
     metadata.list = listid.map(|id| id.to_string());
@@ -163,7 +211,7 @@ pub async fn add_event(
     }
     let content_path = content_dir.join(format!("{}.md", title));
     fs::write(&content_path, "")?;
-    let mut new_event = Event {
+    Ok(Event {
         metadata,
         title: title.to_string(),
         content: content_path.to_string_lossy().to_string(),
@@ -176,10 +224,25 @@ pub async fn add_event(
         priority,
         color: "default".to_string(),
         icon: "default".to_string(),
-    };
+        recurrence: None,
+        estimate: None,
+    })
+}
+
+/// 生成新事件但不保存，调用方补充其他字段后自行写入仓库
+///
+/// 与 `add_event` 相同，会创建Markdown内容文件并生成标签（如果启用）。
+pub async fn prepare_event(
+    state: State<'_, StorageState>,
+    title: &str,
+    listid: Option<&str>,
+    priority: Priority,
+    ddl: &str,
+) -> Result<Event, ErrorKind> {
+    let mut new_event = new_event(title, listid, priority, ddl)?;
 
     // Get tags from gen_tag
-    let tags = gen_tag(app.clone(), state.clone(), title).await?;
+    let tags = gen_tag(state.clone(), title).await?;
 
     // Check if each tag exists and add non-existent tags
     if let Some(tags_vec) = &tags {
@@ -198,23 +261,15 @@ pub async fn add_event(
 
     // Assign tags to new_event
     new_event.metadata.tag = tags;
-    let mut guard = state.0.lock().await;
-    let storage = guard.deref_mut();
-    Repository::<Event>::add(storage, &new_event)?;
-
-    // 使缓存失效
-    if let Some(list_id) = &new_event.metadata.list {
-        EVENT_LIST_CACHE.remove(list_id);
-    }
-
-    Ok(new_event.clone())
+    Ok(new_event)
 }
 
 /// 获取事件内容
 ///
 /// 根据事件UUID获取事件的Markdown内容。系统会首先从缓存中查找，
 /// 如果缓存未命中，则从数据库获取事件信息，再读取对应的内容文件。
-/// 获取后会更新缓存以提高后续访问速度。
+/// 获取后会更新缓存以提高后续访问速度。内容文件尚未同步到本机时返回空内容，
+/// 订阅日历中的事件返回其描述。
 ///
 /// # 参数
 /// * `state` - 应用状态，包含数据库访问权限
@@ -237,20 +292,29 @@ pub async fn event_content(
     let storage = guard.deref_mut();
     let event = Repository::<Event>::get_by_name(storage, uuid)?;
     if let Some(event) = event {
-        let content = fs::read_to_string(&event.content)?;
+        // 内容文件可能尚未从其他设备同步到本机，此时按空内容处理且不缓存
+        let content = match fs::read_to_string(&event.content) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(String::new()),
+            Err(e) => return Err(e.into()),
+        };
 
         // 更新缓存
         EVENT_CONTENT_CACHE.set(uuid, content.clone());
 
         return Ok(content);
     }
+    if let Some(item) = Repository::<OverlayItem>::get_by_name(storage, uuid)? {
+        return Ok(item.description);
+    }
     Ok("".to_string())
 }
 
 /// 写入事件内容
 ///
 /// 更新指定事件的Markdown内容。系统会先从数据库获取事件信息，
-/// 然后将新内容写入到对应的内容文件中，并更新缓存。
+/// 然后将新内容写入到对应的内容文件中，更新缓存并发布事件的更新通知。
+/// 订阅日历中的事件是只读的，写入时返回 `PermissionDenied`。
 ///
 /// # 参数
 /// * `state` - 应用状态，包含数据库访问权限
@@ -267,12 +331,26 @@ pub async fn write_content(
 ) -> Result<(), ErrorKind> {
     let mut guard = state.0.lock().await;
     let storage = guard.deref_mut();
+    if subscription::is_overlay(storage, uuid)? {
+        return Err(ErrorKind::PermissionDenied);
+    }
     let event = Repository::<Event>::get_by_name(storage, uuid)?;
     if let Some(event) = event {
+        if let Some(parent) = Path::new(&event.content).parent() {
+            fs::create_dir_all(parent)?;
+        }
         fs::write(&event.content, &content)?;
-
-        // 更新缓存
+        drop(guard);
+        bus::publish(&Change {
+            entity: EntityKind::Event,
+            kind: ChangeKind::Updated,
+            id: uuid.to_string(),
+            scopes: event.metadata.list.into_iter().collect(),
+        });
+
+        // 变更会清除旧的内容缓存，发布后再写入新内容
         EVENT_CONTENT_CACHE.set(uuid, content);
+        search::schedule_refresh(&state, uuid).await;
 
         return Ok(());
     }
@@ -283,24 +361,29 @@ pub async fn write_content(
 ///
 /// 根据前端提供的事件数据更新现有事件。系统会先从数据库获取旧事件，
 /// 然后用新数据更新各个字段，并保存到数据库中。同时会清除相关缓存。
+/// 阻塞关系不会通过此命令修改。如果本次更新将事件标记为完成，
+/// 会返回因此解除阻塞的事件。订阅日历中的事件是只读的，返回 `PermissionDenied`。
 ///
 /// # 参数
 /// * `state` - 应用状态，包含数据库访问权限
 /// * `f_event` - 前端事件对象，包含要更新的事件数据
 ///
 /// # 返回
-/// * `Result<(), ErrorKind>` - 成功时返回空元组，失败时返回错误
+/// * `Result<Vec<FEvent>, ErrorKind>` - 成功时返回被解除阻塞的事件，失败时返回错误
 #[tauri::command]
 pub async fn update_event(
     state: State<'_, StorageState>,
     f_event: FEvent,
-) -> Result<(), ErrorKind> {
+) -> Result<Vec<FEvent>, ErrorKind> {
     let mut guard = state.0.lock().await;
     let storage = guard.deref_mut();
+    if f_event.readonly || subscription::is_overlay(storage, &f_event.id)? {
+        return Err(ErrorKind::PermissionDenied);
+    }
     let old_event = Repository::<Event>::get_by_name(storage, &f_event.id)?;
     if let Some(mut new) = old_event {
-        // 保存旧列表ID用于缓存失效
-        let old_list_id = new.metadata.list.clone();
+        let just_finished = !new.finished && f_event.finished;
+        let title_changed = new.title != f_event.title;
 
         new.metadata.tag = f_event.tag;
         new.title = f_event.title;
@@ -309,16 +392,22 @@ pub async fn update_event(
         new.priority = f_event.priority;
         new.color = f_event.color;
         new.icon = f_event.icon;
+        new.recurrence = f_event.recurrence;
+        new.estimate = f_event.estimate;
         new.metadata.list = Some(f_event.listid.clone()); // 直接使用字符串类型的listid
         Repository::<Event>::add(storage, &new)?;
 
-        // 使缓存失效
-        if let Some(list_id) = old_list_id {
-            EVENT_LIST_CACHE.remove(&list_id);
+        // 完成阻塞者时返回被解除阻塞的事件
+        let unblocked = if just_finished {
+            dependency::unblocked_by(storage, &f_event.id)?
+        } else {
+            vec![]
+        };
+        drop(guard);
+        if title_changed {
+            search::schedule_refresh(&state, &f_event.id).await;
         }
-        EVENT_LIST_CACHE.remove(&f_event.listid);
-
-        return Ok(());
+        return Ok(unblocked.into_iter().map(FEvent::from).collect());
     }
     Err(ErrorKind::NotFound)
 }
@@ -326,7 +415,7 @@ pub async fn update_event(
 /// 删除事件
 ///
 /// 根据UUID删除指定事件。系统会先获取事件信息以确定其所属列表，
-/// 然后从数据库中删除事件，并清除相关缓存。
+/// 然后从数据库中删除事件，并清除相关缓存。订阅日历中的事件不能删除。
 ///
 /// # 参数
 /// * `state` - 应用状态，包含数据库访问权限
@@ -336,9 +425,17 @@ pub async fn update_event(
 /// * `Result<(), ErrorKind>` - 成功时返回空元组，失败时返回错误
 #[tauri::command]
 pub async fn delete_event(state: State<'_, StorageState>, uuid: &str) -> Result<(), ErrorKind> {
-    // 获取事件所属的列表ID
     let mut guard = state.0.lock().await;
     let storage = guard.deref_mut();
+    if subscription::is_overlay(storage, uuid)? {
+        return Err(ErrorKind::PermissionDenied);
+    }
+    remove_event(storage, uuid)?;
+    Ok(())
+}
+
+/// 删除事件及其Markdown文件、嵌入向量与阻塞关系
+pub fn remove_event(storage: &Storage, uuid: &str) -> Result<()> {
     let event = Repository::<Event>::get_by_name(storage, uuid)?;
 
     // 删除事件的Markdown文件
@@ -352,14 +449,8 @@ pub async fn delete_event(state: State<'_, StorageState>, uuid: &str) -> Result<
     }
     // 删除事件
     Repository::<Event>::delete(storage, uuid)?;
-
-    // 使缓存失效
-    EVENT_CONTENT_CACHE.remove(uuid);
-    if let Some(event) = event {
-        if let Some(list_id) = event.metadata.list {
-            EVENT_LIST_CACHE.remove(&list_id);
-        }
-    }
+    Repository::<Embedding>::delete(storage, uuid)?;
+    dependency::detach_blocker(storage, uuid)?;
 
     Ok(())
 }
@@ -368,6 +459,8 @@ pub async fn delete_event(state: State<'_, StorageState>, uuid: &str) -> Result<
 ///
 /// 根据提供的过滤条件字符串查询符合条件的事件。系统会解析过滤字符串为过滤枚举，
 /// 然后应用对应的过滤函数来筛选事件，最后将结果转换为前端事件对象返回。
+/// `actionable` 过滤器会隐藏已完成和仍被阻塞的事件。
+/// 按日期筛选时（如 `today`、`this_week`）还会附带订阅日历中的只读事件。
 ///
 /// # 参数
 /// * `state` - 应用状态，包含数据库访问权限
@@ -383,7 +476,7 @@ pub async fn filter_events(
 ) -> Result<Vec<FEvent>, ErrorKind> {
     let mut guard = state.0.lock().await;
     let storage = guard.deref_mut();
-    let filter_enum = match map_filter(filter, word_match) {
+    let filter_enum = match map_filter(storage, filter, word_match) {
         Ok(filter_enum) => filter_enum,
         Err(e) => {
             log::error!("Error parsing filter: {}", e);
@@ -397,13 +490,17 @@ pub async fn filter_events(
         }
     };
     let res = Repository::<Event>::filter(storage, filter_func);
-    match res {
-        Ok(events) => Ok(events
+    let mut events: Vec<FEvent> = match res {
+        Ok(events) => events
             .into_iter()
             .map(|event| FEvent::from(event))
-            .collect()),
+            .collect(),
         Err(e) => {
             return Err(ErrorKind::from(e));
         }
+    };
+    if is_date_filter(filter, word_match) {
+        events.extend(subscription::overlay_events(storage, filter_func)?);
     }
+    Ok(events)
 }
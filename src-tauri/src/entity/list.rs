@@ -8,6 +8,7 @@ use uuid::Uuid;
 
 use crate::entity::{Event, FEvent, Repository, StorageState};
 use crate::error::ErrorKind;
+use crate::utils::bus::EntityKind;
 use crate::utils::{list_exists, EVENT_LIST_CACHE, LIST_CACHE};
 
 use super::{Entity,event::delete_event};
@@ -41,6 +42,9 @@ impl Entity for List {
     fn value(&self) -> Vec<u8> {
         serde_json::to_vec(self).unwrap_or_default()
     }
+    fn kind() -> EntityKind {
+        EntityKind::List
+    }
 }
 
 impl List {
@@ -75,9 +79,6 @@ pub async fn new_list(
     let new_list = List::new(title, icon);
     Repository::<List>::add(storage, &new_list)?;
 
-    // 使列表缓存失效
-    LIST_CACHE.clear();
-
     // 转换为前端使用的 FList 格式
     let f_list = FList {
         id: new_list.uuid.clone(),
@@ -134,10 +135,6 @@ pub async fn delete_list(state: State<'_, StorageState>, listid: &str) -> Result
         Repository::<List>::delete(storage, listid)?;
     }
 
-    // 使相关缓存失效
-    LIST_CACHE.clear();
-    EVENT_LIST_CACHE.remove(listid);
-
     Ok(())
 }
 
@@ -279,8 +276,5 @@ pub async fn rename_list(
         Ok(())
     })?;
 
-    // 使列表缓存失效
-    LIST_CACHE.clear();
-
     Ok(())
 }
@@ -0,0 +1,257 @@
+use anyhow::Result;
+use redb::{self, TableDefinition};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::event::{EventMetadata, Priority};
+use super::{Entity, Event, FEvent, Repository, Storage};
+use crate::utils::bus::EntityKind;
+
+type Table = TableDefinition<'static, &'static [u8], &'static [u8]>;
+
+const SUBSCRIPTION_TABLE: Table = TableDefinition::new("subscriptions");
+const OVERLAY_TABLE: Table = TableDefinition::new("subscription_items");
+
+/// 只读的ICS订阅日历
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct Subscription {
+    pub uuid: String,
+    pub name: String,
+    pub source: String, // http(s)/webcal 地址或本地 .ics 文件路径
+    pub color: String,
+    pub refresh: u32,                // 刷新间隔（分钟）
+    pub last_refreshed: Option<u64>, // 上次成功刷新的时间，毫秒时间戳
+    #[serde(default)]
+    pub last_attempt: Option<u64>, // 上次尝试刷新的时间，无论成功与否
+    pub error: Option<String>,       // 上次刷新失败的原因
+}
+
+impl Subscription {
+    pub fn new(name: &str, source: &str, color: &str, refresh: u32) -> Self {
+        Self {
+            uuid: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            source: source.to_string(),
+            color: color.to_string(),
+            refresh,
+            last_refreshed: None,
+            last_attempt: None,
+            error: None,
+        }
+    }
+
+    /// 距上次尝试刷新已超过刷新间隔，刷新失败后同样等待一个间隔再重试
+    pub fn is_due(&self, now: u64) -> bool {
+        match self.last_attempt.max(self.last_refreshed) {
+            None => true,
+            Some(last) => now >= last + self.refresh.max(1) as u64 * 60 * 1000,
+        }
+    }
+}
+
+impl Entity for Subscription {
+    fn table_def() -> Table {
+        SUBSCRIPTION_TABLE
+    }
+    fn id_bytes(&self) -> Vec<u8> {
+        self.uuid.as_bytes().to_vec()
+    }
+    fn value(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+    fn kind() -> EntityKind {
+        EntityKind::Subscription
+    }
+}
+
+/// 订阅日历中的一个条目，与事件分表存放，刷新时整体替换
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct OverlayItem {
+    pub uuid: String, // 订阅ID与条目UID组合而成
+    pub subscription: String,
+    pub title: String,
+    pub description: String,
+    pub start: Option<u64>, // 毫秒时间戳
+    pub finished: bool,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub recurrence: Option<String>,
+}
+
+impl OverlayItem {
+    pub fn id(subscription: &str, uid: &str) -> String {
+        format!("{}:{}", subscription, uid)
+    }
+
+    /// 转换为事件，以便复用事件的日期筛选
+    fn as_event(&self) -> Event {
+        let mut metadata = EventMetadata::new();
+        metadata.uuid = self.uuid.clone();
+        metadata.list = Some(self.subscription.clone());
+        if !self.tags.is_empty() {
+            metadata.tag = Some(self.tags.clone());
+        }
+        Event {
+            metadata,
+            title: self.title.clone(),
+            content: self.description.clone(),
+            task_time: self.start,
+            finished: self.finished,
+            priority: self.priority.clone(),
+            icon: "default".to_string(),
+            color: "default".to_string(),
+            recurrence: self.recurrence.clone(),
+            estimate: None,
+        }
+    }
+
+    /// 转换为前端事件，所在列表为订阅ID，颜色沿用订阅的颜色
+    pub fn to_fevent(&self, color: &str) -> FEvent {
+        let mut event = FEvent::from(self.as_event());
+        event.color = color.to_string();
+        event.readonly = true;
+        event
+    }
+}
+
+impl Entity for OverlayItem {
+    fn table_def() -> Table {
+        OVERLAY_TABLE
+    }
+    fn id_bytes(&self) -> Vec<u8> {
+        self.uuid.as_bytes().to_vec()
+    }
+    fn value(&self) -> Vec<u8> {
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+    fn kind() -> EntityKind {
+        EntityKind::Subscription
+    }
+    fn scope(&self) -> Option<String> {
+        Some(self.subscription.clone())
+    }
+}
+
+/// 判断ID是否属于订阅日历中的条目
+pub fn is_overlay(storage: &Storage, uuid: &str) -> Result<bool> {
+    Ok(Repository::<OverlayItem>::get_by_name(storage, uuid)?.is_some())
+}
+
+/// 符合筛选条件的订阅条目，以只读的前端事件返回
+pub fn overlay_events(storage: &Storage, filter: impl Fn(&Event) -> bool) -> Result<Vec<FEvent>> {
+    let subscriptions = Repository::<Subscription>::get_all(storage)?;
+    let items = Repository::<OverlayItem>::filter(storage, |item| filter(&item.as_event()))?;
+    Ok(items
+        .into_iter()
+        .filter_map(|item| {
+            let subscription = subscriptions
+                .iter()
+                .find(|subscription| subscription.uuid == item.subscription)?;
+            Some(item.to_fevent(&subscription.color))
+        })
+        .collect())
+}
+
+/// 用新的条目替换订阅中的全部条目，内容未变的条目不重新写入
+pub fn replace_items(storage: &Storage, subscription: &str, items: &[OverlayItem]) -> Result<()> {
+    let existing =
+        Repository::<OverlayItem>::filter(storage, |item| item.subscription == subscription)?;
+    for old in &existing {
+        if !items.iter().any(|item| item.uuid == old.uuid) {
+            Repository::<OverlayItem>::delete(storage, &old.uuid)?;
+        }
+    }
+    for item in items {
+        if !existing.contains(item) {
+            Repository::<OverlayItem>::add(storage, item)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::temp_storage;
+
+    fn storage() -> (Storage, tempfile::TempDir) {
+        let (storage, dir) = temp_storage();
+        let txn = storage.db.begin_write().unwrap();
+        txn.open_table(SUBSCRIPTION_TABLE).unwrap();
+        txn.open_table(OVERLAY_TABLE).unwrap();
+        txn.commit().unwrap();
+        (storage, dir)
+    }
+
+    fn item(subscription: &str, uid: &str, start: Option<u64>) -> OverlayItem {
+        OverlayItem {
+            uuid: OverlayItem::id(subscription, uid),
+            subscription: subscription.to_string(),
+            title: uid.to_string(),
+            description: String::new(),
+            start,
+            finished: false,
+            priority: Priority::Undefined,
+            tags: Vec::new(),
+            recurrence: None,
+        }
+    }
+
+    #[test]
+    fn test_overlay_events() {
+        let (storage, _dir) = storage();
+        let holidays = Subscription::new("Holidays", "holidays.ics", "red", 60);
+        Repository::<Subscription>::add(&storage, &holidays).unwrap();
+        replace_items(
+            &storage,
+            &holidays.uuid,
+            &[
+                item(&holidays.uuid, "new-year", Some(1000)),
+                item(&holidays.uuid, "undated", None),
+            ],
+        )
+        .unwrap();
+
+        let events = overlay_events(&storage, |event| event.task_time == Some(1000)).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].title, "new-year");
+        assert_eq!(events[0].listid, holidays.uuid);
+        assert_eq!(events[0].color, "red");
+        assert!(events[0].readonly);
+        assert!(is_overlay(&storage, &events[0].id).unwrap());
+    }
+
+    #[test]
+    fn test_replace_items() {
+        let (storage, _dir) = storage();
+        replace_items(&storage, "a", &[item("a", "1", None), item("a", "2", None)]).unwrap();
+        replace_items(&storage, "b", &[item("b", "1", None)]).unwrap();
+        replace_items(&storage, "a", &[item("a", "2", Some(5))]).unwrap();
+
+        let mut ids: Vec<String> = Repository::<OverlayItem>::get_all(&storage)
+            .unwrap()
+            .into_iter()
+            .map(|item| format!("{}@{:?}", item.uuid, item.start))
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec!["a:2@Some(5)", "b:1@None"]);
+    }
+
+    #[test]
+    fn test_is_due() {
+        let mut subscription =
+            Subscription::new("Releases", "https://example.com/r.ics", "blue", 30);
+        assert!(subscription.is_due(0));
+        subscription.last_refreshed = Some(1_000);
+        assert!(!subscription.is_due(1_000 + 29 * 60 * 1000));
+        assert!(subscription.is_due(1_000 + 30 * 60 * 1000));
+
+        // 失败后不在每次检查时重试
+        subscription.last_attempt = Some(40 * 60 * 1000);
+        subscription.error = Some("timeout".to_string());
+        assert!(!subscription.is_due(41 * 60 * 1000));
+        assert!(subscription.is_due(70 * 60 * 1000));
+    }
+}
@@ -7,6 +7,7 @@ use ts_rs::TS;
 
 use crate::entity::{Repository, StorageState};
 use crate::error::ErrorKind;
+use crate::utils::bus::EntityKind;
 use crate::utils::tag_exists;
 
 use super::Entity;
@@ -44,6 +45,9 @@ impl Entity for Tag {
     fn value(&self) -> Vec<u8> {
         serde_json::to_vec(self).unwrap_or_default()
     }
+    fn kind() -> EntityKind {
+        EntityKind::Tag
+    }
 }
 
 impl Tag {
@@ -1,6 +1,8 @@
+mod of_dependency;
 mod of_event;
 
-pub use of_event::map_filter;
+pub use of_dependency::actionable_filter;
+pub use of_event::{is_date_filter, map_filter};
 
 type SimpleFilter<T> = fn(&T) -> bool;
 type ClosureFilter<T> = Box<dyn Fn(&T) -> bool>;
@@ -0,0 +1,50 @@
+use std::collections::HashSet;
+
+use super::Filter::{self, B};
+use crate::entity::dependency::is_blocked;
+use crate::entity::Event;
+
+/// 可执行事件过滤器：隐藏已完成和仍被阻塞的事件
+///
+/// `unfinished` 为构造过滤器时所有未完成事件的UUID集合，
+/// 阻塞状态由阻塞者是否完成计算得出。
+pub fn actionable_filter(unfinished: HashSet<String>) -> Filter<Event> {
+    B(Box::new(move |event| {
+        !event.finished && !is_blocked(event, &unfinished)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(filter: &Filter<Event>, event: &Event) -> bool {
+        match filter {
+            Filter::A(f) => f(event),
+            Filter::B(f) => f(event),
+        }
+    }
+
+    #[test]
+    fn test_actionable_filter() {
+        let blocker = Event::new("Blocker", "");
+        let mut blocked = Event::new("Blocked", "");
+        blocked.metadata.blocked_by = vec![blocker.metadata.uuid.clone()];
+        let mut done = Event::new("Done", "");
+        done.finished = true;
+
+        let unfinished: HashSet<String> = [&blocker, &blocked]
+            .iter()
+            .map(|e| e.metadata.uuid.clone())
+            .collect();
+        let filter = actionable_filter(unfinished);
+        assert!(matches(&filter, &blocker));
+        assert!(!matches(&filter, &blocked), "被阻塞的事件不可执行");
+        assert!(!matches(&filter, &done), "已完成的事件不可执行");
+
+        // 阻塞者完成后，被阻塞事件变为可执行
+        let unfinished: HashSet<String> = [blocked.metadata.uuid.clone()].into_iter().collect();
+        let filter = actionable_filter(unfinished);
+        assert!(matches(&filter, &blocked));
+    }
+}
@@ -2,14 +2,24 @@ use chrono::{self, DateTime, NaiveDate};
 
 use anyhow::{Ok, Result};
 
+use super::actionable_filter;
 use super::Filter::{self, A, B};
-use crate::entity::Event;
+use crate::entity::dependency::unfinished_ids;
+use crate::entity::event::Priority;
+use crate::entity::{Event, Storage};
 use crate::utils::target_date_timestamp as timestamp;
 
 const ONE_DAY: u64 = 24 * 60 * 60 * 1000;
 const ONE_WEEK: u64 = 7 * ONE_DAY;
 
-pub fn map_filter(filter: &str, word_match: Option<bool>) -> Result<Filter<Event>> {
+/// 将过滤条件字符串解析为过滤器
+///
+/// `actionable` 需要读取存储中所有未完成事件来计算阻塞状态，其余条件只依赖事件本身。
+pub fn map_filter(
+    storage: &Storage,
+    filter: &str,
+    word_match: Option<bool>,
+) -> Result<Filter<Event>> {
     if let Some(_) = word_match {
         let filter = filter.to_string();
         return Ok(B(Box::new(move |event| {
@@ -23,6 +33,8 @@ pub fn map_filter(filter: &str, word_match: Option<bool>) -> Result<Filter<Event
         "tomorrow" => Ok(A(|event| tomorrow_filter(event))),
         "this_week" => Ok(A(|event| this_week_filter(event))),
         "next_week" => Ok(A(|event| next_week_filter(event))),
+        "high_priority" => Ok(A(|event| high_priority_filter(event))),
+        "actionable" => Ok(actionable_filter(unfinished_ids(storage)?)),
         date_str => {
             let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
                 .map_err(|_| anyhow::anyhow!("Invalid date filter"))?;
@@ -32,6 +44,13 @@ pub fn map_filter(filter: &str, word_match: Option<bool>) -> Result<Filter<Event
     }
 }
 
+/// 是否为按日期筛选的过滤条件，订阅日历的条目只出现在这类筛选结果中
+pub fn is_date_filter(filter: &str, word_match: Option<bool>) -> bool {
+    word_match.is_none()
+        && (matches!(filter, "today" | "tomorrow" | "this_week" | "next_week")
+            || NaiveDate::parse_from_str(filter, "%Y-%m-%d").is_ok())
+}
+
 fn word_match_filter(entity: &Event, word: &str) -> bool {
     let title: &str = entity.title.as_ref();
     let content: &str = entity.content.as_ref();
@@ -67,6 +86,10 @@ fn overdue_filter(entity: &Event) -> bool {
     entity.task_time.unwrap_or(0) < timestamp && !entity.finished
 }
 
+fn high_priority_filter(entity: &Event) -> bool {
+    matches!(entity.priority, Priority::High) && !entity.finished
+}
+
 fn time_filter(day: NaiveDate, event: &Event) -> bool {
     let event_time = event.task_time;
     match event_time {
@@ -214,4 +237,25 @@ mod tests {
             "Early tomorrow event should match tomorrow's date"
         );
     }
+
+    #[test]
+    fn test_high_priority_filter() {
+        let mut event = Event::new("Test Event", "This is a test event.");
+        assert!(!high_priority_filter(&event));
+
+        event.priority = Priority::High;
+        assert!(high_priority_filter(&event));
+
+        event.finished = true;
+        assert!(!high_priority_filter(&event), "已完成的事件不应匹配");
+    }
+
+    #[test]
+    fn test_is_date_filter() {
+        assert!(is_date_filter("today", None));
+        assert!(is_date_filter("2025-05-01", None));
+        assert!(!is_date_filter("today", Some(true)));
+        assert!(!is_date_filter("overdue", None));
+        assert!(!is_date_filter("high_priority", None));
+    }
 }
@@ -1,6 +1,13 @@
 pub mod aigc;
+pub mod backup;
+pub mod caldav;
+pub mod convert;
 pub mod export;
+pub mod import;
 pub mod notify;
+pub mod quick_add;
+pub mod subscribe;
+pub mod suggest;
 pub mod sync;
 pub mod upload;
 
@@ -1,57 +1,136 @@
+pub mod assist;
+mod client;
+pub mod planner;
+pub mod search;
+
 use anyhow::Result;
-use log::{error};
-use std::sync::Mutex;
-use tauri::{AppHandle, State, Emitter};
-use once_cell::sync::Lazy;
+use log::error;
+use serde::Deserialize;
+use std::time::Duration;
+use tauri::State;
+
+pub use client::{ChatClient, ChatMessage};
 
 use crate::entity::{get_tags, StorageState};
+use crate::error::ErrorKind;
+use crate::function::suggest;
 use crate::utils::config::Model;
 
-// 用于存储生成的标签的全局变量
-static GENERATED_TAGS: Lazy<Mutex<Option<Vec<String>>>> = Lazy::new(|| Mutex::new(None));
-
-#[tauri::command]
-pub async fn gen_tag(
-    app: AppHandle,
-    state: State<'_, StorageState>,
-    title: &str,
-) -> Result<Option<Vec<String>>> {
-    let llm_config = Model::load().unwrap_or_default();
-    let use_llm = llm_config.switch;
-    if !use_llm {
-        return Ok(None);
-    }
+/// 生成标签的请求超时，超时后不重试
+const TAG_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct TagReply {
+    tags: Vec<String>,
+}
+
+/// 读取模型配置，未启用时返回 None
+pub fn enabled_model() -> Option<Model> {
+    Model::load().ok().filter(|model| model.switch)
+}
+
+/// 为命令创建大模型客户端，未启用时返回 `PermissionDenied`
+pub(crate) fn llm_client() -> Result<ChatClient, ErrorKind> {
+    let model = enabled_model().ok_or(ErrorKind::PermissionDenied)?;
+    ChatClient::new(&model).map_err(|e| {
+        error!("Failed to create LLM client: {:?}", e);
+        ErrorKind::InitError
+    })
+}
+
+/// 将大模型调用失败转换为命令错误
+pub(crate) fn llm_error(e: anyhow::Error) -> ErrorKind {
+    error!("LLM request failed: {:?}", e);
+    ErrorKind::InternetError
+}
 
-    // 获取所有可用标签
-    let available_tags = get_tags(state)
-        .await?
-        .iter()
-        .map(|tag| tag.name.clone())
-        .collect::<Vec<String>>();
-
-    // 调用前端的 AigcService.generateTags 方法
-    // 清空之前的标签
-    *GENERATED_TAGS.lock().unwrap() = None;
-
-    // 发送事件到前端
-    app.emit("aigc:generate-tags", (title, available_tags))?;
-
-    // 等待前端返回标签(最多等待10秒)
-    let start_time = std::time::Instant::now();
-    while start_time.elapsed() < std::time::Duration::from_secs(10) {
-        if let Some(tags) = GENERATED_TAGS.lock().unwrap().take() {
-            return Ok(Some(tags));
+fn tag_prompt(title: &str, available_tags: &[String]) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage::system("你是一个专业的内容分类助手，善于为内容选择合适的标签。"),
+        ChatMessage::user(format!(
+            r#"为以下内容选择合适的标签。请遵循以下规则：
+
+1. 首先尝试从给出的标签列表中选择最相关的标签
+2. 如果现有标签不足以准确描述内容的关键主题，可以创建新标签
+3. 新标签应简洁（1-3个汉字，或者一个英文单词），具体且描述性强
+4. 总共选择1-3个标签，优先使用现有标签
+5. 标签应反映内容的核心主题、领域或性质，而非次要细节
+
+可选标签列表:
+{}
+
+需要打标签的内容:
+{}
+
+请以 JSON 对象返回，格式为 {{"tags": ["标签1", "标签2"]}}。"#,
+            available_tags.join(","),
+            title
+        )),
+    ]
+}
+
+/// 为事件标题生成标签
+///
+/// 启用大模型时调用配置的 OpenAI 兼容接口（`TAG_TIMEOUT` 后超时且不重试），
+/// 请求失败或未启用时使用本地建议器。
+pub async fn gen_tag(state: State<'_, StorageState>, title: &str) -> Result<Option<Vec<String>>> {
+    if let Some(model) = enabled_model() {
+        // 获取所有可用标签
+        let available_tags = get_tags(state.clone())
+            .await?
+            .iter()
+            .map(|tag| tag.name.clone())
+            .collect::<Vec<String>>();
+
+        // 创建事件时同步等待标签，接口不可用时应尽快回退到本地建议器
+        let reply = match ChatClient::with_timeout(&model, TAG_TIMEOUT) {
+            Ok(client) => {
+                client
+                    .retries(0, Duration::ZERO)
+                    .chat_json::<TagReply>(&tag_prompt(title, &available_tags))
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match reply {
+            Ok(reply) => {
+                let tags: Vec<String> = reply
+                    .tags
+                    .into_iter()
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+                return Ok((!tags.is_empty()).then_some(tags));
+            }
+            Err(e) => error!("Failed to generate tags with LLM: {:?}", e),
         }
-        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
     }
 
-    error!("Timeout waiting for tags from frontend");
-    Ok(None)
+    // 未启用大模型或调用失败时使用本地建议器
+    let tags = suggest::suggest_for_title(&state, title).await?;
+    Ok((!tags.is_empty()).then_some(tags))
 }
 
-/// 接收前端生成的标签
-#[tauri::command]
-pub async fn receive_generated_tags(tags: Vec<String>) -> tauri::Result<()> {
-    *GENERATED_TAGS.lock().unwrap() = Some(tags);
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use client::mock;
+
+    #[tokio::test]
+    async fn test_tag_prompt_round_trip() {
+        let (api, mut requests) =
+            mock::serve(vec![(200, mock::completion(r#"{"tags":["finance"]}"#))]).await;
+        let client = ChatClient::new(&mock::model(&api)).unwrap();
+        let available = vec!["finance".to_string(), "work".to_string()];
+
+        let reply: TagReply = client
+            .chat_json(&tag_prompt("Pay rent", &available))
+            .await
+            .unwrap();
+        assert_eq!(reply.tags, vec!["finance"]);
+
+        let body = requests.recv().await.unwrap();
+        assert!(body.contains("Pay rent"));
+        assert!(body.contains("finance,work"));
+    }
 }
@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::DerefMut;
+use tauri::State;
+use ts_rs::TS;
+
+use super::{llm_client, llm_error, ChatMessage};
+use crate::entity::event::{event_content, new_event, write_content};
+use crate::entity::{Event, FEvent, Repository, Storage, StorageState};
+use crate::error::ErrorKind;
+
+const SUMMARY_PREFIX: &str = "> AI总结：";
+
+/// AI 拆分出的子任务建议
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct SubtaskProposal {
+    pub title: String,
+    /// 预计耗时（分钟）
+    pub estimate: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct BreakdownReply {
+    subtasks: Vec<SubtaskProposal>,
+}
+
+#[derive(Deserialize)]
+struct SummaryReply {
+    summary: String,
+}
+
+fn breakdown_prompt(title: &str, content: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage::system("你是一个专业的任务规划助手，善于把复杂任务拆分为可执行的步骤。"),
+        ChatMessage::user(format!(
+            r#"请把以下待办事项拆分为 2-8 个具体、可独立完成的子任务，并估计每个子任务需要的分钟数。
+
+标题:
+{}
+
+内容:
+{}
+
+请以 JSON 对象返回，格式为 {{"subtasks": [{{"title": "子任务", "estimate": 30}}]}}。"#,
+            title, content
+        )),
+    ]
+}
+
+fn summary_prompt(content: &str) -> Vec<ChatMessage> {
+    vec![
+        ChatMessage::system("你是一个专业的内容总结助手，善于提取关键信息并生成简洁的总结。"),
+        ChatMessage::user(format!(
+            r#"请对以下内容进行简洁的总结，提取最重要的信息点，总结不超过50个字：
+
+{}
+
+请以 JSON 对象返回，格式为 {{"summary": "总结"}}。"#,
+            content
+        )),
+    ]
+}
+
+/// 在内容开头插入总结，已有的 AI 总结会被替换
+fn with_summary(content: &str, summary: &str) -> String {
+    let body = match content.strip_prefix(SUMMARY_PREFIX) {
+        Some(rest) => rest
+            .split_once('\n')
+            .map(|(_, body)| body.trim_start_matches('\n'))
+            .unwrap_or(""),
+        None => content,
+    };
+    let summary = summary.trim().replace('\n', " ");
+    if body.is_empty() {
+        format!("{}{}\n", SUMMARY_PREFIX, summary)
+    } else {
+        format!("{}{}\n\n{}", SUMMARY_PREFIX, summary, body)
+    }
+}
+
+/// Proposes subtasks for an event
+///
+/// Sends the event title and content to the configured model and returns the
+/// suggested subtasks with estimated effort. Nothing is created until the
+/// proposal is passed to `accept_subtasks`.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Unique identifier of the event to break down
+///
+/// # Returns
+/// * `Result<Vec<SubtaskProposal>, ErrorKind>` - The proposed subtasks, or `PermissionDenied`
+///   when the model is disabled
+#[tauri::command]
+pub async fn breakdown_event(
+    state: State<'_, StorageState>,
+    uuid: &str,
+) -> Result<Vec<SubtaskProposal>, ErrorKind> {
+    let client = llm_client()?;
+    let title = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        Repository::<Event>::get_by_name(storage, uuid)?
+            .ok_or(ErrorKind::NotFound)?
+            .title
+    };
+    let content = event_content(state, uuid).await?;
+
+    let reply: BreakdownReply = client
+        .chat_json(&breakdown_prompt(&title, &content))
+        .await
+        .map_err(llm_error)?;
+    Ok(reply
+        .subtasks
+        .into_iter()
+        .filter(|subtask| !subtask.title.trim().is_empty())
+        .collect())
+}
+
+/// Creates accepted subtasks
+///
+/// Each subtask is created like in `add_event` in the parent's list with the
+/// parent's priority and tags, so no tags are generated per subtask. The
+/// subtasks are stored together with their estimates and added as blockers of
+/// the parent, keeping the parent blocked until all of them are finished. If
+/// any step fails, the subtasks already stored are removed again.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Unique identifier of the parent event
+/// * `subtasks` - The accepted (and possibly edited) subtasks
+///
+/// # Returns
+/// * `Result<Vec<FEvent>, ErrorKind>` - The created subtasks
+#[tauri::command]
+pub async fn accept_subtasks(
+    state: State<'_, StorageState>,
+    uuid: &str,
+    subtasks: Vec<SubtaskProposal>,
+) -> Result<Vec<FEvent>, ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let parent = Repository::<Event>::get_by_name(storage, uuid)?.ok_or(ErrorKind::NotFound)?;
+
+    let mut events = Vec::new();
+    for subtask in subtasks {
+        let mut event = new_event(
+            subtask.title.trim(),
+            parent.metadata.list.as_deref(),
+            parent.priority.clone(),
+            "",
+        )?;
+        event.metadata.tag = parent.metadata.tag.clone();
+        event.estimate = subtask.estimate;
+        events.push(event);
+    }
+
+    store_subtasks(storage, uuid, &events)?;
+    Ok(events.into_iter().map(FEvent::from).collect())
+}
+
+/// 保存子任务并将其加入父事件的阻塞列表，失败时删除已保存的子任务及其内容文件
+///
+/// 新事件没有阻塞关系，加入父事件的阻塞列表不会形成环。
+fn store_subtasks(storage: &Storage, parent: &str, subtasks: &[Event]) -> anyhow::Result<()> {
+    let stored = subtasks
+        .iter()
+        .try_for_each(|event| Repository::<Event>::add(storage, event))
+        .and_then(|_| {
+            Repository::<Event>::update(storage, parent, |event: &mut Event| {
+                event
+                    .metadata
+                    .blocked_by
+                    .extend(subtasks.iter().map(|subtask| subtask.metadata.uuid.clone()));
+                Ok(())
+            })
+        });
+    if stored.is_err() {
+        for event in subtasks {
+            if Repository::<Event>::get_by_name(storage, &event.metadata.uuid)?.is_some() {
+                Repository::<Event>::delete(storage, &event.metadata.uuid)?;
+            }
+            let _ = fs::remove_file(&event.content);
+        }
+    }
+    stored
+}
+
+/// Summarizes an event's markdown content
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Unique identifier of the event
+///
+/// # Returns
+/// * `Result<String, ErrorKind>` - A short summary, or `PermissionDenied` when the model is disabled
+#[tauri::command]
+pub async fn summarize_content(
+    state: State<'_, StorageState>,
+    uuid: &str,
+) -> Result<String, ErrorKind> {
+    let client = llm_client()?;
+    let content = event_content(state, uuid).await?;
+    if content.trim().is_empty() {
+        return Ok(String::new());
+    }
+
+    let reply: SummaryReply = client
+        .chat_json(&summary_prompt(&content))
+        .await
+        .map_err(llm_error)?;
+    Ok(reply.summary.trim().to_string())
+}
+
+/// Inserts an accepted summary at the top of an event's content
+///
+/// Replaces a previously inserted summary and writes through `write_content`.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Unique identifier of the event
+/// * `summary` - The accepted summary
+///
+/// # Returns
+/// * `Result<String, ErrorKind>` - The new content
+#[tauri::command]
+pub async fn apply_summary(
+    state: State<'_, StorageState>,
+    uuid: &str,
+    summary: String,
+) -> Result<String, ErrorKind> {
+    let content = event_content(state.clone(), uuid).await?;
+    let updated = with_summary(&content, &summary);
+    write_content(state, uuid, updated.clone()).await?;
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::aigc::client::mock;
+    use crate::function::aigc::ChatClient;
+    use crate::test::temp_storage;
+
+    #[test]
+    fn test_with_summary() {
+        assert_eq!(with_summary("", "短"), "> AI总结：短\n");
+        assert_eq!(
+            with_summary("# Note\nbody", "总结"),
+            "> AI总结：总结\n\n# Note\nbody"
+        );
+        // 再次总结会替换旧的总结
+        assert_eq!(
+            with_summary("> AI总结：旧\n\n# Note\nbody", "新"),
+            "> AI总结：新\n\n# Note\nbody"
+        );
+    }
+
+    #[test]
+    fn test_store_subtasks() {
+        let (storage, dir) = temp_storage();
+        let parent = Event::new("Taxes", "");
+        Repository::<Event>::add(&storage, &parent).unwrap();
+        let content = dir.path().join("Collect receipts.md");
+        fs::write(&content, "").unwrap();
+        let subtasks = vec![
+            Event::new("Collect receipts", content.to_str().unwrap()),
+            Event::new("File form", ""),
+        ];
+
+        // 父事件不存在时不留下任何子任务
+        assert!(store_subtasks(&storage, "missing", &subtasks).is_err());
+        assert_eq!(Repository::<Event>::get_all(&storage).unwrap().len(), 1);
+        assert!(!content.exists());
+
+        store_subtasks(&storage, &parent.metadata.uuid, &subtasks).unwrap();
+        let parent: Event = Repository::get_by_name(&storage, &parent.metadata.uuid)
+            .unwrap()
+            .unwrap();
+        let ids: Vec<&str> = subtasks.iter().map(|e| e.metadata.uuid.as_str()).collect();
+        assert_eq!(parent.metadata.blocked_by, ids);
+        assert_eq!(Repository::<Event>::get_all(&storage).unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_breakdown_reply() {
+        let reply =
+            r#"{"subtasks":[{"title":"Collect receipts","estimate":20},{"title":"File form"}]}"#;
+        let (api, _requests) = mock::serve(vec![(200, mock::completion(reply))]).await;
+        let client = ChatClient::new(&mock::model(&api)).unwrap();
+
+        let reply: BreakdownReply = client
+            .chat_json(&breakdown_prompt("Taxes", ""))
+            .await
+            .unwrap();
+        assert_eq!(
+            reply.subtasks,
+            vec![
+                SubtaskProposal {
+                    title: "Collect receipts".to_string(),
+                    estimate: Some(20),
+                },
+                SubtaskProposal {
+                    title: "File form".to_string(),
+                    estimate: None,
+                },
+            ]
+        );
+    }
+}
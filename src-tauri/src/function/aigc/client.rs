@@ -0,0 +1,478 @@
+use anyhow::{anyhow, bail, Context, Result};
+use log::warn;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::utils::config::Model;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// 对话消息
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn system(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "system".to_string(),
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        ChatMessage {
+            role: "user".to_string(),
+            content: content.into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+}
+
+#[derive(Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: ResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ResponseMessage {
+    content: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// OpenAI 兼容接口的客户端，提供对话补全和文本向量
+///
+/// 使用配置中的 `api`（基础地址）、`name`（模型名）、`embedding`（向量模型名）
+/// 和 `tokens`（API Key）。网络错误、超时、429 和 5xx 响应会按指数退避重试。
+pub struct ChatClient {
+    http: reqwest::Client,
+    base_url: String,
+    model: String,
+    embedding_model: String,
+    api_key: String,
+    max_retries: u32,
+    retry_delay: Duration,
+}
+
+impl ChatClient {
+    pub fn new(config: &Model) -> Result<Self> {
+        Self::with_timeout(config, REQUEST_TIMEOUT)
+    }
+
+    pub fn with_timeout(config: &Model, timeout: Duration) -> Result<Self> {
+        if config.api.trim().is_empty() {
+            bail!("Model api is not configured");
+        }
+        let embedding_model = if config.embedding.is_empty() {
+            Model::default().embedding
+        } else {
+            config.embedding.clone()
+        };
+        let http = reqwest::Client::builder().timeout(timeout).build()?;
+        Ok(ChatClient {
+            http,
+            base_url: base_url(&config.api),
+            model: config.name.clone(),
+            embedding_model,
+            api_key: config.tokens.clone(),
+            max_retries: MAX_RETRIES,
+            retry_delay: RETRY_DELAY,
+        })
+    }
+
+    /// 设置重试次数和首次重试的等待时间
+    pub fn retries(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// 向量模型名称，用于判断已存储的向量是否过期
+    pub fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+
+    /// 发送对话并返回回复文本
+    pub async fn chat(&self, messages: &[ChatMessage]) -> Result<String> {
+        self.complete(messages, None).await
+    }
+
+    /// 要求模型以 JSON 对象回复，并解析为 `T`
+    pub async fn chat_json<T: DeserializeOwned>(&self, messages: &[ChatMessage]) -> Result<T> {
+        let format = ResponseFormat {
+            kind: "json_object",
+        };
+        let reply = self.complete(messages, Some(format)).await?;
+        parse_json_reply(&reply)
+    }
+
+    /// 计算文本向量，返回顺序与输入一致
+    pub async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = EmbeddingRequest {
+            model: &self.embedding_model,
+            input: inputs,
+        };
+        let mut response: EmbeddingResponse = self.post("embeddings", &request).await?;
+        if response.data.len() != inputs.len() {
+            bail!(
+                "Expected {} embeddings, got {}",
+                inputs.len(),
+                response.data.len()
+            );
+        }
+        response.data.sort_by_key(|data| data.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect())
+    }
+
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        response_format: Option<ResponseFormat>,
+    ) -> Result<String> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages,
+            response_format,
+        };
+        let response: ChatResponse = self.post("chat/completions", &request).await?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.message.content)
+            .ok_or_else(|| anyhow!("LLM response has no content"))
+    }
+
+    async fn post<B: Serialize, R: DeserializeOwned>(&self, path: &str, body: &B) -> Result<R> {
+        let url = format!("{}/{}", self.base_url, path);
+        let mut attempt = 0;
+        loop {
+            match self.send(&url, body).await {
+                Ok(response) => return Ok(response),
+                Err(Failure::Fatal(e)) => return Err(e),
+                Err(Failure::Retryable(e)) if attempt >= self.max_retries => return Err(e),
+                Err(Failure::Retryable(e)) => {
+                    let delay = self.retry_delay * 2u32.pow(attempt);
+                    warn!("LLM request failed ({}), retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    async fn send<B: Serialize, R: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &B,
+    ) -> Result<R, Failure> {
+        let response = self
+            .http
+            .post(url)
+            .bearer_auth(&self.api_key)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| Failure::Retryable(anyhow!(e).context("LLM request failed")))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            let error = anyhow!("LLM request returned {}: {}", status, body);
+            let retryable = status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+            return Err(if retryable {
+                Failure::Retryable(error)
+            } else {
+                Failure::Fatal(error)
+            });
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| Failure::Fatal(anyhow!(e).context("Malformed LLM response")))
+    }
+}
+
+/// 接口基础地址，兼容填写了完整补全地址（`.../chat/completions`）的配置
+fn base_url(api: &str) -> String {
+    let api = api.trim().trim_end_matches('/');
+    api.strip_suffix("/chat/completions")
+        .unwrap_or(api)
+        .to_string()
+}
+
+enum Failure {
+    Retryable(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+/// 从模型回复中解析 JSON
+///
+/// 兼容回复被 Markdown 代码块包裹或前后带有说明文字的情况。
+pub fn parse_json_reply<T: DeserializeOwned>(reply: &str) -> Result<T> {
+    let trimmed = reply.trim();
+    if let Ok(value) = serde_json::from_str(trimmed) {
+        return Ok(value);
+    }
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => serde_json::from_str(&trimmed[start..=end])
+            .with_context(|| format!("Invalid JSON in LLM reply: {}", reply)),
+        _ => bail!("No JSON found in LLM reply: {}", reply),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod mock {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    /// 本地模拟的 HTTP 服务器，按顺序返回预设的 (状态码, 响应体)
+    ///
+    /// 返回服务器基础地址和接收请求体的通道。
+    pub async fn serve(responses: Vec<(u16, String)>) -> (String, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let request = read_request(&mut socket).await;
+                let _ = tx.send(request);
+                let response = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        (format!("http://{}", addr), rx)
+    }
+
+    async fn read_request(socket: &mut tokio::net::TcpStream) -> String {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        loop {
+            let n = socket.read(&mut chunk).await.unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+            let text = String::from_utf8_lossy(&buf);
+            if let Some(split) = text.find("\r\n\r\n") {
+                let length = text[..split]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if buf.len() >= split + 4 + length {
+                    return String::from_utf8_lossy(&buf[split + 4..]).to_string();
+                }
+            }
+        }
+        String::new()
+    }
+
+    /// 指向模拟服务器的模型配置
+    pub fn model(api: &str) -> crate::utils::config::Model {
+        crate::utils::config::Model {
+            switch: true,
+            name: "test-model".to_string(),
+            tokens: "secret".to_string(),
+            api: api.to_string(),
+            embedding: "test-embedding".to_string(),
+        }
+    }
+
+    /// 构造一个只包含单条回复的补全响应
+    pub fn completion(content: &str) -> String {
+        serde_json::json!({
+            "choices": [{ "index": 0, "message": { "role": "assistant", "content": content } }]
+        })
+        .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Tags {
+        tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_parse_json_reply() {
+        let plain: Tags = parse_json_reply(r#"{"tags":["work"]}"#).unwrap();
+        assert_eq!(plain.tags, vec!["work"]);
+
+        let fenced: Tags = parse_json_reply("```json\n{\"tags\": [\"a\", \"b\"]}\n```").unwrap();
+        assert_eq!(fenced.tags, vec!["a", "b"]);
+
+        assert!(parse_json_reply::<Tags>("no json here").is_err());
+    }
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(
+            base_url("https://api.openai.com/v1/"),
+            "https://api.openai.com/v1"
+        );
+        assert_eq!(
+            base_url("https://api.openai.com/v1/chat/completions"),
+            "https://api.openai.com/v1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_chat_json() {
+        let (api, mut requests) = mock::serve(vec![(
+            200,
+            mock::completion(r#"{"tags":["work","report"]}"#),
+        )])
+        .await;
+        let client = ChatClient::new(&mock::model(&format!("{}/", api))).unwrap();
+
+        let tags: Tags = client
+            .chat_json(&[ChatMessage::user("Write report")])
+            .await
+            .unwrap();
+        assert_eq!(tags.tags, vec!["work", "report"]);
+
+        let body: Value = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["response_format"]["type"], "json_object");
+        assert_eq!(body["messages"][0]["content"], "Write report");
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() {
+        let (api, _requests) = mock::serve(vec![
+            (503, "{}".to_string()),
+            (429, "{}".to_string()),
+            (200, mock::completion("done")),
+        ])
+        .await;
+        let client = ChatClient::new(&mock::model(&api))
+            .unwrap()
+            .retries(2, Duration::from_millis(1));
+
+        assert_eq!(
+            client.chat(&[ChatMessage::user("hi")]).await.unwrap(),
+            "done"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let (api, _requests) = mock::serve(vec![
+            (401, r#"{"error":"bad key"}"#.to_string()),
+            (200, mock::completion("unreachable")),
+        ])
+        .await;
+        let client = ChatClient::new(&mock::model(&api))
+            .unwrap()
+            .retries(2, Duration::from_millis(1));
+
+        let error = client.chat(&[ChatMessage::user("hi")]).await.unwrap_err();
+        assert!(error.to_string().contains("401"));
+    }
+
+    #[tokio::test]
+    async fn test_embed() {
+        let response = serde_json::json!({
+            "data": [
+                { "index": 1, "embedding": [0.0, 1.0] },
+                { "index": 0, "embedding": [1.0, 0.0] }
+            ]
+        });
+        let (api, mut requests) = mock::serve(vec![(200, response.to_string())]).await;
+        let client = ChatClient::new(&mock::model(&api)).unwrap();
+
+        let inputs = vec!["first".to_string(), "second".to_string()];
+        let vectors = client.embed(&inputs).await.unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let body: Value = serde_json::from_str(&requests.recv().await.unwrap()).unwrap();
+        assert_eq!(body["model"], "test-embedding");
+        assert_eq!(body["input"][1], "second");
+    }
+
+    #[tokio::test]
+    async fn test_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api = format!("http://{}", listener.local_addr().unwrap());
+        // 接受连接但从不回复
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+        let client = ChatClient::with_timeout(&mock::model(&api), Duration::from_millis(100))
+            .unwrap()
+            .retries(1, Duration::from_millis(1));
+
+        assert!(client.chat(&[ChatMessage::user("hi")]).await.is_err());
+    }
+}
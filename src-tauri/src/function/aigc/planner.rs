@@ -0,0 +1,466 @@
+use anyhow::Result;
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::DerefMut;
+use tauri::State;
+use ts_rs::TS;
+
+use super::{llm_client, llm_error, ChatMessage};
+use crate::entity::event::Priority;
+use crate::entity::subscription;
+use crate::entity::{Event, FEvent, Repository, Storage, StorageState};
+use crate::error::ErrorKind;
+use crate::filter::{map_filter, Filter};
+
+const DEFAULT_SLOT_MINUTES: u32 = 30;
+
+/// 计划中对单个事件的处理方式
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub enum PlanAction {
+    Schedule,
+    Defer,
+}
+
+/// 计划中的一项
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export)]
+pub struct PlanItem {
+    pub id: String,
+    pub title: String,
+    pub action: PlanAction,
+    /// 建议的时间，毫秒时间戳字符串
+    pub task_time: String,
+    pub priority: Priority,
+    pub reason: String,
+}
+
+/// 当日计划建议，`items` 按建议的执行顺序排列
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export)]
+pub struct DayPlan {
+    pub date: String,
+    pub items: Vec<PlanItem>,
+}
+
+#[derive(Serialize)]
+struct Candidate<'a> {
+    id: &'a str,
+    title: &'a str,
+    priority: &'a Priority,
+    due: Option<String>,
+    estimate: Option<u32>,
+    overdue: bool,
+}
+
+#[derive(Serialize)]
+struct BusySlot<'a> {
+    title: &'a str,
+    start: String,
+    end: String,
+}
+
+#[derive(Deserialize)]
+struct PlanReply {
+    #[serde(default)]
+    plan: Vec<ReplySlot>,
+    #[serde(default)]
+    deferred: Vec<ReplyDeferral>,
+}
+
+#[derive(Deserialize)]
+struct ReplySlot {
+    id: String,
+    start: String,
+    priority: Option<String>,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Deserialize)]
+struct ReplyDeferral {
+    id: String,
+    date: String,
+    #[serde(default)]
+    reason: String,
+}
+
+fn local_millis(date: NaiveDate, time: NaiveTime) -> Option<u64> {
+    Local
+        .from_local_datetime(&NaiveDateTime::new(date, time))
+        .earliest()
+        .map(|dt| dt.timestamp_millis() as u64)
+}
+
+fn local_datetime(millis: u64) -> Option<NaiveDateTime> {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|dt| dt.naive_local())
+}
+
+fn parse_priority(value: &str) -> Option<Priority> {
+    match value.to_lowercase().as_str() {
+        "high" => Some(Priority::High),
+        "medium" => Some(Priority::Medium),
+        "low" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn apply_filter(storage: &Storage, name: &str) -> Result<Vec<Event>> {
+    let filter = map_filter(storage, name, None)?;
+    Repository::<Event>::filter(storage, |event| {
+        !event.finished
+            && match &filter {
+                Filter::A(f) => f(event),
+                Filter::B(f) => f(event),
+            }
+    })
+}
+
+/// 收集需要规划的事件和今天已占用的事件
+///
+/// 今天已设定具体时间的事件视为已占用的时间段，其余逾期、今天到期和高优先级事件作为候选。
+/// 没有时间的事件不算逾期，只在高优先级时作为候选。
+fn collect(storage: &Storage, today: NaiveDate) -> Result<(Vec<Event>, Vec<Event>)> {
+    let mut seen = HashSet::new();
+    let mut candidates = Vec::new();
+    let mut busy = Vec::new();
+    for name in ["overdue", "today", "high_priority"] {
+        for event in apply_filter(storage, name)? {
+            if name == "overdue" && event.task_time.is_none() {
+                continue;
+            }
+            if !seen.insert(event.metadata.uuid.clone()) {
+                continue;
+            }
+            let scheduled_today = event
+                .task_time
+                .and_then(local_datetime)
+                .is_some_and(|dt| dt.date() == today && dt.time() != NaiveTime::MIN);
+            if scheduled_today {
+                busy.push(event);
+            } else {
+                candidates.push(event);
+            }
+        }
+    }
+    Ok((candidates, busy))
+}
+
+fn plan_prompt(now: NaiveDateTime, candidates: &[Event], busy: &[Event]) -> Vec<ChatMessage> {
+    let today = now.date();
+    let candidates: Vec<Candidate> = candidates
+        .iter()
+        .map(|event| {
+            let due = event.task_time.and_then(local_datetime);
+            Candidate {
+                id: &event.metadata.uuid,
+                title: &event.title,
+                priority: &event.priority,
+                due: due.map(|dt| dt.format("%Y-%m-%d %H:%M").to_string()),
+                estimate: event.estimate,
+                overdue: due.is_some_and(|dt| dt.date() < today),
+            }
+        })
+        .collect();
+    let busy: Vec<BusySlot> = busy
+        .iter()
+        .filter_map(|event| {
+            let start = event.task_time.and_then(local_datetime)?;
+            let minutes = event.estimate.unwrap_or(DEFAULT_SLOT_MINUTES);
+            let end = start + chrono::Duration::minutes(minutes as i64);
+            Some(BusySlot {
+                title: &event.title,
+                start: start.format("%H:%M").to_string(),
+                end: end.format("%H:%M").to_string(),
+            })
+        })
+        .collect();
+
+    vec![
+        ChatMessage::system("你是一个专业的日程规划助手，善于根据优先级和截止时间安排一天的工作。"),
+        ChatMessage::user(format!(
+            r#"现在是 {}。请为今天制定计划：
+
+1. 从候选任务中选择今天能完成的任务，按建议的执行顺序排列，并给出开始时间（HH:MM，不早于当前时间）
+2. 不要与已占用的时间段重叠，未给出预计耗时的任务按 {} 分钟估算
+3. 今天做不完的任务请推迟到之后的某一天（YYYY-MM-DD）
+4. 可以根据紧急程度调整优先级（High、Medium、Low）
+5. 每项给出一句简短的理由
+
+候选任务:
+{}
+
+已占用的时间段:
+{}
+
+请以 JSON 对象返回，格式为 {{"plan": [{{"id": "任务id", "start": "09:00", "priority": "High", "reason": "理由"}}], "deferred": [{{"id": "任务id", "date": "2025-01-02", "reason": "理由"}}]}}。"#,
+            now.format("%Y-%m-%d %H:%M"),
+            DEFAULT_SLOT_MINUTES,
+            serde_json::to_string(&candidates).unwrap_or_default(),
+            serde_json::to_string(&busy).unwrap_or_default(),
+        )),
+    ]
+}
+
+/// 将模型回复转换为计划，忽略未知的任务、无效的时间和重复项
+fn to_plan(reply: PlanReply, candidates: &HashMap<String, Event>, today: NaiveDate) -> DayPlan {
+    let mut seen = HashSet::new();
+    let mut items = Vec::new();
+
+    for slot in reply.plan {
+        let Some(event) = candidates.get(&slot.id) else {
+            continue;
+        };
+        let Ok(start) = NaiveTime::parse_from_str(slot.start.trim(), "%H:%M") else {
+            continue;
+        };
+        let Some(task_time) = local_millis(today, start) else {
+            continue;
+        };
+        if !seen.insert(slot.id.clone()) {
+            continue;
+        }
+        items.push(PlanItem {
+            id: slot.id,
+            title: event.title.clone(),
+            action: PlanAction::Schedule,
+            task_time: task_time.to_string(),
+            priority: slot
+                .priority
+                .as_deref()
+                .and_then(parse_priority)
+                .unwrap_or_else(|| event.priority.clone()),
+            reason: slot.reason,
+        });
+    }
+
+    for deferral in reply.deferred {
+        let Some(event) = candidates.get(&deferral.id) else {
+            continue;
+        };
+        let Ok(date) = NaiveDate::parse_from_str(deferral.date.trim(), "%Y-%m-%d") else {
+            continue;
+        };
+        if date <= today {
+            continue;
+        }
+        // 推迟时保留原有的时刻
+        let time = event
+            .task_time
+            .and_then(local_datetime)
+            .map(|dt| dt.time().with_second(0).unwrap_or(NaiveTime::MIN))
+            .unwrap_or(NaiveTime::MIN);
+        let Some(task_time) = local_millis(date, time) else {
+            continue;
+        };
+        if !seen.insert(deferral.id.clone()) {
+            continue;
+        }
+        items.push(PlanItem {
+            id: deferral.id,
+            title: event.title.clone(),
+            action: PlanAction::Defer,
+            task_time: task_time.to_string(),
+            priority: event.priority.clone(),
+            reason: deferral.reason,
+        });
+    }
+
+    DayPlan {
+        date: today.format("%Y-%m-%d").to_string(),
+        items,
+    }
+}
+
+/// Plans the day with the configured model
+///
+/// Collects overdue, due-today and high-priority events through the `filter` module,
+/// together with their estimates and the time already taken by events scheduled today,
+/// and asks the model for an ordered plan. Nothing is changed until the proposal (or
+/// part of it) is passed to `accept_plan`.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+///
+/// # Returns
+/// * `Result<DayPlan, ErrorKind>` - The proposed plan, or `PermissionDenied` when the model is disabled
+#[tauri::command]
+pub async fn plan_my_day(state: State<'_, StorageState>) -> Result<DayPlan, ErrorKind> {
+    let client = llm_client()?;
+    let now = Local::now().naive_local();
+    let (candidates, busy) = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        collect(storage, now.date())?
+    };
+    if candidates.is_empty() {
+        return Ok(DayPlan {
+            date: now.date().format("%Y-%m-%d").to_string(),
+            items: Vec::new(),
+        });
+    }
+
+    let reply: PlanReply = client
+        .chat_json(&plan_prompt(now, &candidates, &busy))
+        .await
+        .map_err(llm_error)?;
+    let candidates = candidates
+        .into_iter()
+        .map(|event| (event.metadata.uuid.clone(), event))
+        .collect();
+    Ok(to_plan(reply, &candidates, now.date()))
+}
+
+/// Applies accepted plan items
+///
+/// Updates only the time and priority of each event, leaving its other fields
+/// untouched. Pass the whole plan to accept it, or only the items the user kept.
+/// Subscribed calendar events are read-only and return `PermissionDenied`.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `items` - The accepted plan items
+///
+/// # Returns
+/// * `Result<Vec<FEvent>, ErrorKind>` - The updated events
+#[tauri::command]
+pub async fn accept_plan(
+    state: State<'_, StorageState>,
+    items: Vec<PlanItem>,
+) -> Result<Vec<FEvent>, ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let mut updated = Vec::new();
+    for item in items {
+        if subscription::is_overlay(storage, &item.id)? {
+            return Err(ErrorKind::PermissionDenied);
+        }
+        if Repository::<Event>::get_by_name(storage, &item.id)?.is_none() {
+            log::warn!("Skipping plan item for missing event {}", item.id);
+            continue;
+        }
+        let mut accepted = None;
+        Repository::<Event>::update(storage, &item.id, |event: &mut Event| {
+            event.task_time = item.task_time.parse::<u64>().ok();
+            event.priority = item.priority;
+            accepted = Some(event.clone());
+            Ok(())
+        })?;
+        updated.extend(accepted.map(FEvent::from));
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::temp_storage;
+
+    fn reply(json: &str) -> PlanReply {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_to_plan() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let mut report = Event::new("Write report", "");
+        report.priority = Priority::Medium;
+        let mut call = Event::new("Call bank", "");
+        call.task_time = local_millis(
+            NaiveDate::from_ymd_opt(2026, 10, 12).unwrap(),
+            NaiveTime::from_hms_opt(15, 30, 0).unwrap(),
+        );
+        let candidates: HashMap<String, Event> = [report.clone(), call.clone()]
+            .into_iter()
+            .map(|e| (e.metadata.uuid.clone(), e))
+            .collect();
+
+        let json = format!(
+            r#"{{
+                "plan": [
+                    {{"id": "{report}", "start": "09:30", "priority": "High", "reason": "due soon"}},
+                    {{"id": "unknown", "start": "10:00"}},
+                    {{"id": "{report}", "start": "11:00"}},
+                    {{"id": "{call}", "start": "late"}}
+                ],
+                "deferred": [
+                    {{"id": "{call}", "date": "2026-10-16", "reason": "bank closed"}}
+                ]
+            }}"#,
+            report = report.metadata.uuid,
+            call = call.metadata.uuid
+        );
+        let plan = to_plan(reply(&json), &candidates, today);
+
+        assert_eq!(plan.date, "2026-10-14");
+        assert_eq!(plan.items.len(), 2);
+
+        let first = &plan.items[0];
+        assert_eq!(first.id, report.metadata.uuid);
+        assert_eq!(first.action, PlanAction::Schedule);
+        assert!(matches!(first.priority, Priority::High));
+        assert_eq!(
+            first.task_time,
+            local_millis(today, NaiveTime::from_hms_opt(9, 30, 0).unwrap())
+                .unwrap()
+                .to_string()
+        );
+
+        // 推迟保留原有的 15:30
+        let second = &plan.items[1];
+        assert_eq!(second.action, PlanAction::Defer);
+        assert_eq!(
+            second.task_time,
+            local_millis(
+                NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+                NaiveTime::from_hms_opt(15, 30, 0).unwrap()
+            )
+            .unwrap()
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_deferral_must_be_in_future() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+        let event = Event::new("Stretch", "");
+        let candidates = HashMap::from([(event.metadata.uuid.clone(), event.clone())]);
+        let json = format!(
+            r#"{{"deferred": [{{"id": "{}", "date": "2026-10-14"}}]}}"#,
+            event.metadata.uuid
+        );
+
+        assert!(to_plan(reply(&json), &candidates, today).items.is_empty());
+    }
+
+    #[test]
+    fn test_collect_skips_undated() {
+        let (storage, _dir) = temp_storage();
+        let undated = Event::new("Someday", "");
+        let mut urgent = Event::new("Urgent", "");
+        urgent.priority = Priority::High;
+        let mut late = Event::new("Late", "");
+        late.task_time = local_millis(
+            NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        );
+        for event in [&undated, &urgent, &late] {
+            Repository::<Event>::add(&storage, event).unwrap();
+        }
+
+        let (candidates, busy) = collect(&storage, Local::now().date_naive()).unwrap();
+        let ids: HashSet<&str> = candidates
+            .iter()
+            .map(|e| e.metadata.uuid.as_str())
+            .collect();
+        assert_eq!(
+            ids,
+            HashSet::from([urgent.metadata.uuid.as_str(), late.metadata.uuid.as_str()])
+        );
+        assert!(busy.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::DerefMut;
+use tauri::{Manager, State};
+use ts_rs::TS;
+
+use super::{enabled_model, llm_client, llm_error, ChatClient};
+use crate::entity::embedding::{self, Embedding};
+use crate::entity::{Event, FEvent, Repository, Storage, StorageState};
+use crate::error::ErrorKind;
+use crate::utils::bus::{self, ChangeKind, EntityKind};
+
+const MAX_TEXT_CHARS: usize = 4000;
+const BATCH_SIZE: usize = 32;
+
+/// 上次检查所有事件时使用的向量模型，模型不变时之后只需检查有变更的事件
+static CHECKED_MODEL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+/// 上次检查之后新增或修改的事件
+static CHANGED: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 语义搜索结果
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export)]
+pub struct SearchHit {
+    pub event: FEvent,
+    /// 余弦相似度，越接近 1 越相似
+    pub score: f32,
+}
+
+/// 用于计算向量的事件文本：标题加Markdown内容，过长时截断
+fn event_text(event: &Event) -> String {
+    let content = fs::read_to_string(&event.content).unwrap_or_default();
+    format!("{}\n{}", event.title, content)
+        .chars()
+        .take(MAX_TEXT_CHARS)
+        .collect()
+}
+
+/// 重新计算缺失或过期的向量
+///
+/// `uuids` 为 None 时检查所有事件。文本或向量模型发生变化时摘要不同，向量会被重新计算。
+pub async fn refresh(
+    state: &State<'_, StorageState>,
+    client: &ChatClient,
+    uuids: Option<&[String]>,
+) -> Result<()> {
+    let stale = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        let events = match uuids {
+            Some(uuids) => {
+                let mut events = Vec::new();
+                for uuid in uuids {
+                    events.extend(Repository::<Event>::get_by_name(storage, uuid)?);
+                }
+                events
+            }
+            None => Repository::<Event>::get_all(storage)?,
+        };
+
+        let mut stale = Vec::new();
+        for event in events {
+            let text = event_text(&event);
+            let digest = embedding::digest(client.embedding_model(), &text);
+            let current = Repository::<Embedding>::get_by_name(storage, &event.metadata.uuid)?;
+            if current.is_none_or(|current| current.digest != digest) {
+                stale.push((event.metadata.uuid, digest, text));
+            }
+        }
+        stale
+    };
+
+    for batch in stale.chunks(BATCH_SIZE) {
+        let inputs: Vec<String> = batch.iter().map(|(_, _, text)| text.clone()).collect();
+        let vectors = client.embed(&inputs).await?;
+
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        for ((uuid, digest, _), vector) in batch.iter().zip(vectors) {
+            Repository::<Embedding>::add(
+                storage,
+                &Embedding {
+                    uuid: uuid.clone(),
+                    digest: *digest,
+                    vector,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 记录新增或修改的事件，搜索前只重新检查这些事件的向量
+pub fn track_changes() {
+    bus::subscribe(|change| {
+        if change.entity == EntityKind::Event && change.kind != ChangeKind::Deleted {
+            CHANGED.lock().insert(change.id.clone());
+        }
+    });
+}
+
+// 搜索前刷新向量：首次搜索或向量模型变化后检查所有事件，之后只检查有变更的事件
+async fn refresh_changed(state: &State<'_, StorageState>, client: &ChatClient) -> Result<()> {
+    let model = client.embedding_model().to_string();
+    let full = CHECKED_MODEL.lock().as_deref() != Some(model.as_str());
+    let changed: Vec<String> = CHANGED.lock().drain().collect();
+    let result = if full {
+        refresh(state, client, None).await
+    } else if changed.is_empty() {
+        return Ok(());
+    } else {
+        refresh(state, client, Some(&changed)).await
+    };
+    match result {
+        Ok(()) => {
+            if full {
+                *CHECKED_MODEL.lock() = Some(model);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            // 失败时保留待检查的事件，下次搜索时重试
+            CHANGED.lock().extend(changed);
+            Err(e)
+        }
+    }
+}
+
+/// 在后台刷新单个事件的向量，未启用大模型时不做任何事
+pub async fn schedule_refresh(state: &State<'_, StorageState>, uuid: &str) {
+    if enabled_model().is_none() {
+        return;
+    }
+    let app = state.1.lock().await.handle().clone();
+    let uuid = uuid.to_string();
+    tauri::async_runtime::spawn(async move {
+        let Ok(client) = llm_client() else {
+            return;
+        };
+        let state = app.state::<StorageState>();
+        if let Err(e) = refresh(&state, &client, Some(&[uuid.clone()])).await {
+            log::warn!("Failed to refresh embedding for {}: {:?}", uuid, e);
+        }
+    });
+}
+
+fn resolve_hits(storage: &Storage, hits: Vec<(String, f32)>) -> Result<Vec<SearchHit>> {
+    let mut results = Vec::new();
+    for (uuid, score) in hits {
+        if let Some(event) = Repository::<Event>::get_by_name(storage, &uuid)? {
+            results.push(SearchHit {
+                event: FEvent::from(event),
+                score,
+            });
+        }
+    }
+    Ok(results)
+}
+
+/// Searches events by meaning instead of keywords
+///
+/// Embeds the query with the configured model and returns the `k` events whose
+/// title and content are closest to it. Embeddings of events changed since the
+/// last search are computed first; the first search checks every event.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `query` - Free-text search query
+/// * `k` - Maximum number of results
+///
+/// # Returns
+/// * `Result<Vec<SearchHit>, ErrorKind>` - Matching events ordered by similarity, or
+///   `PermissionDenied` when the model is disabled
+#[tauri::command]
+pub async fn semantic_search(
+    state: State<'_, StorageState>,
+    query: String,
+    k: usize,
+) -> Result<Vec<SearchHit>, ErrorKind> {
+    let client = llm_client()?;
+    refresh_changed(&state, &client).await.map_err(llm_error)?;
+    let query = client
+        .embed(&[query])
+        .await
+        .map_err(llm_error)?
+        .pop()
+        .ok_or(ErrorKind::InternetError)?;
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let embeddings = Repository::<Embedding>::get_all(storage)?;
+    let hits = embedding::nearest(&query, &embeddings, k, None);
+    Ok(resolve_hits(storage, hits)?)
+}
+
+/// Finds events similar to the given one
+///
+/// Useful for spotting duplicates before creating or after editing a task.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Unique identifier of the event to compare against
+/// * `k` - Maximum number of results
+///
+/// # Returns
+/// * `Result<Vec<SearchHit>, ErrorKind>` - Other events ordered by similarity
+#[tauri::command]
+pub async fn similar_events(
+    state: State<'_, StorageState>,
+    uuid: &str,
+    k: usize,
+) -> Result<Vec<SearchHit>, ErrorKind> {
+    let client = llm_client()?;
+    refresh_changed(&state, &client).await.map_err(llm_error)?;
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let target = Repository::<Embedding>::get_by_name(storage, uuid)?.ok_or(ErrorKind::NotFound)?;
+    let embeddings = Repository::<Embedding>::get_all(storage)?;
+    let hits = embedding::nearest(&target.vector, &embeddings, k, Some(uuid));
+    Ok(resolve_hits(storage, hits)?)
+}
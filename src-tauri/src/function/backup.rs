@@ -0,0 +1,677 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{Local, Utc};
+use redb::{Database, ReadableTable, TableDefinition, TableHandle};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use ts_rs::TS;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::entity::{Event, Repository, Storage, StorageState};
+use crate::function::sync::RECORD_DIR;
+use crate::utils::config::{self, Backup};
+use crate::utils::manager::scheduled_task::ScheduledTask;
+use crate::utils::manager::tasker::TaskManager;
+use crate::utils::{bus, clear_all_caches, AppPaths};
+
+/// 备份文件格式标识
+const FORMAT: &str = "todopulse-backup";
+/// 备份文件格式版本，结构发生不兼容变化时递增
+const VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const DB_ENTRY: &str = "data.db";
+const CONFIG_ENTRY: &str = "config.toml";
+const CONTENT_DIR: &str = "content";
+/// 写入中的临时文件的扩展名，如同步记录的 `.json.tmp` 与未完成备份的 `.partial`
+const SCRATCH_EXTENSIONS: &[&str] = &["tmp", "partial"];
+/// 自动备份的文件名前缀，轮换时只删除这些文件
+const AUTO_PREFIX: &str = "ToDoPulse-auto-";
+
+/// 备份中的文件
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct BackupFile {
+    pub path: String, // 压缩包内的路径
+    #[ts(type = "number")]
+    pub size: u64,
+}
+
+/// 备份清单，恢复前用于校验压缩包
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct BackupManifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: String, // RFC 3339
+    /// 备份时的数据目录，恢复到其他位置时用于改写事件内容路径
+    pub data_dir: String,
+    pub files: Vec<BackupFile>,
+}
+
+// 将数据库的所有表复制到新的数据库文件，读事务保证复制的是同一时刻的数据
+fn snapshot_db(db: &Database, dest: &Path) -> Result<()> {
+    let target = Database::create(dest)?;
+    let read = db.begin_read()?;
+    let write = target.begin_write()?;
+    for handle in read.list_tables()? {
+        let def: TableDefinition<&[u8], &[u8]> = TableDefinition::new(handle.name());
+        let source = read.open_table(def)?;
+        let mut table = write.open_table(def)?;
+        for entry in source.iter()? {
+            let (key, value) = entry?;
+            table.insert(key.value(), value.value())?;
+        }
+    }
+    write.commit()?;
+    Ok(())
+}
+
+fn is_scratch(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| SCRATCH_EXTENSIONS.iter().any(|scratch| ext == *scratch))
+}
+
+// 收集目录下的所有文件，跳过 `skip` 中的路径与临时文件
+fn collect_files(dir: &Path, skip: &[&Path]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if skip.contains(&path.as_path()) || is_scratch(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(collect_files(&path, skip)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+// 压缩包内统一使用 `/` 分隔路径
+fn entry_name(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn add_file(zip: &mut ZipWriter<File>, name: &str, path: &Path) -> Result<BackupFile> {
+    let size = fs::metadata(path)?.len();
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(size >= u32::MAX as u64);
+    zip.start_file(name, options)?;
+    io::copy(&mut File::open(path)?, zip)?;
+    Ok(BackupFile {
+        path: name.to_string(),
+        size,
+    })
+}
+
+/// 将数据库快照、数据目录中的内容与附件以及配置写入压缩包
+///
+/// `skip` 为数据目录中不备份的路径，如数据库文件与备份目录；同步记录与写入中的
+/// 临时文件总是跳过。先写入临时文件，完成后再重命名，避免留下不完整的备份。
+pub fn write_archive(
+    db: &Database,
+    data_dir: &Path,
+    skip: &[&Path],
+    config: Option<&str>,
+    dest: &Path,
+) -> Result<BackupManifest> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let partial = dest.with_extension("partial");
+    let snapshot = std::env::temp_dir().join(format!("todopulse-{}.db", uuid::Uuid::new_v4()));
+
+    let result = (|| -> Result<BackupManifest> {
+        let mut zip = ZipWriter::new(File::create(&partial)?);
+        let mut files = Vec::new();
+
+        snapshot_db(db, &snapshot)?;
+        files.push(add_file(&mut zip, DB_ENTRY, &snapshot)?);
+
+        // 备份位于数据目录中时不能把备份自身打包进去
+        let records = data_dir.join(RECORD_DIR);
+        let mut skip = skip.to_vec();
+        skip.extend([records.as_path(), partial.as_path(), dest]);
+        for path in collect_files(data_dir, &skip)? {
+            let relative = path.strip_prefix(data_dir)?;
+            let name = format!("{}/{}", CONTENT_DIR, entry_name(relative));
+            files.push(add_file(&mut zip, &name, &path)?);
+        }
+
+        if let Some(config) = config {
+            zip.start_file(CONFIG_ENTRY, SimpleFileOptions::default())?;
+            io::Write::write_all(&mut zip, config.as_bytes())?;
+            files.push(BackupFile {
+                path: CONFIG_ENTRY.to_string(),
+                size: config.len() as u64,
+            });
+        }
+
+        let manifest = BackupManifest {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            data_dir: data_dir.to_string_lossy().to_string(),
+            files,
+        };
+        zip.start_file(MANIFEST, SimpleFileOptions::default())?;
+        serde_json::to_writer_pretty(&mut zip, &manifest)?;
+        zip.finish()?;
+        Ok(manifest)
+    })();
+
+    let _ = fs::remove_file(&snapshot);
+    match result {
+        Ok(manifest) => {
+            fs::rename(&partial, dest)?;
+            Ok(manifest)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&partial);
+            Err(e)
+        }
+    }
+}
+
+/// 读取并校验备份清单：格式与版本匹配，清单中的文件都存在且大小一致
+pub fn read_manifest(archive: &mut ZipArchive<File>) -> Result<BackupManifest> {
+    let manifest: BackupManifest = match archive.by_name(MANIFEST) {
+        Ok(file) => serde_json::from_reader(file)?,
+        Err(_) => bail!("不是ToDoPulse的备份文件：缺少清单"),
+    };
+    if manifest.format != FORMAT {
+        bail!("不是ToDoPulse的备份文件");
+    }
+    if manifest.version > VERSION {
+        bail!(
+            "备份文件版本 {} 高于当前支持的版本 {}",
+            manifest.version,
+            VERSION
+        );
+    }
+    if !manifest.files.iter().any(|file| file.path == DB_ENTRY) {
+        bail!("备份中缺少数据库");
+    }
+    for file in &manifest.files {
+        let allowed = file.path == DB_ENTRY
+            || file.path == CONFIG_ENTRY
+            || file.path.starts_with(&format!("{}/", CONTENT_DIR));
+        let entry = archive
+            .by_name(&file.path)
+            .map_err(|_| anyhow!("备份中缺少文件 {}", file.path))?;
+        if !allowed || entry.enclosed_name().as_deref() != Some(Path::new(&file.path)) {
+            bail!("备份中的文件路径无效: {}", file.path);
+        }
+        if entry.size() != file.size {
+            bail!("备份中的文件 {} 大小与清单不符", file.path);
+        }
+    }
+    Ok(manifest)
+}
+
+/// 将清单中的文件解压到临时目录，解压时会校验每个文件的CRC
+pub fn extract(
+    archive: &mut ZipArchive<File>,
+    manifest: &BackupManifest,
+    staging: &Path,
+) -> Result<()> {
+    for file in &manifest.files {
+        let target = staging.join(&file.path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut entry = archive.by_name(&file.path)?;
+        io::copy(&mut entry, &mut File::create(&target)?)?;
+    }
+    // 确认数据库可以打开
+    Database::open(staging.join(DB_ENTRY)).map_err(|e| anyhow!("备份中的数据库已损坏: {}", e))?;
+    Ok(())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let path = entry?.path();
+        let target = to.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+// 清空目录，保留 `keep` 中的路径及其所在的目录
+fn clear_dir(dir: &Path, keep: &[&Path]) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if keep.contains(&path.as_path()) {
+            continue;
+        }
+        if !path.is_dir() {
+            fs::remove_file(&path)?;
+        } else if keep.iter().any(|kept| kept.starts_with(&path)) {
+            clear_dir(&path, keep)?;
+        } else {
+            fs::remove_dir_all(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 用解压出的数据替换数据目录，调用前需释放数据库文件
+///
+/// `keep` 中的路径不会被删除，如备份目录与未包含在备份中的同步记录。
+pub fn replace_data(staging: &Path, data_dir: &Path, db_path: &Path, keep: &[&Path]) -> Result<()> {
+    clear_dir(data_dir, keep)?;
+    let content = staging.join(CONTENT_DIR);
+    if content.exists() {
+        copy_dir(&content, data_dir)?;
+    }
+    fs::copy(staging.join(DB_ENTRY), db_path)?;
+    Ok(())
+}
+
+// 备份恢复到不同的数据目录时，改写事件中记录的内容文件路径
+fn rewrite_content_paths(storage: &Storage, from: &Path, to: &Path) -> Result<()> {
+    if from == to {
+        return Ok(());
+    }
+    for mut event in Repository::<Event>::get_all(storage)? {
+        let Ok(relative) = Path::new(&event.content).strip_prefix(from) else {
+            continue;
+        };
+        event.content = to.join(relative).to_string_lossy().to_string();
+        Repository::<Event>::add(storage, &event)?;
+    }
+    Ok(())
+}
+
+/// 删除最旧的自动备份，只保留 `keep` 份，返回被删除的文件
+pub fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy())
+                .is_some_and(|name| name.starts_with(AUTO_PREFIX) && name.ends_with(".zip"))
+        })
+        .collect();
+    // 文件名中的时间戳使文件名顺序与时间顺序一致
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = backups.into_iter().take(excess).collect();
+    for path in &removed {
+        fs::remove_file(path)?;
+    }
+    Ok(removed)
+}
+
+/// 备份目录，未配置时使用导出目录下的 backups
+fn backup_dir() -> PathBuf {
+    match Backup::load() {
+        Ok(backup) if !backup.dir.is_empty() => PathBuf::from(backup.dir),
+        _ => AppPaths::export_dir().join("backups"),
+    }
+}
+
+fn backup_name(prefix: &str) -> String {
+    format!("{}{}.zip", prefix, Local::now().format("%Y%m%d-%H%M%S"))
+}
+
+// 备份当前数据，配置中的密钥会被去除
+fn backup_to(storage: &Storage, dest: &Path) -> Result<BackupManifest> {
+    let config = config::get_config()
+        .ok()
+        .and_then(|config| toml::to_string(&config.without_secrets()).ok());
+    write_archive(
+        &storage.db,
+        AppPaths::data_dir(),
+        &[&Storage::db_path(), &backup_dir()],
+        config.as_deref(),
+        dest,
+    )
+}
+
+/// 在替换数据前备份当前数据，返回备份文件路径
+pub(crate) fn backup_before(storage: &Storage, prefix: &str) -> Result<PathBuf> {
+    let dest = backup_dir().join(backup_name(prefix));
+    backup_to(storage, &dest)?;
+    Ok(dest)
+}
+
+/// Creates a backup archive of all application data
+///
+/// Writes a single zip archive containing a consistent snapshot of the database,
+/// every event content directory with its attachments, the configuration with
+/// the API key and WebDAV password removed, and a manifest describing the files.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `path` - Optional destination file or directory; defaults to the backup directory
+///
+/// # Returns
+/// * `Result<String, String>` - Path of the created archive or error message
+#[tauri::command]
+pub async fn create_backup(
+    state: State<'_, StorageState>,
+    path: Option<String>,
+) -> Result<String, String> {
+    let dest = match path.map(PathBuf::from) {
+        Some(path) if path.is_dir() => path.join(backup_name("ToDoPulse-")),
+        Some(path) => path,
+        None => backup_dir().join(backup_name("ToDoPulse-")),
+    };
+
+    // 持有锁直到备份完成，避免备份过程中内容文件被修改
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    backup_to(storage, &dest).map_err(|e| e.to_string())?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Restores application data from a backup archive
+///
+/// Validates the manifest and every file in the archive before touching any data.
+/// The current data is saved to a "before-restore" backup first, then the database,
+/// content directories and configuration are replaced. Secrets missing from the
+/// backed-up configuration keep their current values.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `path` - Path of the backup archive
+///
+/// # Returns
+/// * `Result<BackupManifest, String>` - Manifest of the restored backup or error message
+#[tauri::command]
+pub async fn restore_backup(
+    state: State<'_, StorageState>,
+    path: String,
+) -> Result<BackupManifest, String> {
+    let staging = std::env::temp_dir().join(format!("todopulse-restore-{}", uuid::Uuid::new_v4()));
+    let result = restore_from(state, Path::new(&path), &staging).await;
+    let _ = fs::remove_dir_all(&staging);
+    result.map_err(|e| e.to_string())
+}
+
+async fn restore_from(
+    state: State<'_, StorageState>,
+    path: &Path,
+    staging: &Path,
+) -> Result<BackupManifest> {
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let manifest = read_manifest(&mut archive)?;
+    extract(&mut archive, &manifest, staging)?;
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let before = storage.snapshot()?;
+
+    let safety = backup_before(storage, "ToDoPulse-before-restore-")?;
+    log::info!(
+        "Saved current data to {} before restoring",
+        safety.display()
+    );
+
+    let data_dir = AppPaths::data_dir();
+    let backups = backup_dir();
+    let records = data_dir.join(RECORD_DIR);
+    let keep = [safety.as_path(), backups.as_path(), records.as_path()];
+    storage.close()?;
+    let replaced = replace_data(staging, data_dir, &Storage::db_path(), &keep);
+    storage.reload()?;
+    replaced.map_err(|e| anyhow!("恢复失败，原数据已备份到 {}: {}", safety.display(), e))?;
+    rewrite_content_paths(storage, Path::new(&manifest.data_dir), data_dir)?;
+
+    if let Ok(text) = fs::read_to_string(staging.join(CONFIG_ENTRY)) {
+        config::restore(&text)?;
+    }
+
+    clear_all_caches();
+    let changes = before.changes(&storage.snapshot()?);
+    drop(guard);
+    for change in &changes {
+        bus::publish(change);
+    }
+    Ok(manifest)
+}
+
+async fn scheduled_backup(app: &AppHandle, keep: usize) -> Result<()> {
+    let dir = backup_dir();
+    let dest = dir.join(backup_name(AUTO_PREFIX));
+    {
+        let state = app.state::<StorageState>();
+        let mut guard = state.0.lock().await;
+        backup_to(guard.deref_mut(), &dest)?;
+    }
+    log::info!("Scheduled backup written to {}", dest.display());
+    for path in rotate(&dir, keep)? {
+        log::info!("Removed old backup {}", path.display());
+    }
+    Ok(())
+}
+
+/// 根据配置启动每日自动备份任务
+pub async fn setup(app: AppHandle) {
+    let backup = match Backup::load() {
+        Ok(backup) => backup,
+        Err(e) => {
+            log::error!("Backup config not found: {}", e);
+            return;
+        }
+    };
+    if !backup.switch {
+        log::info!("Scheduled backup disabled");
+        return;
+    }
+
+    let keep = backup.keep.max(1) as usize;
+    let task = ScheduledTask::new("backup_task", vec![backup.time.as_str()], move || {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = scheduled_backup(&app, keep).await {
+                log::error!("Scheduled backup failed: {}", e);
+            }
+        });
+    });
+    match task {
+        Ok(task) => {
+            TaskManager::start(Box::new(task)).await;
+            log::info!("Scheduled backup at {}", backup.time);
+        }
+        Err(e) => log::error!("Invalid backup time {}: {}", backup.time, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("events");
+
+    fn sample_data(root: &Path) -> (Database, PathBuf, PathBuf) {
+        let data_dir = root.join("data");
+        fs::create_dir_all(data_dir.join("Pay rent")).unwrap();
+        fs::write(data_dir.join("Pay rent/Pay rent.md"), "![r](./receipt.png)").unwrap();
+        fs::write(data_dir.join("Pay rent/receipt.png"), b"png").unwrap();
+        let db_path = data_dir.join("data.db");
+        let db = Database::create(&db_path).unwrap();
+        let txn = db.begin_write().unwrap();
+        txn.open_table(TABLE)
+            .unwrap()
+            .insert(b"id-1".as_slice(), b"event".as_slice())
+            .unwrap();
+        txn.commit().unwrap();
+        (db, data_dir, db_path)
+    }
+
+    #[test]
+    fn test_backup_round_trip() {
+        let root = tempfile::tempdir().unwrap();
+        let (db, data_dir, db_path) = sample_data(root.path());
+        // 位于数据目录中的备份目录、同步记录与临时文件不打包
+        let backups = data_dir.join("backups");
+        fs::create_dir_all(&backups).unwrap();
+        fs::write(backups.join("old.zip"), b"zip").unwrap();
+        let records = data_dir.join(RECORD_DIR).join("laptop");
+        fs::create_dir_all(&records).unwrap();
+        fs::write(records.join("1.json"), "{}").unwrap();
+        fs::write(data_dir.join("Pay rent/note.json.tmp"), "{}").unwrap();
+        let dest = backups.join("test.zip");
+
+        let skip = [db_path.as_path(), backups.as_path()];
+        let manifest = write_archive(&db, &data_dir, &skip, Some("[theme]\n"), &dest).unwrap();
+        let paths: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "data.db",
+                "content/Pay rent/Pay rent.md",
+                "content/Pay rent/receipt.png",
+                "config.toml"
+            ]
+        );
+        assert!(!dest.with_extension("partial").exists());
+
+        let mut archive = ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        assert_eq!(read_manifest(&mut archive).unwrap(), manifest);
+        let staging = root.path().join("staging");
+        extract(&mut archive, &manifest, &staging).unwrap();
+
+        // 替换后只剩备份中的内容与保留的目录
+        drop(db);
+        fs::write(data_dir.join("stray.md"), "new").unwrap();
+        let records = data_dir.join(RECORD_DIR);
+        replace_data(&staging, &data_dir, &db_path, &[&backups, &records]).unwrap();
+        assert!(!data_dir.join("stray.md").exists());
+        assert!(!data_dir.join("Pay rent/note.json.tmp").exists());
+        assert!(dest.exists());
+        assert!(records.join("laptop/1.json").exists());
+        assert_eq!(
+            fs::read(data_dir.join("Pay rent/receipt.png")).unwrap(),
+            b"png"
+        );
+        let db = Database::open(&db_path).unwrap();
+        let txn = db.begin_read().unwrap();
+        let table = txn.open_table(TABLE).unwrap();
+        assert_eq!(
+            table.get(b"id-1".as_slice()).unwrap().unwrap().value(),
+            b"event"
+        );
+    }
+
+    // 构造包含给定清单与文件的压缩包
+    fn archive_with(path: &Path, manifest: Option<&BackupManifest>, files: &[(&str, &[u8])]) {
+        let mut zip = ZipWriter::new(File::create(path).unwrap());
+        for (name, data) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }
+        if let Some(manifest) = manifest {
+            zip.start_file(MANIFEST, SimpleFileOptions::default())
+                .unwrap();
+            serde_json::to_writer(&mut zip, manifest).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_rejects_invalid_archives() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("test.zip");
+        let check = |manifest: Option<&BackupManifest>, files: &[(&str, &[u8])]| {
+            archive_with(&path, manifest, files);
+            let mut archive = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+            read_manifest(&mut archive).map_err(|e| e.to_string())
+        };
+        let db: (&str, &[u8]) = (DB_ENTRY, b"not a database");
+        let mut manifest = BackupManifest {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            app_version: "0.1.0".to_string(),
+            created_at: "2026-10-18T03:00:00+00:00".to_string(),
+            data_dir: "/data".to_string(),
+            files: vec![BackupFile {
+                path: DB_ENTRY.to_string(),
+                size: 14,
+            }],
+        };
+
+        assert!(check(None, &[db]).unwrap_err().contains("缺少清单"));
+        manifest.version = VERSION + 1;
+        assert!(check(Some(&manifest), &[db])
+            .unwrap_err()
+            .contains("高于当前支持的版本"));
+        manifest.version = VERSION;
+        manifest.files[0].size = 3;
+        assert!(check(Some(&manifest), &[db])
+            .unwrap_err()
+            .contains("大小与清单不符"));
+        manifest.files[0].size = 14;
+        manifest.files.push(BackupFile {
+            path: "content/../../escape.md".to_string(),
+            size: 1,
+        });
+        let escape: (&str, &[u8]) = ("content/../../escape.md", b"x");
+        assert!(check(Some(&manifest), &[db, escape])
+            .unwrap_err()
+            .contains("路径无效"));
+
+        // 清单无误，但数据库无法打开
+        manifest.files.pop();
+        let manifest = check(Some(&manifest), &[db]).unwrap();
+        let mut archive = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let err = extract(&mut archive, &manifest, &root.path().join("staging")).unwrap_err();
+        assert!(err.to_string().contains("数据库已损坏"));
+    }
+
+    #[test]
+    fn test_rotate_keeps_newest_auto_backups() {
+        let root = tempfile::tempdir().unwrap();
+        for name in [
+            "ToDoPulse-auto-20261014-030000.zip",
+            "ToDoPulse-auto-20261016-030000.zip",
+            "ToDoPulse-auto-20261015-030000.zip",
+            "ToDoPulse-20261001-120000.zip",
+            "notes.txt",
+        ] {
+            fs::write(root.path().join(name), "").unwrap();
+        }
+
+        let removed = rotate(root.path(), 2).unwrap();
+        assert_eq!(
+            removed,
+            vec![root.path().join("ToDoPulse-auto-20261014-030000.zip")]
+        );
+        let mut left: Vec<String> = fs::read_dir(root.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                "ToDoPulse-20261001-120000.zip",
+                "ToDoPulse-auto-20261015-030000.zip",
+                "ToDoPulse-auto-20261016-030000.zip",
+                "notes.txt"
+            ]
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::State;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::entity::event::remove_event;
+use crate::entity::{Event, List, Repository, Storage, StorageState};
+use crate::function::export::ics::{write_calendar, IcsMode};
+use crate::function::export::pipeline::collect;
+use crate::function::import::ics::parse_ics;
+use crate::function::import::{apply, create_content, ImportReport};
+use crate::utils::{clear_all_caches, config, AppPaths};
+
+use webdav::caldav::{
+    self, CalDavAction, CalDavClient, CalDavState, Calendar, CalendarState, Changes, ItemState,
+    Resource, Written,
+};
+use webdav::model::ConflictStrategy;
+
+const STATE_FILE: &str = "caldav_state.json";
+
+// 同一时间只允许一次CalDAV同步
+static SYNCING: AtomicBool = AtomicBool::new(false);
+
+/// 持有期间占用同步标志，离开作用域（包括panic）时释放
+struct SyncGuard;
+
+impl SyncGuard {
+    fn acquire() -> Option<Self> {
+        (!SYNCING.swap(true, Ordering::SeqCst)).then_some(SyncGuard)
+    }
+}
+
+impl Drop for SyncGuard {
+    fn drop(&mut self) {
+        SYNCING.store(false, Ordering::SeqCst);
+    }
+}
+
+/// CalDAV同步结果
+#[derive(Serialize, Deserialize, TS, Clone, Debug, Default)]
+#[ts(export)]
+pub struct CalDavReport {
+    pub uploaded: usize,
+    pub downloaded: usize,
+    pub deleted_local: usize,
+    pub deleted_remote: usize,
+    pub conflicts: Vec<String>, // 未能自动解决的冲突，下次同步时重试
+    pub errors: Vec<String>,    // 同步失败的列表及原因
+}
+
+// 将事件渲染为只含一个VTODO的日历对象，导入的事件沿用来源的UID
+fn vtodo(event: &Event, now: DateTime<Utc>) -> Result<String> {
+    let mut event = event.clone();
+    if let Some(uid) = event.metadata.external_id.take() {
+        event.metadata.uuid = uid;
+    }
+    collect(|out| write_calendar(std::slice::from_ref(&event), IcsMode::Todo, now, out))
+        .map_err(|e| anyhow!(e))
+}
+
+// 使用固定的DTSTAMP计算内容哈希，只有事件本身变化时哈希才会改变；
+// 哈希保存在同步状态中，必须在不同版本与平台间保持稳定
+fn content_hash(event: &Event) -> Result<String> {
+    let text = vtodo(event, DateTime::UNIX_EPOCH)?;
+    Ok(format!("{:x}", Sha256::digest(text.as_bytes())))
+}
+
+fn find_event(storage: &Storage, uid: &str) -> Result<Option<Event>> {
+    let found = Repository::<Event>::filter(storage, |event| {
+        event.metadata.uuid == uid || event.metadata.external_id.as_deref() == Some(uid)
+    })?;
+    Ok(found.into_iter().next())
+}
+
+// 冲突时保留本地版本的副本，作为新事件在下次同步时上传
+fn keep_local_copy(storage: &Storage, event: &Event) -> Result<()> {
+    let description = fs::read_to_string(&event.content).unwrap_or_default();
+    let mut copy = event.clone();
+    copy.metadata.uuid = Uuid::new_v4().to_string();
+    copy.metadata.external_id = None;
+    copy.title = format!("{} (本地)", event.title);
+    copy.content = create_content(&copy.title, &description)?;
+    Repository::<Event>::add(storage, &copy)
+}
+
+// 写入下载的VTODO，返回对应本地事件的同步记录
+fn download(
+    storage: &Storage,
+    list: &List,
+    resource: &Resource,
+    keep_local: bool,
+) -> Result<Option<(String, ItemState)>> {
+    let mut imported = ImportReport::default();
+    let data = resource.data.as_deref().unwrap_or_default();
+    let Some(mut draft) = parse_ics(data, &mut imported).into_iter().next() else {
+        // 已取消或无法解析的条目不写入本地
+        log::warn!("跳过远程资源 {}: {:?}", resource.href, imported.skipped);
+        return Ok(None);
+    };
+    let uid = draft
+        .uid
+        .get_or_insert_with(|| resource.href.clone())
+        .clone();
+
+    let previous = if keep_local {
+        find_event(storage, &uid)?
+    } else {
+        None
+    };
+    if let Some(event) = previous {
+        keep_local_copy(storage, &event)?;
+    }
+    apply(storage, vec![draft], Some(&list.uuid), &mut imported)?;
+
+    let Some(mut event) = find_event(storage, &uid)? else {
+        return Ok(None);
+    };
+    // 在远程被移到这个日历的事件
+    if event.metadata.list.as_deref() != Some(list.uuid.as_str()) {
+        Repository::<Event>::update(storage, &event.metadata.uuid, |event| {
+            event.metadata.list = Some(list.uuid.clone());
+            Ok(())
+        })?;
+        event.metadata.list = Some(list.uuid.clone());
+    }
+    let item = ItemState {
+        href: resource.href.clone(),
+        etag: resource.etag.clone(),
+        hash: content_hash(&event)?,
+    };
+    Ok(Some((event.metadata.uuid, item)))
+}
+
+// 同步一个列表与对应的日历，只在读写数据库时持有锁
+async fn sync_calendar(
+    client: &CalDavClient,
+    state: &StorageState,
+    list: &List,
+    calendar: &Calendar,
+    saved: &mut CalendarState,
+    strategy: ConflictStrategy,
+    report: &mut CalDavReport,
+) -> Result<()> {
+    // ctag 未变时日历中没有任何变化
+    let changes = if calendar.ctag.is_some() && calendar.ctag == saved.ctag {
+        Changes::default()
+    } else {
+        client
+            .changes(calendar, saved.sync_token.as_deref())
+            .await?
+    };
+
+    let events: HashMap<String, Event> = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        Repository::<Event>::filter(storage, |event| {
+            event.metadata.list.as_deref() == Some(list.uuid.as_str())
+        })?
+    }
+    .into_iter()
+    .map(|event| (event.metadata.uuid.clone(), event))
+    .collect();
+    let mut local = HashMap::new();
+    for (uid, event) in &events {
+        local.insert(uid.clone(), content_hash(event)?);
+    }
+
+    let actions = caldav::plan(&calendar.href, &local, saved, &changes, strategy);
+    let hrefs: Vec<String> = actions
+        .iter()
+        .filter_map(|action| match action {
+            CalDavAction::Download { href, .. } => Some(href.clone()),
+            _ => None,
+        })
+        .collect();
+    let fetched = client.fetch(calendar, &hrefs).await?;
+
+    // 有写入时 ctag 必然改变；有未解决的冲突时保留旧的 sync-token，下次仍能看到这些变化
+    let mut wrote = false;
+    let mut unresolved = false;
+    for action in actions {
+        match action {
+            CalDavAction::Upload { uid, href, etag } => {
+                let event = &events[&uid];
+                let ics = vtodo(event, Utc::now())?;
+                match client.put(&href, &ics, etag.as_deref()).await? {
+                    Written::Done(etag) => {
+                        let etag = match etag {
+                            Some(etag) => etag,
+                            None => client.etag(&href).await?.unwrap_or_default(),
+                        };
+                        let hash = local[&uid].clone();
+                        saved.items.insert(uid, ItemState { href, etag, hash });
+                        report.uploaded += 1;
+                        wrote = true;
+                    }
+                    Written::Conflict => {
+                        report.conflicts.push(event.title.clone());
+                        unresolved = true;
+                    }
+                }
+            }
+            CalDavAction::Download { href, keep_local } => {
+                let Some(resource) = fetched.iter().find(|resource| resource.href == href) else {
+                    // 查询之后被其他客户端删除
+                    unresolved = true;
+                    continue;
+                };
+                let mut guard = state.0.lock().await;
+                let storage = guard.deref_mut();
+                if let Some((uid, item)) = download(storage, list, resource, keep_local)? {
+                    saved.items.insert(uid, item);
+                    report.downloaded += 1;
+                }
+            }
+            CalDavAction::DeleteLocal { uid } => {
+                let mut guard = state.0.lock().await;
+                let storage = guard.deref_mut();
+                remove_event(storage, &uid)?;
+                saved.items.remove(&uid);
+                report.deleted_local += 1;
+            }
+            CalDavAction::DeleteRemote { uid, href, etag } => {
+                match client.delete(&href, &etag).await? {
+                    Written::Done(_) => {
+                        saved.items.remove(&uid);
+                        report.deleted_remote += 1;
+                        wrote = true;
+                    }
+                    Written::Conflict => {
+                        report.conflicts.push(href);
+                        unresolved = true;
+                    }
+                }
+            }
+            CalDavAction::Forget { uid } => {
+                saved.items.remove(&uid);
+            }
+            CalDavAction::Skip { uid } => {
+                let title = events.get(&uid).map_or(uid, |event| event.title.clone());
+                report.conflicts.push(title);
+                unresolved = true;
+            }
+        }
+    }
+
+    saved.ctag = if wrote || unresolved {
+        None
+    } else {
+        calendar.ctag.clone()
+    };
+    if !unresolved && changes.sync_token.is_some() {
+        saved.sync_token = changes.sync_token;
+    }
+    Ok(())
+}
+
+// 删除本地已删除列表对应的远程日历，成功或日历已不存在时清除墓碑，失败时保留墓碑下次重试
+async fn delete_calendars(
+    client: &CalDavClient,
+    calendars: &mut Vec<Calendar>,
+    sync_state: &mut CalDavState,
+    report: &mut CalDavReport,
+) {
+    let deleted: Vec<(String, String)> = sync_state.deleted.drain().collect();
+    for (id, href) in deleted {
+        if !calendars.iter().any(|calendar| calendar.href == href) {
+            continue;
+        }
+        match client.delete_calendar(&href).await {
+            Ok(()) => calendars.retain(|calendar| calendar.href != href),
+            Err(e) => {
+                log::error!("删除远程日历 {} 失败: {}", href, e);
+                report.errors.push(format!("{}: {}", href, e));
+                sync_state.deleted.insert(id, href);
+            }
+        }
+    }
+}
+
+/// 双向同步所有列表与CalDAV服务器上的日历
+///
+/// 每个列表对应日历主集合下的一个日历，按同步记录、名称依次匹配，
+/// 都没有时创建新日历；服务器上其他客户端创建的日历会在本地创建同名列表。
+/// 本地删除的列表会在同步记录中留下墓碑，并删除对应的远程日历，不会被重新创建。
+pub async fn perform_caldav_sync(
+    state: &StorageState,
+    settings: &config::CalDav,
+) -> Result<CalDavReport> {
+    let client = CalDavClient::new(&settings.host, &settings.username, &settings.password).await?;
+    let strategy = ConflictStrategy::from_name(&settings.conflict);
+    let state_path = AppPaths::config_dir().join(STATE_FILE);
+    let mut sync_state = CalDavState::load(&state_path).await;
+    let mut report = CalDavReport::default();
+
+    let mut calendars: Vec<Calendar> = client
+        .calendars()
+        .await?
+        .into_iter()
+        .filter(|calendar| calendar.todo)
+        .collect();
+
+    let lists = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        let mut lists = Repository::<List>::get_all(storage)?;
+        let removed: Vec<String> = sync_state
+            .calendars
+            .keys()
+            .filter(|id| !lists.iter().any(|list| &list.uuid == *id))
+            .cloned()
+            .collect();
+        for id in removed {
+            if let Some(saved) = sync_state.calendars.remove(&id) {
+                sync_state.deleted.insert(id, saved.href);
+            }
+        }
+        for calendar in &calendars {
+            let known = sync_state
+                .calendars
+                .values()
+                .any(|saved| saved.href == calendar.href)
+                || sync_state
+                    .deleted
+                    .values()
+                    .any(|href| href == &calendar.href)
+                || lists
+                    .iter()
+                    .any(|list| list.title.eq_ignore_ascii_case(&calendar.name));
+            if !known {
+                let list = List::new(&calendar.name, "mdi-format-list-bulleted");
+                Repository::<List>::add(storage, &list)?;
+                lists.push(list);
+            }
+        }
+        lists
+    };
+
+    delete_calendars(&client, &mut calendars, &mut sync_state, &mut report).await;
+
+    for list in &lists {
+        let saved = sync_state.calendars.remove(&list.uuid).unwrap_or_default();
+        let matched = calendars
+            .iter()
+            .find(|calendar| calendar.href == saved.href)
+            .or_else(|| {
+                calendars.iter().find(|calendar| {
+                    calendar.name.eq_ignore_ascii_case(&list.title)
+                        && !sync_state
+                            .calendars
+                            .values()
+                            .any(|other| other.href == calendar.href)
+                        && !sync_state
+                            .deleted
+                            .values()
+                            .any(|href| href == &calendar.href)
+                })
+            })
+            .cloned();
+        let calendar = match matched {
+            Some(calendar) => calendar,
+            None => match client.create_calendar(&list.uuid, &list.title).await {
+                Ok(calendar) => calendar,
+                Err(e) => {
+                    report.errors.push(format!("{}: {}", list.title, e));
+                    continue;
+                }
+            },
+        };
+
+        // 对应的日历变了，之前的记录不再有效
+        let mut saved = if saved.href == calendar.href {
+            saved
+        } else {
+            CalendarState {
+                href: calendar.href.clone(),
+                ..Default::default()
+            }
+        };
+        if let Err(e) = sync_calendar(
+            &client,
+            state,
+            list,
+            &calendar,
+            &mut saved,
+            strategy,
+            &mut report,
+        )
+        .await
+        {
+            log::error!("同步列表 {} 失败: {}", list.title, e);
+            report.errors.push(format!("{}: {}", list.title, e));
+        }
+        sync_state.calendars.insert(list.uuid.clone(), saved);
+    }
+
+    sync_state.save(&state_path).await?;
+    clear_all_caches();
+    Ok(report)
+}
+
+/// Synchronizes lists and events with a CalDAV server
+///
+/// Every list is mapped to a calendar collection under the configured calendar
+/// home and every event to a VTODO resource. ETags and the collection's
+/// ctag/sync-token are tracked so that only changed resources are transferred.
+/// Events changed on both sides are resolved with the configured strategy.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+///
+/// # Returns
+/// * `Result<CalDavReport, String>` - Counts of transferred changes and unresolved conflicts, or error message
+#[tauri::command]
+pub async fn caldav_sync(state: State<'_, StorageState>) -> Result<CalDavReport, String> {
+    let settings = config::CalDav::load().map_err(|e| format!("获取CalDAV配置失败: {}", e))?;
+    if !settings.enabled {
+        return Err("CalDAV同步未启用".to_string());
+    }
+    let Some(_guard) = SyncGuard::acquire() else {
+        return Err("CalDAV同步正在进行中".to_string());
+    };
+
+    log::info!("开始CalDAV同步: {}", settings.host);
+    perform_caldav_sync(&state, &settings).await.map_err(|e| {
+        log::error!("CalDAV同步失败: {}", e);
+        format!("CalDAV同步失败: {}", e)
+    })
+}
+
+/// Tests the connection to a CalDAV server
+///
+/// # Parameters
+/// * `host` - URL of the calendar home collection
+/// * `username` - Username for basic authentication
+/// * `password` - Password for basic authentication
+///
+/// # Returns
+/// * `Result<Vec<String>, String>` - Names of the calendars that can hold tasks, or error message
+#[tauri::command]
+pub async fn test_caldav_connection(
+    host: String,
+    username: String,
+    password: String,
+) -> Result<Vec<String>, String> {
+    log::info!("测试CalDAV连接: {}", host);
+    let calendars = async {
+        CalDavClient::new(&host, &username, &password)
+            .await?
+            .calendars()
+            .await
+    }
+    .await
+    .map_err(|e| format!("连接失败: {}", e))?;
+    Ok(calendars
+        .into_iter()
+        .filter(|calendar| calendar.todo)
+        .map(|calendar| calendar.name)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vtodo_uses_source_uid() {
+        let mut event = Event::new("Call back", "");
+        event.metadata.external_id = Some("todo-1@example.com".to_string());
+        let ics = vtodo(&event, Utc::now()).unwrap();
+        assert!(ics.contains("\r\nUID:todo-1@example.com\r\n"));
+        assert!(!ics.contains(&event.metadata.uuid));
+    }
+
+    #[test]
+    fn test_sync_guard_releases_on_panic() {
+        let guard = SyncGuard::acquire().unwrap();
+        assert!(SyncGuard::acquire().is_none());
+        drop(guard);
+
+        let result = std::panic::catch_unwind(|| {
+            let _guard = SyncGuard::acquire().unwrap();
+            panic!("sync failed");
+        });
+        assert!(result.is_err());
+        assert!(SyncGuard::acquire().is_some());
+    }
+
+    #[test]
+    fn test_content_hash_ignores_dtstamp() {
+        let mut event = Event::new("Call back", "");
+        let hash = content_hash(&event).unwrap();
+        assert_eq!(hash.len(), 64, "SHA-256 十六进制摘要");
+        assert_eq!(content_hash(&event).unwrap(), hash);
+        event.finished = true;
+        assert_ne!(content_hash(&event).unwrap(), hash);
+    }
+}
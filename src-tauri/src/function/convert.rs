@@ -0,0 +1,144 @@
+//! 导入与导出的各个格式共用的转换函数
+
+use chrono::{Local, NaiveDate, TimeZone};
+
+/// 简单重复规则的频率，只由 FREQ 与 INTERVAL 组成的 RRULE 才能写入简化的格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    const ALL: [Frequency; 4] = [
+        Frequency::Daily,
+        Frequency::Weekly,
+        Frequency::Monthly,
+        Frequency::Yearly,
+    ];
+
+    fn rrule(self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+
+    /// todo.txt 的 `rec:` 与 org 重复标记使用的单字母单位
+    pub fn letter(self) -> char {
+        match self {
+            Frequency::Daily => 'd',
+            Frequency::Weekly => 'w',
+            Frequency::Monthly => 'm',
+            Frequency::Yearly => 'y',
+        }
+    }
+
+    /// Obsidian Tasks 的 `every` 规则使用的英文单位
+    pub fn word(self) -> &'static str {
+        match self {
+            Frequency::Daily => "day",
+            Frequency::Weekly => "week",
+            Frequency::Monthly => "month",
+            Frequency::Yearly => "year",
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|freq| freq.letter() == letter)
+    }
+
+    pub fn from_word(word: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|freq| freq.word() == word)
+    }
+}
+
+/// 解析只由 FREQ 与 INTERVAL 组成的 RRULE，返回频率与间隔
+///
+/// 带 BYDAY 等限定的规则无法用简化的格式表示，返回 None。
+pub fn parse_simple_rrule(rrule: &str) -> Option<(Frequency, u32)> {
+    let mut freq = None;
+    let mut interval = 1;
+    for part in rrule.split(';') {
+        match part.split_once('=')? {
+            ("FREQ", value) => {
+                freq = Some(Frequency::ALL.into_iter().find(|f| f.rrule() == value)?)
+            }
+            ("INTERVAL", value) => interval = value.parse::<u32>().ok()?,
+            _ => return None,
+        }
+    }
+    Some((freq?, interval))
+}
+
+/// 由频率与间隔生成 RRULE，间隔为 1 时省略 INTERVAL
+pub fn simple_rrule(freq: Frequency, interval: u32) -> String {
+    if interval == 1 {
+        format!("FREQ={}", freq.rrule())
+    } else {
+        format!("FREQ={};INTERVAL={}", freq.rrule(), interval)
+    }
+}
+
+/// 将 `YYYY-MM-DD` 解析为本地零点的毫秒时间戳
+pub fn parse_date(text: &str) -> Option<u64> {
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()?;
+    let local = Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()?;
+    Some(local.timestamp_millis() as u64)
+}
+
+/// 将毫秒时间戳格式化为本地日期
+pub fn local_date(millis: u64) -> Option<String> {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+}
+
+/// 将毫秒时间戳格式化为本地时间
+pub fn local_time(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_rrule_round_trip() {
+        assert_eq!(
+            parse_simple_rrule("FREQ=WEEKLY;INTERVAL=2"),
+            Some((Frequency::Weekly, 2))
+        );
+        assert_eq!(
+            parse_simple_rrule("FREQ=DAILY"),
+            Some((Frequency::Daily, 1))
+        );
+        assert_eq!(parse_simple_rrule("FREQ=WEEKLY;BYDAY=MO"), None);
+        assert_eq!(parse_simple_rrule("FREQ=HOURLY"), None);
+        assert_eq!(parse_simple_rrule("INTERVAL=2"), None);
+
+        for rrule in ["FREQ=MONTHLY", "FREQ=YEARLY;INTERVAL=10"] {
+            let (freq, interval) = parse_simple_rrule(rrule).unwrap();
+            assert_eq!(simple_rrule(freq, interval), rrule);
+        }
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let millis = parse_date("2026-10-18").unwrap();
+        assert_eq!(local_date(millis).as_deref(), Some("2026-10-18"));
+        assert_eq!(local_time(millis), "2026-10-18 00:00");
+        assert_eq!(parse_date("2026-13-01"), None);
+    }
+}
@@ -1,27 +1,98 @@
-mod ics;
-mod json;
-mod md;
+pub mod csv;
+pub mod html;
+pub mod ics;
+pub mod json;
+pub mod md;
+pub mod org;
+pub mod pipeline;
 pub mod save;
+pub mod template;
+pub mod todotxt;
 
-use self::ics::*;
-use self::json::*;
-use self::md::*;
+use self::csv::*;
+use self::html::*;
+use self::pipeline::{EventSelector, ExportFormat, ExportSink, Exporter, Registry};
 
-use crate::entity::{Event, Repository, StorageState};
+use crate::entity::StorageState;
 use anyhow::Result;
 use serde_json;
+use std::fs;
 use std::ops::DerefMut;
+use std::path::PathBuf;
 use tauri::State;
 
+/// Exports events through the unified export pipeline
+///
+/// Selects events with `selector`, renders them with the exporter registered under
+/// `format`, and sends the result to `sink`. File sinks stream the output to disk
+/// and only replace the target once the export has completed.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `selector` - Which events to export: all, IDs, a list, a status, a date range,
+///   a filter query such as "today", or a search keyword
+/// * `format` - Name of a registered format, see `list_export_formats`
+/// * `sink` - Where the output goes: returned as a string, saved to the export
+///   directory under a file name, or saved to a file path
+///
+/// # Returns
+/// * `Result<String, String>` - Exported content for `Return`, otherwise the saved path, or error message
+#[tauri::command]
+pub async fn export(
+    state: State<'_, StorageState>,
+    selector: EventSelector,
+    format: String,
+    sink: ExportSink,
+) -> Result<String, String> {
+    let registry = Registry::with_templates();
+    let exporter = registry
+        .get(&format)
+        .ok_or_else(|| "Unsupported format".to_string())?;
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    pipeline::run(storage, exporter, &selector, &sink)
+}
+
+/// Lists the formats accepted by the export commands
+///
+/// Includes the built-in formats and one `template:<name>` entry for each user
+/// template, together with the file extension of the output.
+///
+/// # Returns
+/// * `Result<Vec<ExportFormat>, String>` - Available formats or error message
+#[tauri::command]
+pub async fn list_export_formats() -> Result<Vec<ExportFormat>, String> {
+    Ok(Registry::with_templates().formats())
+}
+
+/// 通过导出管道将选中的事件以字符串返回
+async fn export_to_string(
+    state: State<'_, StorageState>,
+    selector: EventSelector,
+    fmt: &str,
+) -> Result<String, String> {
+    export(state, selector, fmt.to_string(), ExportSink::Return).await
+}
+
+/// 由可选的事件ID与列表ID确定要导出的事件，都未给出时导出全部事件
+fn selector(event_ids: Option<Vec<String>>, list_id: Option<String>) -> EventSelector {
+    match (event_ids, list_id) {
+        (Some(ids), _) => EventSelector::Ids(ids),
+        (None, Some(list_id)) => EventSelector::List(list_id),
+        (None, None) => EventSelector::All,
+    }
+}
+
 /// Exports specific events in the requested format
 ///
 /// Takes a list of event IDs and exports them in the specified format.
-/// Supports exporting to ICS, Markdown, or JSON formats.
+/// Kept for compatibility, equivalent to `export` with an ID selector.
 ///
 /// # Parameters
 /// * `state` - Application state containing the database connection
 /// * `event_ids` - JSON value containing event IDs to export (string or array of strings)
-/// * `fmt` - Format to export to: "ics", "md", or "json"
+/// * `fmt` - Name of a registered format, e.g. "ics", "ics-event", "md", "json", "csv",
+///   "todotxt", "org", "html", "tasks", or "template:<name>"
 ///
 /// # Returns
 /// * `Result<String, String>` - Exported content as string or error message
@@ -31,8 +102,6 @@ pub async fn export_events(
     event_ids: serde_json::Value,
     fmt: &str,
 ) -> Result<String, String> {
-    let mut guard = state.0.lock().await;
-    let storage = guard.deref_mut();
     let event_ids: Vec<String> = if event_ids.is_string() {
         vec![event_ids.as_str().unwrap_or("").to_string()]
     } else if event_ids.is_array() {
@@ -45,34 +114,19 @@ pub async fn export_events(
     } else {
         return Err("Invalid event IDs".to_string());
     };
-    let events: Vec<Event> = event_ids
-        .iter()
-        .filter_map(|id| match Repository::<Event>::get_by_name(storage, id) {
-            Ok(Some(event)) => Some(event),
-            Ok(None) => None,
-            Err(e) => {
-                eprintln!("获取事件 {} 时出错: {}", id, e);
-                None
-            }
-        })
-        .collect();
-    match fmt {
-        "ics" => export_events_to_ics(events),
-        "md" => export_events_to_md(events),
-        "json" => export_events_to_json(events),
-        _ => Err("Unsupported format".to_string()),
-    }
+    export_to_string(state, EventSelector::Ids(event_ids), fmt).await
 }
 
 /// Exports all events from a specific list in the requested format
 ///
 /// Retrieves all events belonging to the specified list and exports them
-/// in the requested format.
+/// in the requested format. Kept for compatibility, equivalent to `export`
+/// with a list selector.
 ///
 /// # Parameters
 /// * `state` - Application state containing the database connection
 /// * `list_id` - UUID of the list containing events to export
-/// * `fmt` - Format to export to: "ics", "md", or "json"
+/// * `fmt` - Name of a registered format, see `list_export_formats`
 ///
 /// # Returns
 /// * `Result<String, String>` - Exported content as string or error message
@@ -82,20 +136,17 @@ pub async fn export_list_events(
     list_id: &str,
     fmt: &str,
 ) -> Result<String, String> {
-    match fmt {
-        "ics" => export_list_events_to_ics(state, list_id).await,
-        "md" => export_list_events_to_md(state, list_id).await,
-        "json" => export_list_events_to_json(state, list_id).await,
-        _ => Err("Unsupported format".to_string()),
-    }
+    export_to_string(state, EventSelector::List(list_id.to_string()), fmt).await
 }
+
 /// Exports all events in the database in the requested format
 ///
 /// Retrieves all events from the database and exports them in the specified format.
+/// Kept for compatibility, equivalent to `export` with the `All` selector.
 ///
 /// # Parameters
 /// * `state` - Application state containing the database connection
-/// * `fmt` - Format to export to: "ics", "md", or "json"
+/// * `fmt` - Name of a registered format, see `list_export_formats`
 ///
 /// # Returns
 /// * `Result<String, String>` - Exported content as string or error message
@@ -104,24 +155,20 @@ pub async fn export_all_events(
     state: State<'_, StorageState>,
     fmt: &str,
 ) -> Result<String, String> {
-    match fmt {
-        "ics" => export_all_events_to_ics(state).await,
-        "md" => export_all_events_to_md(state).await,
-        "json" => export_all_events_to_json(state).await,
-        _ => Err("Unsupported format".to_string()),
-    }
+    export_to_string(state, EventSelector::All, fmt).await
 }
 
 /// Exports events within a specific date range in the requested format
 ///
 /// Retrieves events that fall within the specified timestamp range and
-/// exports them in the requested format.
+/// exports them in the requested format. Kept for compatibility, equivalent
+/// to `export` with a date range selector.
 ///
 /// # Parameters
 /// * `state` - Application state containing the database connection
 /// * `start` - Start timestamp (Unix milliseconds)
 /// * `end` - End timestamp (Unix milliseconds)
-/// * `fmt` - Format to export to: "ics", "md", or "json"
+/// * `fmt` - Name of a registered format, see `list_export_formats`
 ///
 /// # Returns
 /// * `Result<String, String>` - Exported content as string or error message
@@ -132,23 +179,19 @@ pub async fn export_events_by_date_range(
     end: u64,
     fmt: &str,
 ) -> Result<String, String> {
-    match fmt {
-        "ics" => export_events_by_date_range_to_ics(state, start, end).await,
-        "md" => export_events_by_date_range_to_md(state, start, end).await,
-        "json" => export_events_by_date_range_to_json(state, start, end).await,
-        _ => Err("Unsupported format".to_string()),
-    }
+    export_to_string(state, EventSelector::DateRange { start, end }, fmt).await
 }
 
 /// Exports events with a specific completion status in the requested format
 ///
 /// Retrieves events with the specified completion status (completed or not completed)
-/// and exports them in the requested format.
+/// and exports them in the requested format. Kept for compatibility, equivalent
+/// to `export` with a status selector.
 ///
 /// # Parameters
 /// * `state` - Application state containing the database connection
 /// * `status` - Completion status to filter by (true = completed, false = not completed)
-/// * `fmt` - Format to export to: "ics", "md", or "json"
+/// * `fmt` - Name of a registered format, see `list_export_formats`
 ///
 /// # Returns
 /// * `Result<String, String>` - Exported content as string or error message
@@ -158,10 +201,97 @@ pub async fn export_events_by_status(
     status: bool,
     fmt: &str,
 ) -> Result<String, String> {
-    match fmt {
-        "ics" => export_events_by_status_to_ics(state, status).await,
-        "md" => export_events_by_status_to_md(state, status).await,
-        "json" => export_events_by_status_to_json(state, status).await,
-        _ => Err("Unsupported format".to_string()),
+    export_to_string(state, EventSelector::Status(status), fmt).await
+}
+
+/// Exports events as CSV with the chosen columns
+///
+/// Exports the given events, or all events of a list, or all events when neither
+/// is given. Dates are written in local time as `YYYY-MM-DD HH:MM`, tags are
+/// separated by semicolons.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `event_ids` - Optional IDs of the events to export
+/// * `list_id` - Optional UUID of a list whose events should be exported
+/// * `columns` - Columns in output order; empty for title, list, tags, priority, due, finished and created
+///
+/// # Returns
+/// * `Result<String, String>` - CSV content as string or error message
+#[tauri::command]
+pub async fn export_csv(
+    state: State<'_, StorageState>,
+    event_ids: Option<Vec<String>>,
+    list_id: Option<String>,
+    columns: Vec<CsvColumn>,
+) -> Result<String, String> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let exporter = Exporter::new("csv", "csv", move |storage, selection, out| {
+        write_events_to_csv(storage, &selection.events, &columns, out)
+    });
+    pipeline::run(
+        storage,
+        &exporter,
+        &selector(event_ids, list_id),
+        &ExportSink::Return,
+    )
+}
+
+/// Exports events as a small static site with copied attachments
+///
+/// Renders the given events, or all events of a list, or all events when neither
+/// is given, into `index.html` inside a new folder named after `filename`.
+/// Attachments referenced from the markdown are copied into its `assets` folder
+/// instead of being embedded, which keeps the page small for events with large files.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `event_ids` - Optional IDs of the events to export
+/// * `list_id` - Optional UUID of a list whose events should be exported
+/// * `filename` - Name of the folder to create
+/// * `custom_path` - Optional directory to create the folder in, defaults to the export directory
+///
+/// # Returns
+/// * `Result<String, String>` - Path to the saved `index.html` or error message
+#[tauri::command]
+pub async fn export_html_site(
+    state: State<'_, StorageState>,
+    event_ids: Option<Vec<String>>,
+    list_id: Option<String>,
+    filename: String,
+    custom_path: Option<String>,
+) -> Result<String, String> {
+    let page = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        let selection = selector(event_ids, list_id).select(storage)?;
+        render_events_to_html(storage, &selection.events, AttachmentMode::Copy)?
+    };
+
+    let base = match custom_path {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(save::get_export_directory().await?),
+    };
+    let site = base.join(save::sanitize_filename(&filename));
+    for (source, target) in &page.assets {
+        let target = target
+            .split('/')
+            .map(|part| urlencoding::decode(part).map(|part| part.into_owned()))
+            .collect::<Result<PathBuf, _>>()
+            .map_err(|e| e.to_string())?;
+        let target = site.join(target);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("无法创建目录: {}", e))?;
+        }
+        fs::copy(source, &target).map_err(|e| format!("复制附件失败: {}", e))?;
     }
+    fs::create_dir_all(&site).map_err(|e| format!("无法创建目录: {}", e))?;
+    save::save_export_file(
+        page.html,
+        "index".to_string(),
+        "html".to_string(),
+        site.to_str().map(str::to_string),
+    )
+    .await
 }
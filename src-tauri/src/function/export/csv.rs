@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use ts_rs::TS;
+
+use crate::entity::event::Priority;
+use crate::entity::{Event, List, Repository, Storage};
+use crate::function::convert::local_time;
+
+/// CSV导出与导入时可用的列
+#[derive(Serialize, Deserialize, TS, Clone, Copy, Debug, PartialEq)]
+#[ts(export)]
+pub enum CsvColumn {
+    Title,
+    List,     // 列表名称
+    Tags,     // 以分号分隔
+    Priority, // High、Medium、Low，未设置时为空
+    Due,      // 本地时间 `YYYY-MM-DD HH:MM`
+    Finished, // true 或 false
+    Created,  // 本地时间 `YYYY-MM-DD HH:MM`
+    Content,  // Markdown内容
+    Id,       // 事件UUID，导入时用于去重
+}
+
+impl CsvColumn {
+    /// 未指定列时导出的列，不包含内容与UUID
+    pub const DEFAULT: &'static [CsvColumn] = &[
+        CsvColumn::Title,
+        CsvColumn::List,
+        CsvColumn::Tags,
+        CsvColumn::Priority,
+        CsvColumn::Due,
+        CsvColumn::Finished,
+        CsvColumn::Created,
+    ];
+
+    /// 表头中的列名
+    pub fn header(&self) -> &'static str {
+        match self {
+            CsvColumn::Title => "Title",
+            CsvColumn::List => "List",
+            CsvColumn::Tags => "Tags",
+            CsvColumn::Priority => "Priority",
+            CsvColumn::Due => "Due",
+            CsvColumn::Finished => "Finished",
+            CsvColumn::Created => "Created",
+            CsvColumn::Content => "Content",
+            CsvColumn::Id => "Id",
+        }
+    }
+}
+
+fn cell(event: &Event, column: CsvColumn, lists: &HashMap<String, String>) -> String {
+    match column {
+        CsvColumn::Title => event.title.clone(),
+        CsvColumn::List => event
+            .metadata
+            .list
+            .as_ref()
+            .and_then(|id| lists.get(id))
+            .cloned()
+            .unwrap_or_default(),
+        CsvColumn::Tags => event.metadata.tag.clone().unwrap_or_default().join(";"),
+        CsvColumn::Priority => match event.priority {
+            Priority::High => "High",
+            Priority::Medium => "Medium",
+            Priority::Low => "Low",
+            Priority::Undefined => "",
+        }
+        .to_string(),
+        CsvColumn::Due => event.task_time.map(local_time).unwrap_or_default(),
+        CsvColumn::Finished => event.finished.to_string(),
+        CsvColumn::Created => local_time(event.metadata.timestamp),
+        CsvColumn::Content => fs::read_to_string(&event.content).unwrap_or_default(),
+        CsvColumn::Id => event.metadata.uuid.clone(),
+    }
+}
+
+/// 将事件写为CSV，第一行为表头
+pub fn write_csv(
+    out: &mut dyn Write,
+    events: &[Event],
+    columns: &[CsvColumn],
+    lists: &HashMap<String, String>,
+) -> Result<(), String> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer
+        .write_record(columns.iter().map(|c| c.header()))
+        .map_err(|e| e.to_string())?;
+    for event in events {
+        writer
+            .write_record(columns.iter().map(|&c| cell(event, c, lists)))
+            .map_err(|e| e.to_string())?;
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// 导出多个事件为CSV格式，`columns` 为空时使用默认列
+pub fn write_events_to_csv(
+    storage: &Storage,
+    events: &[Event],
+    columns: &[CsvColumn],
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let lists: HashMap<String, String> = Repository::<List>::get_all(storage)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|list| (list.uuid, list.title))
+        .collect();
+    let columns = if columns.is_empty() {
+        CsvColumn::DEFAULT
+    } else {
+        columns
+    };
+    write_csv(out, events, columns, &lists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::export::pipeline::collect;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn test_write_csv() {
+        let mut event = Event::new("Buy milk, eggs", "");
+        event.metadata.timestamp = Local
+            .with_ymd_and_hms(2026, 10, 10, 9, 30, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        event.metadata.list = Some("home-id".to_string());
+        event.metadata.tag = Some(vec!["errand".to_string(), "food".to_string()]);
+        event.priority = Priority::Medium;
+        event.task_time = Some(
+            Local
+                .with_ymd_and_hms(2026, 10, 20, 18, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        );
+        let mut done = Event::new("Say \"hi\"", "");
+        done.finished = true;
+        let lists = HashMap::from([("home-id".to_string(), "Home".to_string())]);
+
+        let events = [event, done.clone()];
+        let text = collect(|out| write_csv(out, &events, CsvColumn::DEFAULT, &lists)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Title,List,Tags,Priority,Due,Finished,Created");
+        assert_eq!(
+            lines[1],
+            "\"Buy milk, eggs\",Home,errand;food,Medium,2026-10-20 18:00,false,2026-10-10 09:30"
+        );
+        assert!(lines[2].starts_with("\"Say \"\"hi\"\"\",,,,,true,"));
+
+        let columns = [CsvColumn::Id, CsvColumn::Title];
+        let text =
+            collect(|out| write_csv(out, std::slice::from_ref(&done), &columns, &lists)).unwrap();
+        assert_eq!(
+            text,
+            format!("Id,Title\n{},\"Say \"\"hi\"\"\"\n", done.metadata.uuid)
+        );
+    }
+}
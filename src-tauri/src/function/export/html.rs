@@ -0,0 +1,507 @@
+use base64::{engine::general_purpose, Engine as _};
+use pulldown_cmark::{html, CowStr, Event as MdEvent, Options, Parser, Tag};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+use crate::entity::event::Priority;
+use crate::entity::{Event, List, Repository, Storage};
+use crate::function::convert::local_time;
+
+/// 附件的处理方式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AttachmentMode {
+    Inline, // 以data URI嵌入页面，生成单个自包含文件
+    Copy,   // 复制到页面旁的 `assets` 目录
+}
+
+/// 渲染结果，`assets` 为需要复制的附件及其相对于页面的路径
+pub struct HtmlPage {
+    pub html: String,
+    pub assets: Vec<(PathBuf, String)>,
+}
+
+const STYLE: &str = r#"
+body { margin: 0 auto; max-width: 860px; padding: 24px; font-family: -apple-system, "Segoe UI", "PingFang SC", "Microsoft YaHei", sans-serif; line-height: 1.6; color: #24292f; }
+header { border-bottom: 1px solid #d0d7de; margin-bottom: 16px; }
+header p, .meta { color: #57606a; font-size: 0.9em; }
+nav a { margin-right: 12px; }
+h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 4px; }
+article { border: 1px solid #d0d7de; border-left: 4px solid #8c959f; border-radius: 6px; margin: 12px 0; padding: 8px 16px; }
+article.high { border-left-color: #cf222e; }
+article.medium { border-left-color: #bf8700; }
+article.low { border-left-color: #1a7f37; }
+article.done h4 { text-decoration: line-through; color: #57606a; }
+.tag { background: #eaeef2; border-radius: 10px; margin-right: 4px; padding: 0 8px; }
+img { max-width: 100%; }
+pre { background: #f6f8fa; overflow: auto; padding: 8px; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #d0d7de; padding: 4px 8px; }
+"#;
+
+/// 转义HTML中的特殊字符
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "txt" | "md" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 将指向事件目录内文件的相对链接解析为相对路径，外部链接、锚点与越出目录的路径返回 `None`
+fn relative_path(url: &str) -> Option<PathBuf> {
+    let url = url.split(['#', '?']).next()?;
+    if url.is_empty() || url.starts_with('/') || url.contains(':') {
+        return None;
+    }
+    let decoded = urlencoding::decode(url).ok()?;
+    let mut path = PathBuf::new();
+    for component in Path::new(decoded.as_ref()).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+/// 链接只允许http(s)、mailto与相对路径，其余协议（如 `javascript:`）在导出页面中被替换为 `#`
+fn is_safe_url(url: &str) -> bool {
+    let Some(colon) = url.find(':') else {
+        return true;
+    };
+    if url[..colon].contains(['/', '?', '#']) {
+        return true;
+    }
+    let scheme = url[..colon].to_ascii_lowercase();
+    matches!(scheme.as_str(), "http" | "https" | "mailto")
+}
+
+/// 改写附件链接，内联模式返回data URI，复制模式记录附件并返回其在 `assets` 中的位置
+fn rewrite_url(
+    url: &str,
+    dir: &Path,
+    uuid: &str,
+    mode: AttachmentMode,
+    assets: &mut Vec<(PathBuf, String)>,
+) -> Option<String> {
+    let relative = relative_path(url)?;
+    let source = dir.join(&relative);
+    if !source.is_file() {
+        return None;
+    }
+    match mode {
+        AttachmentMode::Inline => {
+            let data = fs::read(&source).ok()?;
+            Some(format!(
+                "data:{};base64,{}",
+                mime_type(&source),
+                general_purpose::STANDARD.encode(data)
+            ))
+        }
+        AttachmentMode::Copy => {
+            let target = std::iter::once(uuid.to_string())
+                .chain(
+                    relative
+                        .iter()
+                        .map(|part| urlencoding::encode(&part.to_string_lossy()).into_owned()),
+                )
+                .collect::<Vec<_>>()
+                .join("/");
+            let target = format!("assets/{}", target);
+            if !assets.iter().any(|(path, _)| path == &source) {
+                assets.push((source, target.clone()));
+            }
+            Some(target)
+        }
+    }
+}
+
+/// 处理链接与图片地址：不安全的协议替换为 `#`，内容目录内的附件按 `mode` 改写
+fn resolve_url<'a>(
+    url: CowStr<'a>,
+    dir: Option<&Path>,
+    uuid: &str,
+    mode: AttachmentMode,
+    assets: &mut Vec<(PathBuf, String)>,
+) -> CowStr<'a> {
+    if !is_safe_url(&url) {
+        return CowStr::Borrowed("#");
+    }
+    dir.and_then(|dir| rewrite_url(&url, dir, uuid, mode, assets))
+        .map(CowStr::from)
+        .unwrap_or(url)
+}
+
+/// 将事件的Markdown内容渲染为HTML，并处理其中引用的附件
+fn render_markdown(
+    event: &Event,
+    mode: AttachmentMode,
+    assets: &mut Vec<(PathBuf, String)>,
+) -> String {
+    let content_path = Path::new(&event.content);
+    // 只有内容文件存在时才解析附件，避免相对链接落到当前工作目录
+    let (markdown, dir) = match fs::read_to_string(content_path) {
+        Ok(markdown) => (markdown, content_path.parent()),
+        Err(_) => (event.content.clone(), None),
+    };
+
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(&markdown, options).map(|md_event| {
+        // 内容中的原始HTML按文本输出，导出页面不执行其中的脚本
+        let md_event = match md_event {
+            MdEvent::Html(raw) | MdEvent::InlineHtml(raw) => return MdEvent::Text(raw),
+            other => other,
+        };
+        let mut resolve = |url| resolve_url(url, dir, &event.metadata.uuid, mode, assets);
+        match md_event {
+            MdEvent::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => MdEvent::Start(Tag::Image {
+                link_type,
+                dest_url: resolve(dest_url),
+                title,
+                id,
+            }),
+            MdEvent::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => MdEvent::Start(Tag::Link {
+                link_type,
+                dest_url: resolve(dest_url),
+                title,
+                id,
+            }),
+            other => other,
+        }
+    });
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, parser);
+    rendered
+}
+
+/// 渲染单个事件卡片
+fn render_event(
+    page: &mut String,
+    event: &Event,
+    mode: AttachmentMode,
+    assets: &mut Vec<(PathBuf, String)>,
+) {
+    let priority = match event.priority {
+        Priority::High => Some(("high", "高")),
+        Priority::Medium => Some(("medium", "中")),
+        Priority::Low => Some(("low", "低")),
+        Priority::Undefined => None,
+    };
+    let mut class = priority.map(|(class, _)| class).unwrap_or("").to_string();
+    if event.finished {
+        class = format!("{} done", class).trim().to_string();
+    }
+
+    page.push_str(&format!(
+        "<article id=\"event-{}\" class=\"{}\">\n<h4>{}</h4>\n<p class=\"meta\">",
+        escape(&event.metadata.uuid),
+        class,
+        escape(&event.title)
+    ));
+    let mut meta = Vec::new();
+    if let Some(task_time) = event.task_time {
+        meta.push(format!("截止时间：{}", local_time(task_time)));
+    }
+    if let Some((_, label)) = priority {
+        meta.push(format!("优先级：{}", label));
+    }
+    if let Some(recurrence) = &event.recurrence {
+        meta.push(format!("重复：<code>{}</code>", escape(recurrence)));
+    }
+    meta.push(format!(
+        "创建时间：{}",
+        local_time(event.metadata.timestamp)
+    ));
+    page.push_str(&meta.join(" · "));
+    page.push_str("</p>\n");
+
+    if let Some(tags) = event.metadata.tag.as_ref().filter(|tags| !tags.is_empty()) {
+        page.push_str("<p>");
+        for tag in tags {
+            page.push_str(&format!("<span class=\"tag\">{}</span>", escape(tag)));
+        }
+        page.push_str("</p>\n");
+    }
+
+    page.push_str(&render_markdown(event, mode, assets));
+    page.push_str("</article>\n");
+}
+
+/// 将事件渲染为完整的HTML页面
+///
+/// 事件按列表分组，列表按名称排序，未归属列表的事件放在最后；
+/// 每个列表内先列出进行中的事件，再列出已完成的事件，各自按截止时间排序。
+pub fn render_page(
+    events: &[Event],
+    lists: &HashMap<String, String>,
+    mode: AttachmentMode,
+    exported_at: u64,
+) -> HtmlPage {
+    let mut groups: Vec<(Option<&str>, Vec<&Event>)> = Vec::new();
+    for event in events {
+        let list = event
+            .metadata
+            .list
+            .as_deref()
+            .and_then(|id| lists.get(id))
+            .map(String::as_str);
+        match groups.iter_mut().find(|(title, _)| *title == list) {
+            Some((_, group)) => group.push(event),
+            None => groups.push((list, vec![event])),
+        }
+    }
+    groups.sort_by_key(|(title, _)| (title.is_none(), *title));
+
+    let mut page = String::from(
+        "<!DOCTYPE html>\n<html lang=\"zh-CN\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>ToDoPulse 导出</title>\n<style>",
+    );
+    page.push_str(STYLE);
+    page.push_str("</style>\n</head>\n<body>\n<header>\n<h1>ToDoPulse</h1>\n");
+    page.push_str(&format!(
+        "<p>导出时间：{} · 共 {} 个事件</p>\n</header>\n<nav>\n",
+        local_time(exported_at),
+        events.len()
+    ));
+    for (idx, (title, _)) in groups.iter().enumerate() {
+        page.push_str(&format!(
+            "<a href=\"#list-{}\">{}</a>\n",
+            idx,
+            escape(title.unwrap_or("未分类"))
+        ));
+    }
+    page.push_str("</nav>\n");
+
+    let mut assets = Vec::new();
+    for (idx, (title, mut group)) in groups.into_iter().enumerate() {
+        group.sort_by_key(|event| (event.task_time.is_none(), event.task_time));
+        page.push_str(&format!(
+            "<section id=\"list-{}\">\n<h2>{}</h2>\n",
+            idx,
+            escape(title.unwrap_or("未分类"))
+        ));
+        for (finished, heading) in [(false, "进行中"), (true, "已完成")] {
+            let events: Vec<&&Event> = group
+                .iter()
+                .filter(|event| event.finished == finished)
+                .collect();
+            if events.is_empty() {
+                continue;
+            }
+            page.push_str(&format!("<h3>{}（{}）</h3>\n", heading, events.len()));
+            for event in events {
+                render_event(&mut page, event, mode, &mut assets);
+            }
+        }
+        page.push_str("</section>\n");
+    }
+    page.push_str("</body>\n</html>\n");
+
+    HtmlPage { html: page, assets }
+}
+
+/// 将事件渲染为HTML页面，列表名称从存储中读取
+pub fn render_events_to_html(
+    storage: &Storage,
+    events: &[Event],
+    mode: AttachmentMode,
+) -> Result<HtmlPage, String> {
+    let lists: HashMap<String, String> = Repository::<List>::get_all(storage)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|list| (list.uuid, list.title))
+        .collect();
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+    Ok(render_page(events, &lists, mode, now))
+}
+
+/// 导出多个事件为自包含的HTML文件，附件以data URI嵌入
+pub fn write_html(storage: &Storage, events: &[Event], out: &mut dyn Write) -> Result<(), String> {
+    let page = render_events_to_html(storage, events, AttachmentMode::Inline)?;
+    out.write_all(page.html.as_bytes())
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event_with_attachment(dir: &Path) -> Event {
+        fs::write(dir.join("photo one.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        fs::write(
+            dir.join("content.md"),
+            "Some **bold** text\n\n![shot](photo%20one.png)\n[notes](../secret.txt) [site](https://example.com)\n",
+        )
+        .unwrap();
+        let mut event = Event::new("Fix <script>", dir.join("content.md").to_str().unwrap());
+        event.priority = Priority::High;
+        event.metadata.tag = Some(vec!["a&b".to_string()]);
+        event
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("./a/b.png"), Some(PathBuf::from("a/b.png")));
+        assert_eq!(relative_path("a%20b.png#x"), Some(PathBuf::from("a b.png")));
+        assert_eq!(relative_path("../b.png"), None);
+        assert_eq!(relative_path("/etc/passwd"), None);
+        assert_eq!(relative_path("https://example.com/a.png"), None);
+        assert_eq!(relative_path("#heading"), None);
+    }
+
+    #[test]
+    fn test_is_safe_url() {
+        assert!(is_safe_url("https://example.com"));
+        assert!(is_safe_url("HTTP://example.com"));
+        assert!(is_safe_url("mailto:a@example.com"));
+        assert!(is_safe_url("a/b.png"));
+        assert!(is_safe_url("#heading"));
+        assert!(is_safe_url("./a:b.png"));
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url("JavaScript:alert(1)"));
+        assert!(!is_safe_url("data:text/html,<script>"));
+        assert!(!is_safe_url("vbscript:msgbox"));
+    }
+
+    #[test]
+    fn test_render_neutralizes_script_urls() {
+        let mut assets = Vec::new();
+        let event = Event::new(
+            "Links",
+            "[x](javascript:alert(1)) ![y](javascript:alert(2)) [ok](https://example.com)\n",
+        );
+        let html = render_markdown(&event, AttachmentMode::Inline, &mut assets);
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("<a href=\"#\">x</a>"));
+        assert!(html.contains("<img src=\"#\" alt=\"y\" />"));
+        assert!(html.contains("href=\"https://example.com\""));
+
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("content.md"), "[x](javascript:alert(1))\n").unwrap();
+        let event = Event::new("Links", dir.path().join("content.md").to_str().unwrap());
+        let html = render_markdown(&event, AttachmentMode::Copy, &mut assets);
+        assert!(html.contains("<a href=\"#\">x</a>"));
+    }
+
+    #[test]
+    fn test_render_inline() {
+        let dir = tempfile::tempdir().unwrap();
+        let event = event_with_attachment(dir.path());
+        let mut assets = Vec::new();
+        let html = render_markdown(&event, AttachmentMode::Inline, &mut assets);
+        assert!(html.contains("<strong>bold</strong>"));
+        assert!(html.contains("src=\"data:image/png;base64,iVBORw==\""));
+        assert!(html.contains("href=\"../secret.txt\""));
+        assert!(html.contains("href=\"https://example.com\""));
+        assert!(assets.is_empty());
+
+        let mut page = String::new();
+        render_event(&mut page, &event, AttachmentMode::Inline, &mut assets);
+        assert!(page.contains("<h4>Fix &lt;script&gt;</h4>"));
+        assert!(page.contains("class=\"high\""));
+        assert!(page.contains("<span class=\"tag\">a&amp;b</span>"));
+    }
+
+    #[test]
+    fn test_render_escapes_raw_html() {
+        let mut assets = Vec::new();
+        let event = Event::new(
+            "Raw",
+            "<script>alert(1)</script>\n\nHi <b onclick=\"x()\">there</b>\n",
+        );
+        let html = render_markdown(&event, AttachmentMode::Inline, &mut assets);
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<b "));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains("Hi &lt;b onclick=\"x()\"&gt;there&lt;/b&gt;"));
+    }
+
+    #[test]
+    fn test_render_copy() {
+        let dir = tempfile::tempdir().unwrap();
+        let event = event_with_attachment(dir.path());
+        let page = render_page(
+            std::slice::from_ref(&event),
+            &HashMap::new(),
+            AttachmentMode::Copy,
+            0,
+        );
+        let target = format!("assets/{}/photo%20one.png", event.metadata.uuid);
+        assert!(page.html.contains(&format!("src=\"{}\"", target)));
+        assert_eq!(
+            page.assets,
+            vec![(dir.path().join("photo one.png"), target)]
+        );
+    }
+
+    #[test]
+    fn test_render_page_groups() {
+        let lists = HashMap::from([
+            ("work".to_string(), "Work".to_string()),
+            ("home".to_string(), "Home".to_string()),
+        ]);
+        let mut loose = Event::new("Loose", "");
+        loose.metadata.list = None;
+        let mut done = Event::new("Done at work", "");
+        done.metadata.list = Some("work".to_string());
+        done.finished = true;
+        let mut open = Event::new("Open at work", "");
+        open.metadata.list = Some("work".to_string());
+        let mut home = Event::new("At home", "");
+        home.metadata.list = Some("home".to_string());
+
+        let page = render_page(
+            &[loose, done, open, home],
+            &lists,
+            AttachmentMode::Inline,
+            0,
+        )
+        .html;
+        let position = |needle: &str| page.find(needle).unwrap();
+        assert!(position("<h2>Home</h2>") < position("<h2>Work</h2>"));
+        assert!(position("<h2>Work</h2>") < position("<h2>未分类</h2>"));
+        assert!(position("Open at work") < position("<h3>已完成（1）</h3>"));
+        assert!(position("<h3>已完成（1）</h3>") < position("Done at work"));
+        assert!(page.contains("共 4 个事件"));
+    }
+}
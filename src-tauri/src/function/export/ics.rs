@@ -1,65 +1,152 @@
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use std::fs;
-use std::ops::DerefMut;
-use tauri::State;
+use std::io::Write;
 
 use crate::entity::event::Priority;
-use crate::entity::{Event, Repository, StorageState};
-
-/// 将单个 Event 添加到 ICS 文本中
-fn export_event_to_ics(ics: &mut String, event: &Event) -> Result<(), String> {
-    // 事件开始
-    ics.push_str("BEGIN:VEVENT\r\n");
+use crate::entity::Event;
+
+/// ICS 导出方式
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IcsMode {
+    /// 导出为待办（VTODO），保留截止时间与完成状态
+    Todo,
+    /// 导出为日历事件（VEVENT），用于日历订阅，没有截止时间的事件被跳过
+    Event,
+}
 
-    // UID (使用 event 的 uuid)
-    ics.push_str(&format!("UID:{}\r\n", event.metadata.uuid));
+/// 内容行最多 75 个字节（不含换行），超出部分折到以空格开头的续行
+const MAX_LINE_OCTETS: usize = 75;
+
+/// 写入一行 `NAME:value` 内容并按 RFC 5545 折行，不会在 UTF-8 字符中间断开
+fn push_line(ics: &mut String, name: &str, value: &str) {
+    let line = format!("{}:{}", name, value);
+    let mut limit = MAX_LINE_OCTETS;
+    let mut start = 0;
+    let mut len = 0;
+    for (i, c) in line.char_indices() {
+        if len + c.len_utf8() > limit {
+            ics.push_str(&line[start..i]);
+            ics.push_str("\r\n ");
+            start = i;
+            len = 0;
+            // 续行开头的空格占一个字节
+            limit = MAX_LINE_OCTETS - 1;
+        }
+        len += c.len_utf8();
+    }
+    ics.push_str(&line[start..]);
+    ics.push_str("\r\n");
+}
 
-    // 创建时间戳
-    let created = Utc
-        .timestamp_millis_opt(event.metadata.timestamp as i64)
+fn utc_time(millis: u64) -> Result<String, String> {
+    Utc.timestamp_millis_opt(millis as i64)
         .single()
-        .ok_or_else(|| "无效的时间戳".to_string())?;
-    ics.push_str(&format!("DTSTAMP:{}\r\n", created.format("%Y%m%dT%H%M%SZ")));
+        .map(|dt| dt.format("%Y%m%dT%H%M%SZ").to_string())
+        .ok_or_else(|| "无效的时间戳".to_string())
+}
 
-    // 标题
-    let summary = escape_ics_field(&event.title);
-    ics.push_str(&format!("SUMMARY:{}\r\n", summary));
+/// 将单个 Event 添加到 ICS 文本中，`now` 用作 DTSTAMP
+///
+/// 没有单独记录的提醒与完成时间：有截止时间的未完成事件在截止时提醒，
+/// 已完成事件的 COMPLETED 使用导出时间。
+fn export_event_to_ics(
+    ics: &mut String,
+    event: &Event,
+    mode: IcsMode,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let due = event.task_time.map(utc_time).transpose()?;
+    if mode == IcsMode::Event && due.is_none() {
+        return Ok(());
+    }
+    let component = match mode {
+        IcsMode::Todo => "VTODO",
+        IcsMode::Event => "VEVENT",
+    };
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    push_line(ics, "BEGIN", component);
+    push_line(ics, "UID", &event.metadata.uuid);
+    push_line(ics, "DTSTAMP", &stamp);
+    push_line(ics, "CREATED", &utc_time(event.metadata.timestamp)?);
+    push_line(ics, "SUMMARY", &escape_ics_field(&event.title));
 
     // 内容描述 (如果 content 是文件路径，读取文件内容)
     let description = fs::read_to_string(&event.content).unwrap_or_else(|_| event.content.clone());
-    ics.push_str(&format!(
-        "DESCRIPTION:{}\r\n",
-        escape_ics_field(&description)
-    ));
+    if !description.is_empty() {
+        push_line(ics, "DESCRIPTION", &escape_ics_field(&description));
+    }
 
-    // 开始时间
-    if let Some(task_time) = event.task_time {
-        let dt = Utc
-            .timestamp_millis_opt(task_time as i64)
-            .single()
-            .ok_or_else(|| "无效的任务时间".to_string())?;
-        ics.push_str(&format!("DTSTART:{}\r\n", dt.format("%Y%m%dT%H%M%SZ")));
+    match mode {
+        IcsMode::Todo => {
+            // 重复规则需要 DTSTART 作为起点，不能晚于 DUE
+            if event.recurrence.is_some() {
+                let start = match &due {
+                    Some(due) => due.clone(),
+                    None => utc_time(event.metadata.timestamp)?,
+                };
+                push_line(ics, "DTSTART", &start);
+            }
+            if let Some(due) = &due {
+                push_line(ics, "DUE", due);
+            }
+        }
+        IcsMode::Event => {
+            push_line(ics, "DTSTART", due.as_deref().unwrap_or_default());
+            if let Some(estimate) = event.estimate.filter(|m| *m > 0) {
+                push_line(ics, "DURATION", &format!("PT{}M", estimate));
+            }
+            // 任务不占用空闲时间
+            push_line(ics, "TRANSP", "TRANSPARENT");
+        }
+    }
+    if let Some(rrule) = &event.recurrence {
+        push_line(ics, "RRULE", rrule);
     }
 
-    // 优先级 (将 Priority 枚举转换为 ICS 优先级: 1=高, 5=中, 9=低)
+    // 优先级 (1=高, 5=中, 9=低)，未设置时省略而不是写 0
     let priority = match event.priority {
-        Priority::High => 1,
-        Priority::Medium => 5,
-        Priority::Low => 9,
-        Priority::Undefined => 0,
+        Priority::High => Some(1),
+        Priority::Medium => Some(5),
+        Priority::Low => Some(9),
+        Priority::Undefined => None,
     };
-    ics.push_str(&format!("PRIORITY:{}\r\n", priority));
+    if let Some(priority) = priority {
+        push_line(ics, "PRIORITY", &priority.to_string());
+    }
 
-    // 状态 (完成状态)
-    if event.finished {
-        ics.push_str("STATUS:COMPLETED\r\n");
-    } else {
-        ics.push_str("STATUS:NEEDS-ACTION\r\n");
+    let tags = event.metadata.tag.clone().unwrap_or_default();
+    if !tags.is_empty() {
+        let categories: Vec<String> = tags.iter().map(|tag| escape_ics_field(tag)).collect();
+        push_line(ics, "CATEGORIES", &categories.join(","));
     }
 
-    // 事件结束
-    ics.push_str("END:VEVENT\r\n");
+    // 完成状态，VEVENT 没有对应的状态值
+    if mode == IcsMode::Todo {
+        if event.finished {
+            push_line(ics, "STATUS", "COMPLETED");
+            push_line(ics, "COMPLETED", &stamp);
+            push_line(ics, "PERCENT-COMPLETE", "100");
+        } else {
+            push_line(ics, "STATUS", "NEEDS-ACTION");
+            push_line(ics, "PERCENT-COMPLETE", "0");
+        }
+    }
 
+    if due.is_some() && !event.finished {
+        // 待办的提醒相对于 DUE，日历事件的提醒相对于 DTSTART
+        let trigger = match mode {
+            IcsMode::Todo => "TRIGGER;RELATED=END",
+            IcsMode::Event => "TRIGGER",
+        };
+        push_line(ics, "BEGIN", "VALARM");
+        push_line(ics, "ACTION", "DISPLAY");
+        push_line(ics, trigger, "PT0S");
+        push_line(ics, "DESCRIPTION", &escape_ics_field(&event.title));
+        push_line(ics, "END", "VALARM");
+    }
+
+    push_line(ics, "END", component);
     Ok(())
 }
 
@@ -68,149 +155,184 @@ fn escape_ics_field(text: &str) -> String {
     text.replace("\\", "\\\\")
         .replace(";", "\\;")
         .replace(",", "\\,")
+        .replace("\r\n", "\\n")
         .replace("\n", "\\n")
         .replace("\r", "")
 }
 
-/// 导出多个事件为一个 ICS 文件
-pub fn export_events_to_ics(events: Vec<Event>) -> Result<String, String> {
+/// 以指定的时间戳写出日历，时间戳用于 DTSTAMP
+pub fn write_calendar(
+    events: &[Event],
+    mode: IcsMode,
+    now: DateTime<Utc>,
+    out: &mut dyn Write,
+) -> Result<(), String> {
     let mut ics = String::new();
 
     // 日历开始
-    ics.push_str("BEGIN:VCALENDAR\r\n");
-    ics.push_str("VERSION:2.0\r\n");
-    ics.push_str("PRODID:-//ToDoPulse//EN\r\n");
+    push_line(&mut ics, "BEGIN", "VCALENDAR");
+    push_line(&mut ics, "VERSION", "2.0");
+    push_line(&mut ics, "PRODID", "-//ToDoPulse//ToDoPulse//EN");
+    push_line(&mut ics, "CALSCALE", "GREGORIAN");
 
-    // 添加所有指定的事件
+    // 逐个写入所有指定的事件
     for event in events {
-        export_event_to_ics(&mut ics, &event)?;
+        export_event_to_ics(&mut ics, event, mode, now)?;
+        out.write_all(ics.as_bytes()).map_err(|e| e.to_string())?;
+        ics.clear();
     }
 
     // 日历结束
-    ics.push_str("END:VCALENDAR\r\n");
+    push_line(&mut ics, "END", "VCALENDAR");
+    out.write_all(ics.as_bytes()).map_err(|e| e.to_string())
+}
 
-    Ok(ics)
+/// 导出多个事件为一个 ICS 文件
+pub fn write_events_to_ics(
+    events: &[Event],
+    mode: IcsMode,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    write_calendar(events, mode, Utc::now(), out)
 }
 
-/// 导出特定列表中的所有事件为 ICS 格式
-pub async fn export_list_events_to_ics(
-    state: State<'_, StorageState>,
-    list_id: &str,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 获取指定列表中的所有事件
-        let filtered_events = Repository::<Event>::filter(storage, |event| {
-            event
-                .metadata
-                .list
-                .as_ref()
-                .map_or(false, |list| list == list_id)
-        })
-        .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            return Err("列表中没有事件".to_string());
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::export::pipeline::collect;
 
-        filtered_events
-    };
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> u64 {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0)
+            .unwrap()
+            .timestamp_millis() as u64
+    }
 
-    // 导出事件（MutexGuard已释放）
-    export_events_to_ics(events)
-}
+    fn sample_events() -> Vec<Event> {
+        let mut rent = Event::new(
+            "Pay rent; utilities, water",
+            "每月一号交房租。Remember to include the utility bills for electricity and water.\nThanks",
+        );
+        rent.metadata.uuid = "0d5f6a3e-rent".to_string();
+        rent.metadata.timestamp = at(2026, 10, 1, 8, 0);
+        rent.metadata.tag = Some(vec!["home".to_string(), "bills".to_string()]);
+        rent.task_time = Some(at(2026, 11, 1, 9, 0));
+        rent.recurrence = Some("FREQ=MONTHLY;BYMONTHDAY=1".to_string());
+        rent.priority = Priority::High;
+
+        let mut flights = Event::new("Book flights", "");
+        flights.metadata.uuid = "4b1c2d9e-flights".to_string();
+        flights.metadata.timestamp = at(2026, 10, 2, 12, 0);
+        flights.finished = true;
+
+        let mut report = Event::new("Draft report", "");
+        report.metadata.uuid = "9a8b7c6d-report".to_string();
+        report.metadata.timestamp = at(2026, 10, 3, 7, 15);
+        report.task_time = Some(at(2026, 10, 20, 14, 30));
+        report.estimate = Some(90);
+        report.priority = Priority::Low;
+
+        vec![rent, flights, report]
+    }
 
-/// 导出所有事件为 ICS 格式
-pub async fn export_all_events_to_ics(state: State<'_, StorageState>) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
+    fn export(mode: IcsMode) -> String {
+        let now = Utc.with_ymd_and_hms(2026, 10, 18, 3, 0, 0).unwrap();
+        collect(|out| write_calendar(&sample_events(), mode, now, out)).unwrap()
+    }
 
-        // 获取所有事件
-        let all_events = Repository::<Event>::get_all(storage).map_err(|e| e.to_string())?;
+    // 夹具文件以 LF 保存，比较前统一为 CRLF
+    fn fixture(text: &str) -> String {
+        text.replace("\r\n", "\n").replace('\n', "\r\n")
+    }
 
-        if all_events.is_empty() {
-            return Err("没有任何事件".to_string());
+    /// 检查 RFC 5545 的基本要求：CRLF 换行、行长、组件嵌套与必需属性
+    fn validate(ics: &str) {
+        assert!(ics.ends_with("\r\n"));
+        let physical: Vec<&str> = ics.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+        for line in &physical {
+            assert!(!line.contains('\n'), "裸换行: {:?}", line);
+            assert!(line.len() <= MAX_LINE_OCTETS, "超过 75 字节: {:?}", line);
         }
 
-        all_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_ics(events)
-}
-
-/// Exports events in a date range to ICS calendar format
-///
-/// Filters events that fall within the specified time range and exports them
-/// in ICS (iCalendar) format for use with calendar applications.
-///
-/// # Parameters
-/// * `state` - Application state containing the database connection
-/// * `start_time` - Start timestamp in milliseconds (Unix time)
-/// * `end_time` - End timestamp in milliseconds (Unix time)
-///
-/// # Returns
-/// * `Result<String, String>` - ICS content as string or error message
-#[tauri::command]
-pub async fn export_events_by_date_range_to_ics(
-    state: State<'_, StorageState>,
-    start_time: u64,
-    end_time: u64,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 过滤在时间范围内的事件
-        let filtered_events = Repository::<Event>::filter(storage, |event| {
-            if let Some(task_time) = event.task_time {
-                task_time >= start_time && task_time <= end_time
-            } else {
-                false
+        let mut lines: Vec<String> = Vec::new();
+        for line in physical {
+            match line.strip_prefix(' ') {
+                Some(rest) => lines.last_mut().unwrap().push_str(rest),
+                None => lines.push(line.to_string()),
             }
-        })
-        .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            return Err("指定时间范围内没有事件".to_string());
         }
 
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_ics(events)
-}
-
-/// 根据完成状态导出事件为 ICS 格式
-pub async fn export_events_by_status_to_ics(
-    state: State<'_, StorageState>,
-    finished: bool,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 过滤特定完成状态的事件
-        let filtered_events =
-            Repository::<Event>::filter(storage, |event| event.finished == finished)
-                .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            let status = if finished { "已完成" } else { "未完成" };
-            return Err(format!("没有{}的事件", status));
+        let mut stack: Vec<(String, Vec<String>)> = Vec::new();
+        for line in lines {
+            let (name, value) = line.split_once(':').expect("缺少冒号");
+            let name = name.split(';').next().unwrap().to_string();
+            match name.as_str() {
+                "BEGIN" => stack.push((value.to_string(), Vec::new())),
+                "END" => {
+                    let (component, props) = stack.pop().expect("多余的 END");
+                    assert_eq!(component, value);
+                    let count = |name: &str| props.iter().filter(|p| *p == name).count();
+                    let required: &[&str] = match component.as_str() {
+                        "VCALENDAR" => &["VERSION", "PRODID"],
+                        "VTODO" => &["UID", "DTSTAMP"],
+                        "VEVENT" => &["UID", "DTSTAMP", "DTSTART"],
+                        "VALARM" => &["ACTION", "TRIGGER", "DESCRIPTION"],
+                        other => panic!("未知组件 {}", other),
+                    };
+                    for name in required {
+                        assert_eq!(count(name), 1, "{} 中的 {}", component, name);
+                    }
+                    if count("RRULE") > 0 {
+                        assert_eq!(count("DTSTART"), 1, "RRULE 需要 DTSTART");
+                    }
+                    if component == "VEVENT" {
+                        assert_eq!(count("STATUS"), 0, "VEVENT 没有 COMPLETED 状态");
+                    }
+                }
+                _ => {
+                    assert!(!stack.is_empty(), "组件外的属性 {}", name);
+                    if name == "PRIORITY" {
+                        assert_ne!(value, "0");
+                    }
+                    stack.last_mut().unwrap().1.push(name);
+                }
+            }
         }
+        assert!(stack.is_empty(), "未结束的组件");
+    }
 
-        filtered_events
-    };
+    #[test]
+    fn test_vtodo_matches_fixture() {
+        let ics = export(IcsMode::Todo);
+        validate(&ics);
+        assert_eq!(ics, fixture(include_str!("fixtures/vtodo.ics")));
+    }
 
-    // 导出事件（MutexGuard已释放）
-    export_events_to_ics(events)
+    #[test]
+    fn test_vevent_matches_fixture() {
+        let ics = export(IcsMode::Event);
+        validate(&ics);
+        assert_eq!(ics, fixture(include_str!("fixtures/vevent.ics")));
+    }
+
+    #[test]
+    fn test_fold_long_lines() {
+        let mut ics = String::new();
+        let value = "é".repeat(100);
+        push_line(&mut ics, "SUMMARY", &value);
+        let lines: Vec<&str> = ics.trim_end().split("\r\n").collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        let unfolded: String = lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| if i == 0 { *line } else { &line[1..] })
+            .collect();
+        assert_eq!(unfolded, format!("SUMMARY:{}", value));
+
+        // 正好 75 字节不折行
+        let mut ics = String::new();
+        push_line(&mut ics, "SUMMARY", &"a".repeat(67));
+        assert_eq!(ics.len(), 77);
+    }
 }
@@ -1,197 +1,139 @@
-use chrono::{TimeZone, Utc};
-use serde_json::{json, Value};
-use std::ops::DerefMut;
-use tauri::State;
-
-use crate::entity::{Event, Repository, StorageState};
-
-/// 将单个事件转换为JSON格式
-fn export_event_to_json(event: &Event) -> Result<Value, String> {
-    // 创建时间戳
-    let created = Utc
-        .timestamp_millis_opt(event.metadata.timestamp as i64)
-        .single()
-        .ok_or_else(|| "无效的时间戳".to_string())?;
-
-    // 任务时间（如果有）
-    let task_time = if let Some(time) = event.task_time {
-        let dt = Utc
-            .timestamp_millis_opt(time as i64)
-            .single()
-            .ok_or_else(|| "无效的任务时间".to_string())?;
-        Some(dt.format("%Y-%m-%d %H:%M:%S").to_string())
-    } else {
-        None
-    };
-
-    // 优先级
-    let priority_str = match event.priority {
-        crate::entity::event::Priority::High => "高",
-        crate::entity::event::Priority::Medium => "中",
-        crate::entity::event::Priority::Low => "低",
-        crate::entity::event::Priority::Undefined => "未定义",
-    };
-
-    // 创建JSON对象
-    let json_event = json!({
-        "id": event.metadata.uuid,
-        "title": event.title,
-        "created": created.format("%Y-%m-%d %H:%M:%S").to_string(),
-        "task_time": task_time,
-        "finished": event.finished,
-        "priority": priority_str,
-        "icon": event.icon,
-        "color": event.color,
-        "tags": event.metadata.tag,
-        "list_id": event.metadata.list,
-        "content": std::fs::read_to_string(&event.content).unwrap_or_else(|_| event.content.clone())
-    });
-
-    Ok(json_event)
+use anyhow::Result;
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use super::pipeline::Selection;
+use crate::entity::tag::TagColor;
+use crate::entity::{Event, List, Repository, Storage, Tag};
+
+/// 交换格式标识
+pub const FORMAT: &str = "todopulse";
+/// 交换格式版本，结构发生不兼容变化时递增
+pub const VERSION: u32 = 1;
+
+/// 可无损导入的JSON导出文件
+#[derive(Serialize, Deserialize)]
+pub struct Archive {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: String, // RFC 3339
+    pub lists: Vec<List>,
+    pub tags: Vec<ArchivedTag>,
+    pub events: Vec<ArchivedEvent>,
 }
 
-/// 导出多个事件为JSON格式
-pub fn export_events_to_json(events: Vec<Event>) -> Result<String, String> {
-    let mut json_events = Vec::new();
-
-    // 添加所有指定的事件
-    for event in &events {
-        let json_event = export_event_to_json(event)?;
-        json_events.push(json_event);
-    }
-
-    // 创建包含元数据的根对象
-    let root = json!({
-        "metadata": {
-            "exported_at": Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-            "count": events.len()
-        },
-        "events": json_events
-    });
-
-    // 序列化为JSON字符串
-    serde_json::to_string_pretty(&root).map_err(|e| e.to_string())
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArchivedTag {
+    pub name: String,
+    pub color: TagColor,
 }
 
-/// 导出特定列表中的所有事件为JSON格式
-pub async fn export_list_events_to_json(
-    state: State<'_, StorageState>,
-    list_id: &str,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 获取指定列表中的所有事件
-        let filtered_events = Repository::<Event>::filter(storage, |event| {
-            event
-                .metadata
-                .list
-                .as_ref()
-                .map_or(false, |list| list == list_id)
-        })
-        .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            return Err("列表中没有事件".to_string());
-        }
-
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_json(events)
+/// 事件及其Markdown内容与附件，`event.content` 为空，导入时重新生成
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ArchivedEvent {
+    pub event: Event,
+    pub markdown: String,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
-/// 导出所有事件为JSON格式
-pub async fn export_all_events_to_json(state: State<'_, StorageState>) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 获取所有事件
-        let all_events = Repository::<Event>::get_all(storage).map_err(|e| e.to_string())?;
-
-        if all_events.is_empty() {
-            return Err("没有任何事件".to_string());
-        }
+/// 事件内容目录中的附件，`data` 为base64编码的文件内容，`path` 仅记录来源位置，导入时不会读取
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub name: String,
+    #[serde(default)]
+    pub data: Option<String>,
+    #[serde(default)]
+    pub path: Option<String>,
+}
 
-        all_events
+// 读取与Markdown文件同目录的附件
+fn read_attachments(content: &Path) -> Vec<Attachment> {
+    let Some(dir) = content.parent() else {
+        return Vec::new();
     };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_json(events)
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut attachments: Vec<Attachment> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path != content)
+        .filter_map(|path| {
+            let data = fs::read(&path).ok()?;
+            Some(Attachment {
+                name: path.file_name()?.to_string_lossy().to_string(),
+                data: Some(general_purpose::STANDARD.encode(data)),
+                path: None,
+            })
+        })
+        .collect();
+    attachments.sort_by(|a, b| a.name.cmp(&b.name));
+    attachments
 }
 
-/// Exports events in a date range to JSON format
-///
-/// Filters events that fall within the specified time range and exports them
-/// in JSON format for programmatic use.
-///
-/// # Parameters
-/// * `state` - Application state containing the database connection
-/// * `start_time` - Start timestamp in milliseconds (Unix time)
-/// * `end_time` - End timestamp in milliseconds (Unix time)
-///
-/// # Returns
-/// * `Result<String, String>` - JSON content as string or error message
-#[tauri::command]
-pub async fn export_events_by_date_range_to_json(
-    state: State<'_, StorageState>,
-    start_time: u64,
-    end_time: u64,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 过滤在时间范围内的事件
-        let filtered_events = Repository::<Event>::filter(storage, |event| {
-            if let Some(task_time) = event.task_time {
-                task_time >= start_time && task_time <= end_time
-            } else {
-                false
+/// 将事件连同其所属列表与用到的标签打包
+pub fn archive(storage: &Storage, events: Vec<Event>) -> Result<Archive> {
+    let lists = Repository::<List>::filter(storage, |list| {
+        events
+            .iter()
+            .any(|event| event.metadata.list.as_ref() == Some(&list.uuid))
+    })?;
+    let tags = Repository::<Tag>::filter(storage, |tag| {
+        events
+            .iter()
+            .any(|event| event.metadata.tag.iter().flatten().any(|t| t == &tag.name))
+    })?
+    .into_iter()
+    .map(|tag| ArchivedTag {
+        name: tag.name,
+        color: tag.color,
+    })
+    .collect();
+
+    let events = events
+        .into_iter()
+        .map(|mut event| {
+            let content = std::mem::take(&mut event.content);
+            ArchivedEvent {
+                markdown: fs::read_to_string(&content).unwrap_or_default(),
+                attachments: read_attachments(Path::new(&content)),
+                event,
             }
         })
-        .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            return Err("指定时间范围内没有事件".to_string());
-        }
-
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_json(events)
+        .collect();
+
+    Ok(Archive {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at: Utc::now().to_rfc3339(),
+        lists,
+        tags,
+        events,
+    })
 }
 
-/// 根据完成状态导出事件为JSON格式
-pub async fn export_events_by_status_to_json(
-    state: State<'_, StorageState>,
-    finished: bool,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 过滤特定完成状态的事件
-        let filtered_events =
-            Repository::<Event>::filter(storage, |event| event.finished == finished)
-                .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            let status = if finished { "已完成" } else { "未完成" };
-            return Err(format!("没有{}的事件", status));
-        }
-
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_json(events)
+/// 导出选中的事件为JSON格式，导出全部数据时包括没有事件的列表与未使用的标签
+pub fn write_json(
+    storage: &Storage,
+    selection: Selection,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let mut archive = archive(storage, selection.events).map_err(|e| e.to_string())?;
+    if selection.everything {
+        archive.lists = Repository::<List>::get_all(storage).map_err(|e| e.to_string())?;
+        archive.tags = Repository::<Tag>::get_all(storage)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|tag| ArchivedTag {
+                name: tag.name,
+                color: tag.color,
+            })
+            .collect();
+    }
+    serde_json::to_writer_pretty(out, &archive).map_err(|e| e.to_string())
 }
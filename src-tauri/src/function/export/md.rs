@@ -1,9 +1,12 @@
 use chrono::{TimeZone, Utc};
 use std::fs;
-use std::ops::DerefMut;
-use tauri::State;
+use std::io::Write;
 
-use crate::entity::{Event, Repository, StorageState};
+use super::todotxt::escape_tag;
+use crate::entity::event::Priority;
+use crate::entity::Event;
+use crate::function::convert::{local_date, parse_simple_rrule};
+use crate::function::import::md::is_field;
 
 /// 将单个事件转换为Markdown格式
 fn export_event_to_md(md: &mut String, event: &Event) -> Result<(), String> {
@@ -76,8 +79,8 @@ fn export_event_to_md(md: &mut String, event: &Event) -> Result<(), String> {
     Ok(())
 }
 
-/// 导出多个事件为Markdown格式
-pub fn export_events_to_md(events: Vec<Event>) -> Result<String, String> {
+/// 导出多个事件为Markdown格式，逐个事件写入输出
+pub fn write_md(events: &[Event], out: &mut dyn Write) -> Result<(), String> {
     let mut md = String::new();
 
     md.push_str("# 事件导出\n\n");
@@ -89,131 +92,72 @@ pub fn export_events_to_md(events: Vec<Event>) -> Result<String, String> {
 
     // 添加所有指定的事件
     for event in events {
-        export_event_to_md(&mut md, &event)?;
+        export_event_to_md(&mut md, event)?;
+        out.write_all(md.as_bytes()).map_err(|e| e.to_string())?;
+        md.clear();
     }
-
-    Ok(md)
+    out.write_all(md.as_bytes()).map_err(|e| e.to_string())
 }
 
-/// 导出特定列表中的所有事件为Markdown格式
-pub async fn export_list_events_to_md(
-    state: State<'_, StorageState>,
-    list_id: &str,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 获取指定列表中的所有事件
-        let filtered_events = Repository::<Event>::filter(storage, |event| {
-            event
-                .metadata
-                .list
-                .as_ref()
-                .map_or(false, |list| list == list_id)
-        })
-        .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            return Err("列表中没有事件".to_string());
-        }
-
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_md(events)
+/// 将 RRULE 转换为 Obsidian Tasks 的重复规则，仅支持带间隔的简单频率
+///
+/// 带 BYDAY 等限定的规则无法表示时省略，重新导入时保留事件原有的规则。
+fn rrule_to_every(rrule: &str) -> Option<String> {
+    let (freq, interval) = parse_simple_rrule(rrule)?;
+    Some(if interval == 1 {
+        format!("every {}", freq.word())
+    } else {
+        format!("every {} {}s", interval, freq.word())
+    })
 }
 
-/// 导出所有事件为Markdown格式
-pub async fn export_all_events_to_md(state: State<'_, StorageState>) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 获取所有事件
-        let all_events = Repository::<Event>::get_all(storage).map_err(|e| e.to_string())?;
-
-        if all_events.is_empty() {
-            return Err("没有任何事件".to_string());
-        }
-
-        all_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_md(events)
+/// 标题中会被当作标签或字段的单词前加 `\`，以 `\` 开头的单词同样转义，导入时去掉一层
+fn escape_title_word(word: &str) -> String {
+    if word.starts_with(['#', '\\']) || is_field(&word.replace('\u{FE0F}', "")) {
+        format!("\\{}", word)
+    } else {
+        word.to_string()
+    }
 }
 
-/// Exports events in a date range to Markdown format
-///
-/// Filters events that fall within the specified time range and exports them
-/// in Markdown format.
-///
-/// # Parameters
-/// * `state` - Application state containing the database connection
-/// * `start_time` - Start timestamp in milliseconds (Unix time)
-/// * `end_time` - End timestamp in milliseconds (Unix time)
+/// 将单个事件转换为 Obsidian Tasks 任务行
 ///
-/// # Returns
-/// * `Result<String, String>` - Markdown content as string or error message
-#[tauri::command]
-pub async fn export_events_by_date_range_to_md(
-    state: State<'_, StorageState>,
-    start_time: u64,
-    end_time: u64,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 过滤在时间范围内的事件
-        let filtered_events = Repository::<Event>::filter(storage, |event| {
-            if let Some(task_time) = event.task_time {
-                task_time >= start_time && task_time <= end_time
-            } else {
-                false
-            }
-        })
-        .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            return Err("指定时间范围内没有事件".to_string());
-        }
-
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_md(events)
+/// 截止时间只保留本地日期，重新导入时日期未变则保留原有时间。
+/// 标签中的空白与 `%` 按 todo.txt 的方式转义，标题中的 `#` 与字段符号前加 `\`。
+/// 🆔 字段为事件UUID，重新导入时据此更新原事件。
+fn export_event_to_task(event: &Event) -> String {
+    let status = if event.finished { "x" } else { " " };
+    let title = event
+        .title
+        .split_whitespace()
+        .map(escape_title_word)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut line = format!("- [{}] {}", status, title);
+
+    for tag in event.metadata.tag.iter().flatten() {
+        line.push_str(&format!(" #{}", escape_tag(tag)));
+    }
+    match event.priority {
+        Priority::High => line.push_str(" ⏫"),
+        Priority::Medium => line.push_str(" 🔼"),
+        Priority::Low => line.push_str(" 🔽"),
+        Priority::Undefined => {}
+    }
+    if let Some(every) = event.recurrence.as_deref().and_then(rrule_to_every) {
+        line.push_str(&format!(" 🔁 {}", every));
+    }
+    if let Some(due) = event.task_time.and_then(local_date) {
+        line.push_str(&format!(" 📅 {}", due));
+    }
+    line.push_str(&format!(" 🆔 {}", event.metadata.uuid));
+    line
 }
 
-/// 根据完成状态导出事件为Markdown格式
-pub async fn export_events_by_status_to_md(
-    state: State<'_, StorageState>,
-    finished: bool,
-) -> Result<String, String> {
-    // 在内部作用域中获取events，确保MutexGuard在作用域结束时被释放
-    let events = {
-        let mut guard = state.0.lock().await;
-        let storage = guard.deref_mut();
-
-        // 过滤特定完成状态的事件
-        let filtered_events =
-            Repository::<Event>::filter(storage, |event| event.finished == finished)
-                .map_err(|e| e.to_string())?;
-
-        if filtered_events.is_empty() {
-            let status = if finished { "已完成" } else { "未完成" };
-            return Err(format!("没有{}的事件", status));
-        }
-
-        filtered_events
-    };
-
-    // 导出事件（MutexGuard已释放）
-    export_events_to_md(events)
+/// 导出多个事件为 Obsidian Tasks 格式的任务列表
+pub fn write_tasks(events: &[Event], out: &mut dyn Write) -> Result<(), String> {
+    for event in events {
+        writeln!(out, "{}", export_event_to_task(event)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
 }
@@ -0,0 +1,448 @@
+use chrono::{Local, TimeZone, Timelike};
+use pulldown_cmark::{CodeBlockKind, Event as MdEvent, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::fs;
+use std::io::Write;
+
+use crate::entity::event::Priority;
+use crate::entity::{Event, List, Repository, Storage};
+use crate::function::convert::parse_simple_rrule;
+
+/// 将毫秒时间戳格式化为 org 时间戳，本地零点只写日期
+///
+/// `active` 为真时使用 `<...>`，否则使用 `[...]`。
+fn org_timestamp(millis: u64, active: bool, repeater: Option<&str>) -> Option<String> {
+    let dt = Local.timestamp_millis_opt(millis as i64).single()?;
+    let mut text = dt.format("%Y-%m-%d %a").to_string();
+    if dt.hour() != 0 || dt.minute() != 0 {
+        text.push_str(&dt.format(" %H:%M").to_string());
+    }
+    if let Some(repeater) = repeater {
+        text.push(' ');
+        text.push_str(repeater);
+    }
+    Some(if active {
+        format!("<{}>", text)
+    } else {
+        format!("[{}]", text)
+    })
+}
+
+/// 将 RRULE 转换为 org 时间戳的重复标记（如 `+2w`），仅支持带间隔的简单频率
+fn rrule_to_repeater(rrule: &str) -> Option<String> {
+    let (freq, interval) = parse_simple_rrule(rrule)?;
+    Some(format!("+{}{}", interval, freq.letter()))
+}
+
+/// org 标签只能包含字母、数字以及 `_@#%`
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Markdown 到 org 的转换状态
+struct OrgWriter {
+    org: String,
+    level: usize,            // 事件标题的层级，正文标题在其下
+    lists: Vec<Option<u64>>, // 嵌套列表，有序列表记录下一个序号
+    in_code: bool,
+    in_image: bool,
+    table_columns: usize,
+}
+
+impl OrgWriter {
+    fn indent(&self) -> String {
+        "  ".repeat(self.lists.len())
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.org.is_empty() || self.org.ends_with('\n')
+    }
+
+    fn newline(&mut self) {
+        if !self.at_line_start() {
+            self.org.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        self.newline();
+        if self.lists.is_empty() && !self.org.is_empty() && !self.org.ends_with("\n\n") {
+            self.org.push('\n');
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.in_image {
+            return;
+        }
+        if self.in_code {
+            // 代码块中以 `*` 或 `#+` 开头的行需要用逗号转义
+            for line in text.split_inclusive('\n') {
+                if line.starts_with('*') || line.starts_with("#+") {
+                    self.org.push(',');
+                }
+                self.org.push_str(line);
+            }
+            return;
+        }
+        // 行首的 `*` 会被当作标题
+        if self.at_line_start() && (text.starts_with('*') || text.starts_with("#+")) {
+            self.org.push(' ');
+        }
+        self.org.push_str(text);
+    }
+
+    fn link_target(url: &str) -> String {
+        if url.contains(':') || url.starts_with('#') {
+            url.to_string()
+        } else {
+            format!("file:{}", url)
+        }
+    }
+
+    fn event(&mut self, event: MdEvent) {
+        match event {
+            MdEvent::Start(tag) => self.start(tag),
+            MdEvent::End(tag) => self.end(tag),
+            MdEvent::Text(text) => self.text(&text),
+            MdEvent::Code(code) => {
+                let marker = if code.contains('~') { '=' } else { '~' };
+                self.org.push_str(&format!("{}{}{}", marker, code, marker));
+            }
+            MdEvent::Html(html) | MdEvent::InlineHtml(html) => self.org.push_str(&html),
+            MdEvent::SoftBreak => {
+                self.org.push('\n');
+                let indent = self.indent();
+                self.org.push_str(&indent);
+            }
+            MdEvent::HardBreak => {
+                self.org.push_str("\\\\\n");
+                let indent = self.indent();
+                self.org.push_str(&indent);
+            }
+            MdEvent::Rule => {
+                self.newline();
+                self.org.push_str("-----");
+                self.blank_line();
+            }
+            MdEvent::TaskListMarker(checked) => {
+                self.org.push_str(if checked { "[X] " } else { "[ ] " });
+            }
+            MdEvent::FootnoteReference(name) => self.org.push_str(&format!("[fn:{}]", name)),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.newline();
+                let depth = match level {
+                    HeadingLevel::H1 => 1,
+                    HeadingLevel::H2 => 2,
+                    HeadingLevel::H3 => 3,
+                    HeadingLevel::H4 => 4,
+                    HeadingLevel::H5 => 5,
+                    HeadingLevel::H6 => 6,
+                };
+                self.org
+                    .push_str(&format!("{} ", "*".repeat(self.level + depth)));
+            }
+            Tag::BlockQuote(_) => {
+                self.newline();
+                self.org.push_str("#+BEGIN_QUOTE\n");
+            }
+            Tag::CodeBlock(kind) => {
+                self.newline();
+                match kind {
+                    CodeBlockKind::Fenced(lang) if !lang.is_empty() => {
+                        self.org.push_str(&format!("#+BEGIN_SRC {}\n", lang))
+                    }
+                    _ => self.org.push_str("#+BEGIN_SRC\n"),
+                }
+                self.in_code = true;
+            }
+            Tag::List(start) => {
+                self.newline();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.newline();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}.", *n - 1)
+                    }
+                    _ => "-".to_string(),
+                };
+                self.org.push_str(&format!("{}{} ", indent, bullet));
+            }
+            Tag::Emphasis => self.org.push('/'),
+            Tag::Strong => self.org.push('*'),
+            Tag::Strikethrough => self.org.push('+'),
+            Tag::Link { dest_url, .. } => {
+                self.org
+                    .push_str(&format!("[[{}][", Self::link_target(&dest_url)));
+            }
+            Tag::Image { dest_url, .. } => {
+                self.org
+                    .push_str(&format!("[[{}]]", Self::link_target(&dest_url)));
+                self.in_image = true;
+            }
+            Tag::Table(alignments) => {
+                self.newline();
+                self.table_columns = alignments.len();
+            }
+            Tag::TableHead | Tag::TableRow => self.org.push('|'),
+            Tag::TableCell => self.org.push(' '),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::Heading(_) => self.blank_line(),
+            TagEnd::BlockQuote(_) => {
+                self.newline();
+                // 引用中最后一段之后的空行
+                while self.org.ends_with("\n\n") {
+                    self.org.pop();
+                }
+                self.org.push_str("#+END_QUOTE");
+                self.blank_line();
+            }
+            TagEnd::CodeBlock => {
+                self.in_code = false;
+                self.newline();
+                self.org.push_str("#+END_SRC");
+                self.blank_line();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                self.blank_line();
+            }
+            TagEnd::Item => self.newline(),
+            TagEnd::Emphasis => self.org.push('/'),
+            TagEnd::Strong => self.org.push('*'),
+            TagEnd::Strikethrough => self.org.push('+'),
+            TagEnd::Link => self.org.push_str("]]"),
+            TagEnd::Image => self.in_image = false,
+            TagEnd::TableHead => {
+                self.org.push('\n');
+                let separator = vec!["---"; self.table_columns].join("+");
+                self.org.push_str(&format!("|{}|\n", separator));
+            }
+            TagEnd::TableRow => self.org.push('\n'),
+            TagEnd::TableCell => self.org.push_str(" |"),
+            TagEnd::Table => self.blank_line(),
+            _ => {}
+        }
+    }
+}
+
+/// 将 Markdown 转换为 org 正文，`level` 为所属事件标题的层级
+pub fn markdown_to_org(markdown: &str, level: usize) -> String {
+    let mut writer = OrgWriter {
+        org: String::new(),
+        level,
+        lists: Vec::new(),
+        in_code: false,
+        in_image: false,
+        table_columns: 0,
+    };
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    for event in Parser::new_ext(markdown, options) {
+        writer.event(event);
+    }
+    let org = writer.org.trim_end();
+    if org.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", org)
+    }
+}
+
+/// 将单个事件写为 org 标题
+///
+/// 截止时间写为 `DEADLINE`，简单的重复规则写为时间戳的重复标记，其余规则保留在 `:RRULE:` 属性中；
+/// 事件UUID写为 `:ID:` 属性，重新导入时据此更新原事件。
+fn export_event_to_org(org: &mut String, event: &Event, level: usize) {
+    let keyword = if event.finished { "DONE" } else { "TODO" };
+    let mut headline = format!("{} {}", "*".repeat(level), keyword);
+    match event.priority {
+        Priority::High => headline.push_str(" [#A]"),
+        Priority::Medium => headline.push_str(" [#B]"),
+        Priority::Low => headline.push_str(" [#C]"),
+        Priority::Undefined => {}
+    }
+    headline.push(' ');
+    headline.push_str(&event.title.split_whitespace().collect::<Vec<_>>().join(" "));
+    let tags: Vec<String> = event
+        .metadata
+        .tag
+        .iter()
+        .flatten()
+        .map(|tag| org_tag(tag))
+        .collect();
+    if !tags.is_empty() {
+        headline.push_str(&format!(" :{}:", tags.join(":")));
+    }
+    org.push_str(&headline);
+    org.push('\n');
+
+    let repeater = event.recurrence.as_deref().and_then(rrule_to_repeater);
+    if let Some(deadline) = event
+        .task_time
+        .and_then(|due| org_timestamp(due, true, repeater.as_deref()))
+    {
+        org.push_str(&format!("DEADLINE: {}\n", deadline));
+    }
+
+    org.push_str(":PROPERTIES:\n");
+    org.push_str(&format!(":ID: {}\n", event.metadata.uuid));
+    if let Some(created) = org_timestamp(event.metadata.timestamp, false, None) {
+        org.push_str(&format!(":CREATED: {}\n", created));
+    }
+    if let Some(estimate) = event.estimate.filter(|m| *m > 0) {
+        org.push_str(&format!(
+            ":EFFORT: {}:{:02}\n",
+            estimate / 60,
+            estimate % 60
+        ));
+    }
+    // 时间戳无法表示的重复规则
+    if let Some(rrule) = event
+        .recurrence
+        .as_ref()
+        .filter(|_| repeater.is_none() || event.task_time.is_none())
+    {
+        org.push_str(&format!(":RRULE: {}\n", rrule));
+    }
+    org.push_str(":END:\n");
+
+    let markdown = fs::read_to_string(&event.content).unwrap_or_default();
+    org.push_str(&markdown_to_org(&markdown, level));
+}
+
+/// 导出多个事件为 org 文件，列表为一级标题，未归属列表的事件在最前面
+pub fn write_org(storage: &Storage, events: &[Event], out: &mut dyn Write) -> Result<(), String> {
+    let lists = Repository::<List>::get_all(storage).map_err(|e| e.to_string())?;
+    let mut org = String::from("#+TITLE: ToDoPulse\n\n");
+
+    let in_list = |event: &Event| {
+        event
+            .metadata
+            .list
+            .as_ref()
+            .is_some_and(|id| lists.iter().any(|list| &list.uuid == id))
+    };
+    for event in events.iter().filter(|event| !in_list(event)) {
+        export_event_to_org(&mut org, event, 1);
+        out.write_all(org.as_bytes()).map_err(|e| e.to_string())?;
+        org.clear();
+    }
+    for list in &lists {
+        let mut members = events
+            .iter()
+            .filter(|event| event.metadata.list.as_ref() == Some(&list.uuid))
+            .peekable();
+        if members.peek().is_none() {
+            continue;
+        }
+        org.push_str(&format!("* {}\n", list.title));
+        for event in members {
+            export_event_to_org(&mut org, event, 2);
+            out.write_all(org.as_bytes()).map_err(|e| e.to_string())?;
+            org.clear();
+        }
+    }
+    out.write_all(org.as_bytes()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_to_org() {
+        let markdown = "Intro with **bold**, *em*, ~~gone~~ and `code`.\n\n\
+                        ## Steps\n\n\
+                        - [ ] call [bank](https://bank.example)\n\
+                        - [x] see ![scan](scan.png)\n\n\
+                        1. first\n2. second\n\n\
+                        ```rust\nfn main() {}\n* not a heading\n```\n\n\
+                        > quoted\n\n\
+                        | a | b |\n|---|---|\n| 1 | 2 |\n";
+        let expected = "Intro with *bold*, /em/, +gone+ and ~code~.\n\n\
+                        **** Steps\n\n\
+                        - [ ] call [[https://bank.example][bank]]\n\
+                        - [X] see [[file:scan.png]]\n\n\
+                        1. first\n2. second\n\n\
+                        #+BEGIN_SRC rust\nfn main() {}\n,* not a heading\n#+END_SRC\n\n\
+                        #+BEGIN_QUOTE\nquoted\n#+END_QUOTE\n\n\
+                        | a | b |\n|---+---|\n| 1 | 2 |\n";
+        assert_eq!(markdown_to_org(markdown, 2), expected);
+        assert_eq!(markdown_to_org("", 2), "");
+    }
+
+    #[test]
+    fn test_export_event_to_org() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = dir.path().join("content.md");
+        fs::write(&content, "Bring the *signed* form").unwrap();
+
+        let mut event = Event::new("Renew  passport", content.to_str().unwrap());
+        event.metadata.timestamp = Local
+            .with_ymd_and_hms(2026, 10, 10, 9, 30, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        event.metadata.tag = Some(vec!["admin".to_string(), "long wait".to_string()]);
+        event.priority = Priority::High;
+        event.task_time = Some(
+            Local
+                .with_ymd_and_hms(2026, 11, 2, 0, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        );
+        event.recurrence = Some("FREQ=YEARLY;INTERVAL=10".to_string());
+        event.estimate = Some(90);
+
+        let mut org = String::new();
+        export_event_to_org(&mut org, &event, 2);
+        assert_eq!(
+            org,
+            format!(
+                "** TODO [#A] Renew passport :admin:long_wait:\n\
+                 DEADLINE: <2026-11-02 Mon +10y>\n\
+                 :PROPERTIES:\n\
+                 :ID: {}\n\
+                 :CREATED: [2026-10-10 Sat 09:30]\n\
+                 :EFFORT: 1:30\n\
+                 :END:\n\
+                 Bring the /signed/ form\n",
+                event.metadata.uuid
+            )
+        );
+
+        event.finished = true;
+        event.priority = Priority::Undefined;
+        event.metadata.tag = None;
+        event.recurrence = Some("FREQ=MONTHLY;BYMONTHDAY=1".to_string());
+        event.estimate = None;
+        let mut org = String::new();
+        export_event_to_org(&mut org, &event, 1);
+        assert!(org.starts_with("* DONE Renew passport\nDEADLINE: <2026-11-02 Mon>\n"));
+        assert!(org.contains(":RRULE: FREQ=MONTHLY;BYMONTHDAY=1\n"));
+    }
+}
@@ -0,0 +1,434 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+use super::csv::{write_events_to_csv, CsvColumn};
+use super::html::write_html;
+use super::ics::{write_events_to_ics, IcsMode};
+use super::json::write_json;
+use super::md::{write_md, write_tasks};
+use super::org::write_org;
+use super::save::sanitize_filename;
+use super::template::{templates_dir, write_template};
+use super::todotxt::write_todotxt;
+use crate::entity::{Event, Repository, Storage};
+use crate::filter::{map_filter, Filter};
+use crate::utils::AppPaths;
+
+/// 要导出的事件
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub enum EventSelector {
+    /// 全部数据，JSON 导出会包含没有事件的列表与未使用的标签
+    All,
+    /// 指定的事件，按给出的顺序导出
+    Ids(Vec<String>),
+    /// 某个列表中的事件
+    List(String),
+    /// 指定完成状态的事件
+    Status(bool),
+    /// 截止时间在范围内的事件，毫秒时间戳，包含两端
+    DateRange {
+        #[ts(type = "number")]
+        start: u64,
+        #[ts(type = "number")]
+        end: u64,
+    },
+    /// 与 `filter_events` 相同的过滤条件，例如 `today`、`this_week`、`actionable` 或 `2026-10-18`
+    Filter(String),
+    /// 标题或内容包含关键词的事件
+    Search(String),
+}
+
+/// 导出结果的去向
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub enum ExportSink {
+    /// 以字符串返回内容
+    Return,
+    /// 保存到导出目录，值为不含扩展名的文件名
+    ExportDir(String),
+    /// 保存到指定的文件路径
+    File(String),
+}
+
+/// 可用的导出格式
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct ExportFormat {
+    pub name: String,
+    pub extension: String,
+}
+
+/// 选中的事件，`everything` 为真时表示导出全部数据
+pub struct Selection {
+    pub events: Vec<Event>,
+    pub everything: bool,
+}
+
+type WriteFn = Box<dyn Fn(&Storage, Selection, &mut dyn Write) -> Result<(), String> + Send + Sync>;
+
+/// 一种导出格式，将选中的事件流式写入输出
+pub struct Exporter {
+    pub name: String,
+    pub extension: String,
+    write: WriteFn,
+}
+
+impl Exporter {
+    pub fn new(
+        name: &str,
+        extension: &str,
+        write: impl Fn(&Storage, Selection, &mut dyn Write) -> Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            extension: extension.to_string(),
+            write: Box::new(write),
+        }
+    }
+
+    pub fn write(
+        &self,
+        storage: &Storage,
+        selection: Selection,
+        out: &mut dyn Write,
+    ) -> Result<(), String> {
+        (self.write)(storage, selection, out)
+    }
+}
+
+/// 按名称查找导出格式
+pub struct Registry {
+    exporters: Vec<Exporter>,
+}
+
+impl Registry {
+    /// 内置格式
+    pub fn builtin() -> Self {
+        let exporters = vec![
+            Exporter::new("ics", "ics", |_, selection, out| {
+                write_events_to_ics(&selection.events, IcsMode::Todo, out)
+            }),
+            Exporter::new("ics-event", "ics", |_, selection, out| {
+                write_events_to_ics(&selection.events, IcsMode::Event, out)
+            }),
+            Exporter::new("md", "md", |_, selection, out| {
+                write_md(&selection.events, out)
+            }),
+            Exporter::new("tasks", "md", |_, selection, out| {
+                write_tasks(&selection.events, out)
+            }),
+            Exporter::new("json", "json", write_json),
+            Exporter::new("csv", "csv", |storage, selection, out| {
+                write_events_to_csv(storage, &selection.events, CsvColumn::DEFAULT, out)
+            }),
+            Exporter::new("todotxt", "txt", |storage, selection, out| {
+                write_todotxt(storage, &selection.events, out)
+            }),
+            Exporter::new("org", "org", |storage, selection, out| {
+                write_org(storage, &selection.events, out)
+            }),
+            Exporter::new("html", "html", |storage, selection, out| {
+                write_html(storage, &selection.events, out)
+            }),
+        ];
+        Self { exporters }
+    }
+
+    /// 内置格式加上模板目录中的用户模板，模板以 `template:<文件名>` 注册
+    pub fn with_templates() -> Self {
+        let mut registry = Self::builtin();
+        let Ok(entries) = fs::read_dir(templates_dir()) else {
+            return registry;
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        names.sort();
+        for name in names {
+            let extension = Path::new(&name)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_else(|| "txt".to_string());
+            let template = name.clone();
+            registry.register(Exporter::new(
+                &format!("template:{}", name),
+                &extension,
+                move |storage, selection, out| {
+                    write_template(storage, &selection.events, &template, out)
+                },
+            ));
+        }
+        registry
+    }
+
+    /// 注册导出格式，同名格式会被替换
+    pub fn register(&mut self, exporter: Exporter) {
+        self.exporters.retain(|e| e.name != exporter.name);
+        self.exporters.push(exporter);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Exporter> {
+        self.exporters.iter().find(|e| e.name == name)
+    }
+
+    pub fn formats(&self) -> Vec<ExportFormat> {
+        self.exporters
+            .iter()
+            .map(|e| ExportFormat {
+                name: e.name.clone(),
+                extension: e.extension.clone(),
+            })
+            .collect()
+    }
+}
+
+fn filter_events(storage: &Storage, filter: Filter<Event>) -> anyhow::Result<Vec<Event>> {
+    Repository::<Event>::filter(storage, |event| match &filter {
+        Filter::A(f) => f(event),
+        Filter::B(f) => f(event),
+    })
+}
+
+impl EventSelector {
+    /// 从存储中取出选中的事件
+    pub fn select(&self, storage: &Storage) -> Result<Selection, String> {
+        let events = match self {
+            EventSelector::All => Repository::<Event>::get_all(storage),
+            EventSelector::Ids(ids) => ids
+                .iter()
+                .filter_map(|id| Repository::<Event>::get_by_name(storage, id).transpose())
+                .collect(),
+            EventSelector::List(list_id) => Repository::<Event>::filter(storage, |event| {
+                event.metadata.list.as_deref() == Some(list_id.as_str())
+            }),
+            EventSelector::Status(finished) => {
+                Repository::<Event>::filter(storage, |event| event.finished == *finished)
+            }
+            EventSelector::DateRange { start, end } => {
+                Repository::<Event>::filter(storage, |event| {
+                    event
+                        .task_time
+                        .is_some_and(|task_time| task_time >= *start && task_time <= *end)
+                })
+            }
+            EventSelector::Filter(query) => filter_events(
+                storage,
+                map_filter(storage, query, None).map_err(|e| e.to_string())?,
+            ),
+            EventSelector::Search(word) => filter_events(
+                storage,
+                map_filter(storage, word, Some(true)).map_err(|e| e.to_string())?,
+            ),
+        }
+        .map_err(|e| e.to_string())?;
+
+        if events.is_empty() {
+            return Err(self.empty_message());
+        }
+        Ok(Selection {
+            events,
+            everything: *self == EventSelector::All,
+        })
+    }
+
+    fn empty_message(&self) -> String {
+        match self {
+            EventSelector::All => "没有任何事件".to_string(),
+            EventSelector::Ids(_) => "没有找到指定的事件".to_string(),
+            EventSelector::List(_) => "列表中没有事件".to_string(),
+            EventSelector::Status(finished) => {
+                let status = if *finished { "已完成" } else { "未完成" };
+                format!("没有{}的事件", status)
+            }
+            EventSelector::DateRange { .. } => "指定时间范围内没有事件".to_string(),
+            EventSelector::Filter(_) | EventSelector::Search(_) => "没有符合条件的事件".to_string(),
+        }
+    }
+}
+
+/// 将写入器的输出收集为字符串
+pub fn collect(write: impl FnOnce(&mut dyn Write) -> Result<(), String>) -> Result<String, String> {
+    let mut buffer = Vec::new();
+    write(&mut buffer)?;
+    String::from_utf8(buffer).map_err(|e| e.to_string())
+}
+
+/// 先写入同目录下的临时文件，成功后再替换目标文件，失败时不留下不完整的文件
+fn write_file(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), String>,
+) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("无法创建目录: {}", e))?;
+    }
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let result = File::create(&partial)
+        .map_err(|e| format!("无法创建文件: {}", e))
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            write(&mut out)?;
+            out.flush().map_err(|e| format!("保存文件失败: {}", e))
+        })
+        .and_then(|_| fs::rename(&partial, path).map_err(|e| format!("保存文件失败: {}", e)));
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result
+}
+
+/// 导出选中的事件，返回内容（`ExportSink::Return`）或保存的文件路径
+pub fn run(
+    storage: &Storage,
+    exporter: &Exporter,
+    selector: &EventSelector,
+    sink: &ExportSink,
+) -> Result<String, String> {
+    let selection = selector.select(storage)?;
+    let path = match sink {
+        ExportSink::Return => {
+            return collect(|out| exporter.write(storage, selection, out));
+        }
+        ExportSink::ExportDir(filename) => AppPaths::export_dir().join(format!(
+            "{}.{}",
+            sanitize_filename(filename),
+            exporter.extension
+        )),
+        ExportSink::File(path) => PathBuf::from(path),
+    };
+    write_file(&path, |out| exporter.write(storage, selection, out))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::event::Priority;
+    use crate::test::temp_storage;
+
+    #[test]
+    fn test_run() {
+        let (storage, dir) = temp_storage();
+        let mut open = Event::new("Open", "");
+        open.priority = Priority::High;
+        let mut done = Event::new("Done", "");
+        done.finished = true;
+        Repository::<Event>::add(&storage, &open).unwrap();
+        Repository::<Event>::add(&storage, &done).unwrap();
+        let registry = Registry::builtin();
+        let tasks = registry.get("tasks").unwrap();
+
+        let text = run(
+            &storage,
+            tasks,
+            &EventSelector::Status(false),
+            &ExportSink::Return,
+        )
+        .unwrap();
+        assert!(text.starts_with("- [ ] Open ⏫"));
+        assert_eq!(text.lines().count(), 1);
+
+        let text = run(
+            &storage,
+            tasks,
+            &EventSelector::Filter("high_priority".to_string()),
+            &ExportSink::Return,
+        )
+        .unwrap();
+        assert!(text.contains("Open"));
+
+        let text = run(
+            &storage,
+            tasks,
+            &EventSelector::Filter("actionable".to_string()),
+            &ExportSink::Return,
+        )
+        .unwrap();
+        assert!(text.contains("Open"));
+        assert!(!text.contains("Done"));
+
+        // 按给出的顺序导出，跳过不存在的ID
+        let ids = EventSelector::Ids(vec![
+            done.metadata.uuid.clone(),
+            "missing".to_string(),
+            open.metadata.uuid.clone(),
+        ]);
+        let text = run(&storage, tasks, &ids, &ExportSink::Return).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("- [x] Done"));
+        assert!(lines[1].starts_with("- [ ] Open"));
+
+        let err = run(
+            &storage,
+            tasks,
+            &EventSelector::List("missing".to_string()),
+            &ExportSink::Return,
+        );
+        assert_eq!(err, Err("列表中没有事件".to_string()));
+
+        let path = dir.path().join("out").join("tasks.md");
+        let saved = run(
+            &storage,
+            tasks,
+            &EventSelector::All,
+            &ExportSink::File(path.to_string_lossy().to_string()),
+        )
+        .unwrap();
+        assert_eq!(saved, path.to_string_lossy());
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::builtin();
+        assert_eq!(registry.get("todotxt").unwrap().extension, "txt");
+        assert!(registry.get("template:missing.md").is_none());
+
+        registry.register(Exporter::new("md", "markdown", |_, _, _| Ok(())));
+        let formats = registry.formats();
+        assert_eq!(formats.iter().filter(|f| f.name == "md").count(), 1);
+        assert_eq!(registry.get("md").unwrap().extension, "markdown");
+    }
+
+    #[test]
+    fn test_write_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("out.txt");
+
+        write_file(&path, |out| {
+            for i in 0..3 {
+                writeln!(out, "line {}", i).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "line 0\nline 1\nline 2\n"
+        );
+
+        // 失败时保留原文件，不留下临时文件
+        let err = write_file(&path, |out| {
+            out.write_all(b"partial").map_err(|e| e.to_string())?;
+            Err("boom".to_string())
+        });
+        assert_eq!(err, Err("boom".to_string()));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "line 0\nline 1\nline 2\n"
+        );
+        assert_eq!(fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+    }
+}
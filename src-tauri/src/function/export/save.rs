@@ -4,6 +4,7 @@ use std::path::{Path, PathBuf};
 use anyhow::Result;
 use tauri_plugin_dialog::DialogExt;
 
+use super::pipeline::Registry;
 use crate::utils::AppPaths;
 
 /// Gets the path to the export directory
@@ -48,6 +49,8 @@ pub async fn select_save_path(
         "json" => "JSON Files",
         "md" => "Markdown Files",
         "csv" => "CSV Files",
+        "html" => "HTML Files",
+        "org" => "Org Files",
         _ => "Text Files",
     };
 
@@ -91,14 +94,7 @@ pub async fn save_export_file(
         let path = Path::new(&path);
         if path.is_dir() {
             // 如果是目录，则在该目录下创建文件
-            let extension = match format.to_lowercase().as_str() {
-                "ics" => "ics",
-                "json" => "json",
-                "markdown" | "md" => "md",
-                "csv" => "csv",
-                _ => "txt",
-            };
-
+            let extension = extension_for(&format);
             let safe_filename = sanitize_filename(&filename);
             let full_filename = format!("{}.{}", safe_filename, extension);
             PathBuf::from(path).join(full_filename)
@@ -114,13 +110,7 @@ pub async fn save_export_file(
         };
 
         // 根据格式创建扩展名
-        let extension = match format.to_lowercase().as_str() {
-            "ics" => "ics",
-            "json" => "json",
-            "markdown" | "md" => "md",
-            "csv" => "csv",
-            _ => "txt",
-        };
+        let extension = extension_for(&format);
 
         // 创建文件名
         let safe_filename = sanitize_filename(&filename);
@@ -141,8 +131,20 @@ pub async fn save_export_file(
     Ok(file_path.to_string_lossy().to_string())
 }
 
+/// 由导出格式名取得文件扩展名，与导出管道中注册的格式保持一致，未知格式使用 `txt`
+fn extension_for(format: &str) -> String {
+    let lower = format.to_lowercase();
+    let name = if lower == "markdown" { "md" } else { &lower };
+    let registry = Registry::with_templates();
+    registry
+        .get(format)
+        .or_else(|| registry.get(name))
+        .map(|exporter| exporter.extension.clone())
+        .unwrap_or_else(|| "txt".to_string())
+}
+
 /// 清理文件名，移除不安全的字符
-fn sanitize_filename(filename: &str) -> String {
+pub(crate) fn sanitize_filename(filename: &str) -> String {
     // 移除不安全字符
     let forbidden_chars = r#"/\?%*:|"<>#;={}@^~[]`"#;
     let mut safe_filename = filename.to_string();
@@ -160,3 +162,17 @@ fn sanitize_filename(filename: &str) -> String {
 
     safe_filename
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension_for() {
+        assert_eq!(extension_for("org"), "org");
+        assert_eq!(extension_for("Markdown"), "md");
+        assert_eq!(extension_for("ics-event"), "ics");
+        assert_eq!(extension_for("todotxt"), "txt");
+        assert_eq!(extension_for("unknown"), "txt");
+    }
+}
@@ -0,0 +1,307 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{Local, TimeZone};
+use minijinja::{path_loader, Environment, Error, ErrorKind};
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::entity::event::Priority;
+use crate::entity::{Event, List, Repository, Storage};
+use crate::utils::AppPaths;
+
+/// 首次使用时写入模板目录的示例模板
+const EXAMPLE_NAME: &str = "example.md";
+const EXAMPLE: &str = r#"# 事件报告
+
+导出时间：{{ exported_at | datetime }}，共 {{ events | length }} 个事件
+{% for list in lists %}
+
+## {{ list.title or "未分类" }}
+
+{% for event in list.events %}
+- [{{ "x" if event.finished else " " }}] {{ event.title }}
+  {%- if event.priority %} ({{ event.priority }}){% endif %}
+  {%- if event.due %} 截止 {{ event.due | datetime("%Y-%m-%d") }}{% endif %}
+  {%- for tag in event.tags %} #{{ tag }}{% endfor %}
+
+{% endfor %}
+{% endfor %}
+"#;
+
+/// 模板中可访问的事件字段，时间均为毫秒时间戳，可用 `datetime` 过滤器格式化
+#[derive(Serialize, Clone)]
+struct TemplateEvent {
+    id: String,
+    title: String,
+    content: String, // Markdown内容
+    list: Option<String>,
+    list_id: Option<String>,
+    tags: Vec<String>,
+    priority: Option<&'static str>, // High、Medium、Low
+    due: Option<u64>,
+    created: u64,
+    finished: bool,
+    recurrence: Option<String>,
+    estimate: Option<u32>,
+    icon: String,
+    color: String,
+}
+
+/// 按列表分组的事件，未归属列表的事件 `id` 与 `title` 为空
+#[derive(Serialize)]
+struct TemplateList {
+    id: Option<String>,
+    title: Option<String>,
+    events: Vec<TemplateEvent>,
+}
+
+#[derive(Serialize)]
+struct TemplateContext {
+    exported_at: u64,
+    events: Vec<TemplateEvent>,
+    lists: Vec<TemplateList>,
+}
+
+/// 用户模板目录
+pub fn templates_dir() -> PathBuf {
+    AppPaths::config_dir().join("templates")
+}
+
+/// 将毫秒时间戳按本地时间格式化，默认格式为 `%Y-%m-%d %H:%M`，格式无效时渲染失败
+fn datetime(millis: u64, format: Option<String>) -> Result<String, Error> {
+    let format = format.as_deref().unwrap_or("%Y-%m-%d %H:%M");
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.contains(&Item::Error) {
+        return Err(Error::new(
+            ErrorKind::InvalidOperation,
+            format!("无效的时间格式: {}", format),
+        ));
+    }
+    Ok(Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|dt| dt.format_with_items(items.iter()).to_string())
+        .unwrap_or_default())
+}
+
+/// 创建从模板目录加载模板的环境，模板之间可以使用 `include` 与 `extends`
+///
+/// 按模板文件扩展名自动转义，`.html` 模板中的字段会进行HTML转义。
+fn environment(dir: &Path) -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_loader(path_loader(dir));
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.add_filter("datetime", datetime);
+    env
+}
+
+fn template_event(event: &Event, lists: &[List]) -> TemplateEvent {
+    let list = event
+        .metadata
+        .list
+        .as_ref()
+        .and_then(|id| lists.iter().find(|list| &list.uuid == id));
+    TemplateEvent {
+        id: event.metadata.uuid.clone(),
+        title: event.title.clone(),
+        content: fs::read_to_string(&event.content).unwrap_or_default(),
+        list: list.map(|list| list.title.clone()),
+        list_id: event.metadata.list.clone(),
+        tags: event.metadata.tag.clone().unwrap_or_default(),
+        priority: match event.priority {
+            Priority::High => Some("High"),
+            Priority::Medium => Some("Medium"),
+            Priority::Low => Some("Low"),
+            Priority::Undefined => None,
+        },
+        due: event.task_time,
+        created: event.metadata.timestamp,
+        finished: event.finished,
+        recurrence: event.recurrence.clone(),
+        estimate: event.estimate,
+        icon: event.icon.clone(),
+        color: event.color.clone(),
+    }
+}
+
+/// 使用模板目录中名为 `name` 的模板渲染事件
+///
+/// 模板可访问 `exported_at`、`events` 以及按列表分组的 `lists`，
+/// 列表顺序与存储中一致，未归属列表的事件放在最后。
+pub fn render_template(
+    dir: &Path,
+    name: &str,
+    events: &[Event],
+    lists: &[List],
+    exported_at: u64,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let env = environment(dir);
+    let template = env
+        .get_template(name)
+        .map_err(|e| format!("加载模板 {} 失败: {}", name, e))?;
+
+    let mut groups: Vec<TemplateList> = lists
+        .iter()
+        .map(|list| TemplateList {
+            id: Some(list.uuid.clone()),
+            title: Some(list.title.clone()),
+            events: Vec::new(),
+        })
+        .collect();
+    groups.push(TemplateList {
+        id: None,
+        title: None,
+        events: Vec::new(),
+    });
+    let events: Vec<TemplateEvent> = events
+        .iter()
+        .map(|event| template_event(event, lists))
+        .collect();
+    for item in &events {
+        let index = groups
+            .iter()
+            .position(|group| group.id.is_some() && group.id == item.list_id)
+            .unwrap_or(groups.len() - 1);
+        groups[index].events.push(item.clone());
+    }
+    groups.retain(|group| !group.events.is_empty());
+
+    let context = TemplateContext {
+        exported_at,
+        events,
+        lists: groups,
+    };
+    template
+        .render_captured_to(context, out)
+        .map(|_| ())
+        .map_err(|e| format!("渲染模板 {} 失败: {}", name, e))
+}
+
+/// 使用用户模板导出多个事件
+pub fn write_template(
+    storage: &Storage,
+    events: &[Event],
+    name: &str,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let lists = Repository::<List>::get_all(storage).map_err(|e| e.to_string())?;
+    let now = chrono::Utc::now().timestamp_millis() as u64;
+    render_template(&templates_dir(), name, events, &lists, now, out)
+}
+
+/// Lists the user-defined export templates
+///
+/// Templates are files in the `templates` folder of the config directory. The folder
+/// is created with an example template when it does not exist yet. A template is
+/// selected in the export commands with the format `template:<name>`, and the
+/// extension of its name is the extension of the exported file.
+///
+/// # Returns
+/// * `Result<Vec<String>, String>` - Sorted template file names or error message
+#[tauri::command]
+pub async fn list_export_templates() -> Result<Vec<String>, String> {
+    let dir = templates_dir();
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| format!("无法创建模板目录: {}", e))?;
+        fs::write(dir.join(EXAMPLE_NAME), EXAMPLE)
+            .map_err(|e| format!("无法写入示例模板: {}", e))?;
+    }
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .map_err(|e| format!("无法读取模板目录: {}", e))?
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::export::pipeline::collect;
+
+    fn render(dir: &Path, name: &str, events: &[Event], lists: &[List]) -> Result<String, String> {
+        collect(|out| render_template(dir, name, events, lists, 0, out))
+    }
+
+    fn fixtures() -> (Vec<Event>, Vec<List>) {
+        let list = List {
+            uuid: "work".to_string(),
+            title: "Work".to_string(),
+            icon: "default".to_string(),
+        };
+        let mut report = Event::new("Write <report>", "");
+        report.metadata.list = Some("work".to_string());
+        report.metadata.tag = Some(vec!["q4".to_string()]);
+        report.priority = Priority::High;
+        report.task_time = Some(
+            Local
+                .with_ymd_and_hms(2026, 10, 20, 18, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        );
+        let mut loose = Event::new("Water plants", "");
+        loose.finished = true;
+        (vec![loose, report], vec![list])
+    }
+
+    #[test]
+    fn test_render_example() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(EXAMPLE_NAME), EXAMPLE).unwrap();
+        let (events, lists) = fixtures();
+
+        let text = render(dir.path(), EXAMPLE_NAME, &events, &lists).unwrap();
+        let work = text.find("## Work").unwrap();
+        let loose = text.find("## 未分类").unwrap();
+        assert!(work < loose);
+        assert!(text.contains("共 2 个事件"));
+        assert!(text.contains("- [ ] Write <report> (High) 截止 2026-10-20 #q4\n"));
+        assert!(text.contains("- [x] Water plants\n"));
+    }
+
+    #[test]
+    fn test_render_html_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("base.html"),
+            "<ul>{% block items %}{% endblock %}</ul>",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("report.html"),
+            "{% extends \"base.html\" %}{% block items %}{% for e in events if not e.finished %}<li>{{ e.title }}</li>{% endfor %}{% endblock %}",
+        )
+        .unwrap();
+        let (events, lists) = fixtures();
+
+        let text = render(dir.path(), "report.html", &events, &lists).unwrap();
+        assert_eq!(text, "<ul><li>Write &lt;report&gt;</li></ul>");
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("broken.md"), "{% for e in events %}").unwrap();
+        let (events, lists) = fixtures();
+
+        assert!(render(dir.path(), "missing.md", &events, &lists).is_err());
+        assert!(render(dir.path(), "../secret.md", &events, &lists).is_err());
+        assert!(render(dir.path(), "broken.md", &events, &lists).is_err());
+
+        // 无效的时间格式返回错误而不是崩溃
+        fs::write(
+            dir.path().join("bad_date.md"),
+            "{{ exported_at | datetime(\"%Q\") }}",
+        )
+        .unwrap();
+        let err = render(dir.path(), "bad_date.md", &events, &lists).unwrap_err();
+        assert!(err.contains("无效的时间格式"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::entity::event::Priority;
+use crate::entity::{Event, List, Repository, Storage};
+use crate::function::convert::{local_date, parse_simple_rrule};
+
+/// todo.txt 中的单词不能包含空白
+fn word(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// 标签中的空白写为 `%20`、`%` 写为 `%25`，导入时还原
+pub(crate) fn escape_tag(tag: &str) -> String {
+    tag.replace('%', "%25")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("%20")
+}
+
+/// 将 RRULE 转换为 `rec:` 扩展的值，仅支持带间隔的简单频率
+fn rrule_to_rec(rrule: &str) -> Option<String> {
+    let (freq, interval) = parse_simple_rrule(rrule)?;
+    Some(format!("{}{}", interval, freq.letter()))
+}
+
+/// 将单个事件转换为一行 todo.txt
+///
+/// 列表写为 `+project`，标签转义后写为 `@context`，截止时间写为 `due:`，事件UUID写为 `id:`。
+/// 已完成的事件按规范把优先级移到 `pri:`，且因没有记录完成日期而省略日期。
+fn export_event_to_todotxt(event: &Event, lists: &HashMap<String, String>) -> String {
+    let priority = match event.priority {
+        Priority::High => Some('A'),
+        Priority::Medium => Some('B'),
+        Priority::Low => Some('C'),
+        Priority::Undefined => None,
+    };
+
+    let mut parts = Vec::new();
+    if event.finished {
+        parts.push("x".to_string());
+    } else {
+        if let Some(priority) = priority {
+            parts.push(format!("({})", priority));
+        }
+        parts.extend(local_date(event.metadata.timestamp));
+    }
+    parts.push(event.title.split_whitespace().collect::<Vec<_>>().join(" "));
+
+    if let Some(list) = event.metadata.list.as_ref().and_then(|id| lists.get(id)) {
+        parts.push(format!("+{}", word(list)));
+    }
+    for tag in event.metadata.tag.iter().flatten() {
+        parts.push(format!("@{}", escape_tag(tag)));
+    }
+    if let Some(due) = event.task_time.and_then(local_date) {
+        parts.push(format!("due:{}", due));
+    }
+    if let Some(rec) = event.recurrence.as_deref().and_then(rrule_to_rec) {
+        parts.push(format!("rec:{}", rec));
+    }
+    if let Some(priority) = priority.filter(|_| event.finished) {
+        parts.push(format!("pri:{}", priority));
+    }
+    parts.push(format!("id:{}", event.metadata.uuid));
+    parts.join(" ")
+}
+
+/// 导出多个事件为 todo.txt 格式，每个事件一行
+pub fn write_todotxt(
+    storage: &Storage,
+    events: &[Event],
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let lists: HashMap<String, String> = Repository::<List>::get_all(storage)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|list| (list.uuid, list.title))
+        .collect();
+
+    for event in events {
+        writeln!(out, "{}", export_event_to_todotxt(event, &lists)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn test_export_event_to_todotxt() {
+        let mut event = Event::new("Call   plumber", "");
+        event.metadata.timestamp = Local
+            .with_ymd_and_hms(2026, 10, 10, 9, 0, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        event.metadata.list = Some("home-id".to_string());
+        event.metadata.tag = Some(vec!["phone".to_string(), "on hold".to_string()]);
+        event.priority = Priority::High;
+        event.task_time = Some(
+            Local
+                .with_ymd_and_hms(2026, 10, 20, 18, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        );
+        event.recurrence = Some("FREQ=WEEKLY;INTERVAL=2".to_string());
+        let lists = HashMap::from([("home-id".to_string(), "Home Repairs".to_string())]);
+
+        assert_eq!(
+            export_event_to_todotxt(&event, &lists),
+            format!(
+                "(A) 2026-10-10 Call plumber +Home_Repairs @phone @on%20hold due:2026-10-20 rec:2w id:{}",
+                event.metadata.uuid
+            )
+        );
+
+        event.finished = true;
+        event.recurrence = Some("FREQ=MONTHLY;BYMONTHDAY=1".to_string());
+        assert_eq!(
+            export_event_to_todotxt(&event, &HashMap::new()),
+            format!(
+                "x Call plumber @phone @on%20hold due:2026-10-20 pri:A id:{}",
+                event.metadata.uuid
+            )
+        );
+    }
+}
@@ -0,0 +1,479 @@
+mod csv;
+pub mod ics;
+mod json;
+pub(crate) mod md;
+mod org;
+mod todotxt;
+
+use self::csv::*;
+use self::ics::*;
+use self::json::*;
+use self::md::*;
+use self::org::*;
+use self::todotxt::*;
+
+use anyhow::Result;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::DerefMut;
+use std::path::Path;
+use tauri::State;
+use ts_rs::TS;
+
+use crate::entity::event::{EventMetadata, Priority};
+use crate::entity::tag::{Tag, TagColor};
+use crate::entity::{Event, List, Repository, Storage, StorageState};
+use crate::function::backup::backup_before;
+use crate::function::convert::parse_simple_rrule;
+use crate::function::export::csv::CsvColumn;
+use crate::utils::AppPaths;
+
+/// 从外部格式解析出的事件，尚未写入数据库
+#[derive(Debug, Clone, PartialEq)]
+pub struct Draft {
+    pub uid: Option<String>, // 来源中的唯一标识，用于去重
+    pub title: String,
+    pub description: Option<String>, // Markdown 正文，来源格式没有正文时为 None
+    pub task_time: Option<u64>,      // 毫秒时间戳
+    pub priority: Priority,
+    pub finished: bool,
+    pub tags: Vec<String>,
+    pub recurrence: Option<String>,
+    pub list: Option<String>,  // 所属列表的名称，不存在时创建
+    pub created: Option<u64>,  // 创建时间，毫秒时间戳
+    pub estimate: Option<u32>, // 预计耗时（分钟）
+    pub lossy: bool,           // 来源格式只有日期和简单重复规则，如 Obsidian Tasks 与 todo.txt
+}
+
+impl Draft {
+    pub fn new(title: &str) -> Self {
+        Self {
+            uid: None,
+            title: title.to_string(),
+            description: None,
+            task_time: None,
+            priority: Priority::Undefined,
+            finished: false,
+            tags: Vec::new(),
+            recurrence: None,
+            list: None,
+            created: None,
+            estimate: None,
+            lossy: false,
+        }
+    }
+}
+
+/// 导入时创建或更新的事件
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct ImportedItem {
+    pub id: String,
+    pub title: String,
+}
+
+/// 导入时跳过的条目及原因
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct SkippedItem {
+    pub title: String,
+    pub reason: String,
+}
+
+/// 导入结果
+#[derive(Serialize, Deserialize, TS, Clone, Debug, Default)]
+#[ts(export)]
+pub struct ImportReport {
+    pub created: Vec<ImportedItem>,
+    pub updated: Vec<ImportedItem>,
+    pub skipped: Vec<SkippedItem>,
+}
+
+impl ImportReport {
+    pub fn skip(&mut self, title: &str, reason: &str) {
+        self.skipped.push(SkippedItem {
+            title: title.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+}
+
+// 为导入的事件创建Markdown内容文件，目录名与已有事件重复时追加序号
+pub(crate) fn create_content(title: &str, description: &str) -> Result<String> {
+    let name: String = title
+        .chars()
+        .map(|c| {
+            if matches!(c, '/' | '\\' | ':') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let mut content_dir = AppPaths::data_dir().join(&name);
+    let mut n = 2;
+    while content_dir.exists() {
+        content_dir = AppPaths::data_dir().join(format!("{} ({})", name, n));
+        n += 1;
+    }
+    fs::create_dir_all(&content_dir)?;
+    let content_path = content_dir.join(format!("{}.md", name));
+    fs::write(&content_path, description)?;
+    Ok(content_path.to_string_lossy().to_string())
+}
+
+// 确保标签存在，新标签使用默认颜色
+fn ensure_tags(storage: &Storage, tags: &[String]) -> Result<()> {
+    let existing = Repository::<Tag>::get_all(storage)?;
+    for tag in tags {
+        if !existing.iter().any(|t| &t.name == tag) {
+            Repository::<Tag>::add(storage, &Tag::new(tag.clone(), TagColor::Primary))?;
+        }
+    }
+    Ok(())
+}
+
+// 列表名称忽略大小写，空格与下划线视为相同（todo.txt 的 +project 不能包含空格）
+fn same_list_name(a: &str, b: &str) -> bool {
+    a.replace(' ', "_")
+        .eq_ignore_ascii_case(&b.replace(' ', "_"))
+}
+
+// 按名称查找列表，不存在时创建
+fn ensure_list(storage: &Storage, name: &str) -> Result<String> {
+    let existing = Repository::<List>::filter(storage, |list| same_list_name(&list.title, name))?;
+    if let Some(list) = existing.into_iter().next() {
+        return Ok(list.uuid);
+    }
+    let list = List::new(name, "mdi-format-list-bulleted");
+    Repository::<List>::add(storage, &list)?;
+    Ok(list.uuid)
+}
+
+// 简化的来源格式只有日期，日期未变时保留已有事件的具体时间
+fn merged_task_time(event: &Event, draft: &Draft) -> Option<u64> {
+    let date = |millis: u64| {
+        Local
+            .timestamp_millis_opt(millis as i64)
+            .single()
+            .map(|dt| dt.date_naive())
+    };
+    match (event.task_time, draft.task_time) {
+        (Some(old), Some(new)) if draft.lossy && date(old) == date(new) => Some(old),
+        _ => draft.task_time,
+    }
+}
+
+// 简化的来源格式无法表示的重复规则（如 BYDAY）在导出时被省略，导入时保留
+fn merged_recurrence(event: &Event, draft: &Draft) -> Option<String> {
+    match (&event.recurrence, &draft.recurrence) {
+        (Some(old), None) if draft.lossy && parse_simple_rrule(old).is_none() => Some(old.clone()),
+        _ => draft.recurrence.clone(),
+    }
+}
+
+fn same_as(event: &Event, draft: &Draft) -> bool {
+    let same_content = || {
+        fs::read_to_string(&event.content).is_ok_and(|content| Some(content) == draft.description)
+    };
+    event.title == draft.title
+        && (draft.description.is_none() || same_content())
+        && event.task_time == merged_task_time(event, draft)
+        && event.priority == draft.priority
+        && event.finished == draft.finished
+        && event.metadata.tag.clone().unwrap_or_default() == draft.tags
+        && event.recurrence == merged_recurrence(event, draft)
+        && (draft.estimate.is_none() || event.estimate == draft.estimate)
+}
+
+/// 将解析出的事件写入数据库
+///
+/// 来源UID与已有事件的导入标识或UUID相同时更新该事件，内容完全相同则跳过；
+/// 草稿没有正文时不改动已有事件的内容文件；来源格式只有日期时保留同一天内的原有时间，
+/// 并保留其无法表示的重复规则。
+/// 新事件放入草稿指定的列表，未指定时放入 `listid`。
+pub fn apply(
+    storage: &Storage,
+    drafts: Vec<Draft>,
+    listid: Option<&str>,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let existing = Repository::<Event>::get_all(storage)?;
+    for draft in drafts {
+        ensure_tags(storage, &draft.tags)?;
+        let tags = if draft.tags.is_empty() {
+            None
+        } else {
+            Some(draft.tags.clone())
+        };
+
+        let matched = draft.uid.as_ref().and_then(|uid| {
+            existing.iter().find(|event| {
+                event.metadata.external_id.as_ref() == Some(uid) || &event.metadata.uuid == uid
+            })
+        });
+        match matched {
+            Some(event) if same_as(event, &draft) => {
+                report.skip(&draft.title, "与已有事件相同");
+            }
+            Some(event) => {
+                let mut event = event.clone();
+                // 来源格式没有正文时保留原有内容
+                if let Some(description) = &draft.description {
+                    fs::write(&event.content, description)?;
+                }
+                event.task_time = merged_task_time(&event, &draft);
+                event.recurrence = merged_recurrence(&event, &draft);
+                event.title = draft.title;
+                event.priority = draft.priority;
+                event.finished = draft.finished;
+                event.metadata.tag = tags;
+                // 来源格式没有预计耗时时保留原值
+                if draft.estimate.is_some() {
+                    event.estimate = draft.estimate;
+                }
+                if let Some(name) = &draft.list {
+                    event.metadata.list = Some(ensure_list(storage, name)?);
+                }
+                Repository::<Event>::add(storage, &event)?;
+                report.updated.push(ImportedItem {
+                    id: event.metadata.uuid,
+                    title: event.title,
+                });
+            }
+            None => {
+                let mut metadata = EventMetadata::new();
+                metadata.list = match &draft.list {
+                    Some(name) => Some(ensure_list(storage, name)?),
+                    None => listid.map(|id| id.to_string()),
+                };
+                if let Some(created) = draft.created {
+                    metadata.timestamp = created;
+                }
+                metadata.tag = tags;
+                metadata.external_id = draft.uid;
+                let event = Event {
+                    metadata,
+                    content: create_content(
+                        &draft.title,
+                        draft.description.as_deref().unwrap_or_default(),
+                    )?,
+                    title: draft.title,
+                    task_time: draft.task_time,
+                    finished: draft.finished,
+                    priority: draft.priority,
+                    color: "default".to_string(),
+                    icon: "default".to_string(),
+                    recurrence: draft.recurrence,
+                    estimate: draft.estimate,
+                };
+                Repository::<Event>::add(storage, &event)?;
+                report.created.push(ImportedItem {
+                    id: event.metadata.uuid,
+                    title: event.title,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Imports events from another calendar or todo application
+///
+/// Parses the given content and creates an event for every entry. Entries that
+/// were imported before (matched by their source UID) are updated in place, or
+/// skipped when nothing changed.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `content` - Text content of the file to import
+/// * `fmt` - Format of the content: "ics", "todotxt", "org" or "md" (Obsidian Tasks lines)
+/// * `listid` - Optional list to put newly created events in
+///
+/// # Returns
+/// * `Result<ImportReport, String>` - Created, updated and skipped entries or error message
+#[tauri::command]
+pub async fn import_events(
+    state: State<'_, StorageState>,
+    content: String,
+    fmt: &str,
+    listid: Option<String>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+    let drafts = match fmt {
+        "ics" => parse_ics(&content, &mut report),
+        "md" => parse_tasks(&content, &mut report),
+        "todotxt" => parse_todotxt(&content, &mut report),
+        "org" => parse_org(&content, &mut report),
+        _ => return Err("Unsupported format".to_string()),
+    };
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    apply(storage, drafts, listid.as_deref(), &mut report).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Imports tasks from a todo.txt file
+///
+/// `+project` puts the task in the list of that name (created when missing),
+/// `@context` becomes a tag and `due:` the deadline. Tasks exported with an
+/// `id:` are matched on re-import.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `content` - Text content of the todo.txt file
+/// * `listid` - Optional list for tasks without a `+project`
+///
+/// # Returns
+/// * `Result<ImportReport, String>` - Created, updated and skipped tasks or error message
+#[tauri::command]
+pub async fn import_todotxt(
+    state: State<'_, StorageState>,
+    content: String,
+    listid: Option<String>,
+) -> Result<ImportReport, String> {
+    import_events(state, content, "todotxt", listid).await
+}
+
+/// Imports headlines from an Emacs Org-mode file
+///
+/// Headlines with a TODO keyword become events and top-level headlines without
+/// one become lists (created when missing). `DEADLINE` (or `SCHEDULED`) sets the
+/// deadline and its repeater the recurrence. Headlines exported with an `:ID:`
+/// property are matched on re-import.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `content` - Text content of the org file
+/// * `listid` - Optional list for headlines outside a top-level list heading
+///
+/// # Returns
+/// * `Result<ImportReport, String>` - Created, updated and skipped headlines or error message
+#[tauri::command]
+pub async fn import_org(
+    state: State<'_, StorageState>,
+    content: String,
+    listid: Option<String>,
+) -> Result<ImportReport, String> {
+    import_events(state, content, "org", listid).await
+}
+
+/// Reads the header and first rows of a CSV file for the column mapping step
+///
+/// Comma, semicolon and tab separated files are detected from the header row.
+/// The returned mapping is guessed from the header names and can be edited by
+/// the user before calling `import_csv`.
+///
+/// # Parameters
+/// * `content` - Text content of the CSV file
+///
+/// # Returns
+/// * `Result<CsvPreview, String>` - Headers, sample rows and suggested mapping or error message
+#[tauri::command]
+pub async fn preview_csv_import(content: String) -> Result<CsvPreview, String> {
+    preview_csv(&content).map_err(|e| e.to_string())
+}
+
+/// Imports rows of a CSV file as events using a column mapping
+///
+/// Every row after the header becomes an event. Rows with a missing title or
+/// values that cannot be read (dates, priority, finished) are reported as skipped.
+/// A mapped list column puts the event in the list of that name (created when
+/// missing); rows without one go to `listid`.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `content` - Text content of the CSV file
+/// * `mapping` - Target column for every CSV column, `null` to ignore it; exactly one must be `Title`
+/// * `listid` - Optional list for rows without a list column
+///
+/// # Returns
+/// * `Result<ImportReport, String>` - Created, updated and skipped rows or error message
+#[tauri::command]
+pub async fn import_csv(
+    state: State<'_, StorageState>,
+    content: String,
+    mapping: Vec<Option<CsvColumn>>,
+    listid: Option<String>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+    let drafts = parse_csv(&content, &mapping, &mut report).map_err(|e| e.to_string())?;
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    apply(storage, drafts, listid.as_deref(), &mut report).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Imports Obsidian Tasks lines from a markdown file or folder
+///
+/// Scans the file, or every `.md` file below the folder (hidden folders such as
+/// `.obsidian` are skipped), and turns `- [ ] Task 📅 2026-10-20 #tag` lines into
+/// events. Tasks exported with an 🆔 are matched on re-import.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `path` - Path of a markdown file or a folder of notes
+/// * `listid` - Optional list to put newly created events in
+///
+/// # Returns
+/// * `Result<ImportReport, String>` - Created, updated and skipped tasks or error message
+#[tauri::command]
+pub async fn import_markdown(
+    state: State<'_, StorageState>,
+    path: String,
+    listid: Option<String>,
+) -> Result<ImportReport, String> {
+    let mut report = ImportReport::default();
+    let mut drafts = Vec::new();
+    for file in collect_markdown(Path::new(&path)).map_err(|e| e.to_string())? {
+        match fs::read_to_string(&file) {
+            Ok(text) => drafts.extend(parse_tasks(&text, &mut report)),
+            Err(e) => report.skip(&file.to_string_lossy(), &format!("读取失败: {}", e)),
+        }
+    }
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    apply(storage, drafts, listid.as_deref(), &mut report).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Imports a JSON export of this application
+///
+/// Reads the versioned JSON format produced by the JSON export, including lists,
+/// tags, markdown content and attachments. Events keep their original UUIDs.
+/// Attachments are only read from their inline data. In `Replace` mode the
+/// current data is saved to a "before-import" backup before it is cleared.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `content` - Text content of the exported JSON file
+/// * `mode` - `Merge` to keep existing data, `Replace` to clear it first
+///
+/// # Returns
+/// * `Result<ImportReport, String>` - Created, updated and skipped events or error message
+#[tauri::command]
+pub async fn import_json(
+    state: State<'_, StorageState>,
+    content: String,
+    mode: ImportMode,
+) -> Result<ImportReport, String> {
+    let archive = parse_archive(&content).map_err(|e| e.to_string())?;
+    let mut report = ImportReport::default();
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    if mode == ImportMode::Replace {
+        let safety =
+            backup_before(storage, "ToDoPulse-before-import-").map_err(|e| e.to_string())?;
+        log::info!(
+            "Saved current data to {} before importing",
+            safety.display()
+        );
+    }
+    import_archive(storage, archive, mode, &mut report).map_err(|e| e.to_string())?;
+    Ok(report)
+}
@@ -0,0 +1,383 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::{Draft, ImportReport};
+use crate::entity::event::Priority;
+use crate::function::export::csv::CsvColumn;
+
+/// 预览时展示的数据行数
+const PREVIEW_ROWS: usize = 5;
+
+/// CSV文件的预览，供用户确认列映射
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct CsvPreview {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// 根据表头推测的映射，与 `headers` 一一对应，None 表示忽略该列
+    pub mapping: Vec<Option<CsvColumn>>,
+}
+
+// 表头别名，比较时忽略大小写
+const ALIASES: &[(CsvColumn, &[&str])] = &[
+    (
+        CsvColumn::Title,
+        &[
+            "title", "name", "task", "subject", "summary", "标题", "任务", "名称",
+        ],
+    ),
+    (CsvColumn::List, &["list", "project", "列表", "项目"]),
+    (CsvColumn::Tags, &["tags", "tag", "labels", "label", "标签"]),
+    (CsvColumn::Priority, &["priority", "优先级"]),
+    (
+        CsvColumn::Due,
+        &[
+            "due",
+            "due date",
+            "deadline",
+            "截止",
+            "截止日期",
+            "截止时间",
+        ],
+    ),
+    (
+        CsvColumn::Finished,
+        &["finished", "done", "completed", "完成", "已完成"],
+    ),
+    (
+        CsvColumn::Created,
+        &[
+            "created",
+            "created at",
+            "creation date",
+            "创建时间",
+            "创建日期",
+        ],
+    ),
+    (
+        CsvColumn::Content,
+        &[
+            "content",
+            "notes",
+            "note",
+            "description",
+            "内容",
+            "备注",
+            "描述",
+        ],
+    ),
+    (CsvColumn::Id, &["id", "uuid", "uid"]),
+];
+
+/// 根据第一行推测分隔符，支持逗号、分号与制表符
+fn delimiter(text: &str) -> u8 {
+    let first = text.lines().next().unwrap_or_default();
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|&d| first.matches(d as char).count())
+        .filter(|&d| first.contains(d as char))
+        .unwrap_or(b',')
+}
+
+fn reader(text: &str) -> csv::Reader<&[u8]> {
+    csv::ReaderBuilder::new()
+        .delimiter(delimiter(text))
+        .flexible(true)
+        .from_reader(text.as_bytes())
+}
+
+/// 推测表头对应的列，每种列最多映射一次
+fn suggest_mapping(headers: &[String]) -> Vec<Option<CsvColumn>> {
+    let mut used = Vec::new();
+    headers
+        .iter()
+        .map(|header| {
+            let header = header.trim().to_lowercase();
+            let column = ALIASES
+                .iter()
+                .find(|(column, aliases)| !used.contains(column) && aliases.contains(&&*header))
+                .map(|(column, _)| *column)?;
+            used.push(column);
+            Some(column)
+        })
+        .collect()
+}
+
+/// 读取表头与前几行数据并推测列映射
+pub fn preview_csv(text: &str) -> Result<CsvPreview> {
+    let mut reader = reader(text);
+    let headers: Vec<String> = reader.headers()?.iter().map(|h| h.to_string()).collect();
+    let mut rows = Vec::new();
+    for record in reader.records().take(PREVIEW_ROWS) {
+        rows.push(record?.iter().map(|c| c.to_string()).collect());
+    }
+    Ok(CsvPreview {
+        mapping: suggest_mapping(&headers),
+        headers,
+        rows,
+    })
+}
+
+fn parse_time(text: &str) -> Option<u64> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Some(dt.timestamp_millis() as u64);
+    }
+    let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(text, fmt).ok())
+        .or_else(|| {
+            ["%Y-%m-%d", "%Y/%m/%d"]
+                .iter()
+                .find_map(|fmt| NaiveDate::parse_from_str(text, fmt).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    let local = Local.from_local_datetime(&naive).earliest()?;
+    Some(local.timestamp_millis() as u64)
+}
+
+fn parse_priority(text: &str) -> Option<Priority> {
+    match text.to_lowercase().as_str() {
+        "" | "none" | "无" => Some(Priority::Undefined),
+        "high" | "h" | "a" | "1" | "高" => Some(Priority::High),
+        "medium" | "m" | "b" | "2" | "中" => Some(Priority::Medium),
+        "low" | "l" | "c" | "3" | "低" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_finished(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" | "done" | "✓" | "是" | "已完成" => Some(true),
+        "" | "false" | "no" | "n" | "0" | "否" | "未完成" => Some(false),
+        _ => None,
+    }
+}
+
+/// 按映射将一行转换为草稿，无法识别的值作为跳过原因返回
+///
+/// 没有映射内容列时草稿不带正文，更新已有事件时保留其内容。
+fn parse_record(
+    record: &csv::StringRecord,
+    mapping: &[Option<CsvColumn>],
+) -> Result<Draft, String> {
+    let mut draft = Draft::new("");
+    for (value, column) in record.iter().zip(mapping) {
+        let value = value.trim();
+        let Some(column) = column else {
+            continue;
+        };
+        match column {
+            CsvColumn::Title => draft.title = value.to_string(),
+            CsvColumn::List => {
+                draft.list = Some(value.to_string()).filter(|list| !list.is_empty());
+            }
+            CsvColumn::Tags => {
+                for tag in value.split([';', ',']).map(str::trim) {
+                    if !tag.is_empty() && !draft.tags.iter().any(|t| t == tag) {
+                        draft.tags.push(tag.to_string());
+                    }
+                }
+            }
+            CsvColumn::Priority => {
+                draft.priority =
+                    parse_priority(value).ok_or_else(|| format!("无法识别优先级: {}", value))?;
+            }
+            CsvColumn::Due if !value.is_empty() => {
+                draft.task_time =
+                    Some(parse_time(value).ok_or_else(|| format!("无法解析截止时间: {}", value))?);
+            }
+            CsvColumn::Created if !value.is_empty() => {
+                draft.created =
+                    Some(parse_time(value).ok_or_else(|| format!("无法解析创建时间: {}", value))?);
+            }
+            CsvColumn::Due | CsvColumn::Created => {}
+            CsvColumn::Finished => {
+                draft.finished =
+                    parse_finished(value).ok_or_else(|| format!("无法识别完成状态: {}", value))?;
+            }
+            CsvColumn::Content => draft.description = Some(value.to_string()),
+            CsvColumn::Id => draft.uid = Some(value.to_string()).filter(|id| !id.is_empty()),
+        }
+    }
+    if draft.title.is_empty() {
+        return Err("缺少标题".to_string());
+    }
+    Ok(draft)
+}
+
+/// 按列映射解析CSV，第一行为表头
+///
+/// `mapping` 与表头的列一一对应，None 表示忽略该列，必须且只能有一列映射为标题。
+pub fn parse_csv(
+    text: &str,
+    mapping: &[Option<CsvColumn>],
+    report: &mut ImportReport,
+) -> Result<Vec<Draft>> {
+    for (i, column) in mapping
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| Some((i, (*c)?)))
+    {
+        if mapping[..i].contains(&Some(column)) {
+            bail!("{} 列被映射了多次", column.header());
+        }
+    }
+    if !mapping.contains(&Some(CsvColumn::Title)) {
+        bail!("没有映射为标题的列");
+    }
+
+    let mut drafts = Vec::new();
+    for record in reader(text).records() {
+        let record = record?;
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        match parse_record(&record, mapping) {
+            Ok(draft) => drafts.push(draft),
+            Err(reason) => {
+                let title = mapping
+                    .iter()
+                    .position(|c| *c == Some(CsvColumn::Title))
+                    .and_then(|i| record.get(i))
+                    .filter(|title| !title.trim().is_empty())
+                    .map(|title| title.to_string())
+                    .unwrap_or_else(|| {
+                        let line = record.position().map_or(0, |p| p.line());
+                        format!("第 {} 行", line)
+                    });
+                report.skip(&title, &reason);
+            }
+        }
+    }
+    Ok(drafts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_time(y: i32, m: u32, d: u32, h: u32, min: u32) -> Option<u64> {
+        Some(
+            Local
+                .with_ymd_and_hms(y, m, d, h, min, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        )
+    }
+
+    #[test]
+    fn test_preview_suggests_mapping() {
+        let text = "Task Name;Deadline;Owner;标签\nShip release;2026-10-20;Ana;work\n";
+        let preview = preview_csv(text).unwrap();
+        assert_eq!(
+            preview.headers,
+            vec!["Task Name", "Deadline", "Owner", "标签"]
+        );
+        assert_eq!(
+            preview.rows,
+            vec![vec!["Ship release", "2026-10-20", "Ana", "work"]]
+        );
+        assert_eq!(
+            preview.mapping,
+            vec![None, Some(CsvColumn::Due), None, Some(CsvColumn::Tags)]
+        );
+
+        let preview = preview_csv("title,name,Done\n").unwrap();
+        assert_eq!(
+            preview.mapping,
+            vec![Some(CsvColumn::Title), None, Some(CsvColumn::Finished)],
+            "同一列只推测一次"
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_with_mapping() {
+        let text = "Task,Team,Labels,Prio,Due,Done,Notes\n\
+                    \"Review budget, Q4\",Finance,\"money; review\",high,2026-10-20 14:30,no,\"Line 1\nLine 2\"\n\
+                    ,,,,,,\n\
+                    Archive files,,,B,2026/10/01,yes,\n\
+                    No date,,,,next week,,\n\
+                    ,Orphan,,,,,\n";
+        let mapping = [
+            Some(CsvColumn::Title),
+            Some(CsvColumn::List),
+            Some(CsvColumn::Tags),
+            Some(CsvColumn::Priority),
+            Some(CsvColumn::Due),
+            Some(CsvColumn::Finished),
+            Some(CsvColumn::Content),
+        ];
+        let mut report = ImportReport::default();
+        let drafts = parse_csv(text, &mapping, &mut report).unwrap();
+        assert_eq!(drafts.len(), 2);
+
+        let draft = &drafts[0];
+        assert_eq!(draft.title, "Review budget, Q4");
+        assert_eq!(draft.list.as_deref(), Some("Finance"));
+        assert_eq!(draft.tags, vec!["money", "review"]);
+        assert_eq!(draft.priority, Priority::High);
+        assert_eq!(draft.task_time, local_time(2026, 10, 20, 14, 30));
+        assert!(!draft.finished);
+        assert_eq!(draft.description.as_deref(), Some("Line 1\nLine 2"));
+
+        assert_eq!(drafts[1].priority, Priority::Medium);
+        assert_eq!(drafts[1].task_time, local_time(2026, 10, 1, 0, 0));
+        assert!(drafts[1].finished);
+        assert_eq!(drafts[1].list, None);
+
+        let skipped: Vec<(&str, &str)> = report
+            .skipped
+            .iter()
+            .map(|s| (s.title.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("No date", "无法解析截止时间: next week"),
+                ("第 7 行", "缺少标题")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_rejects_bad_mapping() {
+        let mut report = ImportReport::default();
+        let err = parse_csv("a,b\n", &[None, Some(CsvColumn::Due)], &mut report).unwrap_err();
+        assert_eq!(err.to_string(), "没有映射为标题的列");
+        let mapping = [Some(CsvColumn::Title), Some(CsvColumn::Title)];
+        assert!(parse_csv("a,b\n", &mapping, &mut report).is_err());
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        use crate::entity::Event;
+        use crate::function::export::csv::write_csv;
+        use crate::function::export::pipeline::collect;
+        use std::collections::HashMap;
+
+        let mut event = Event::new("Plan trip", "");
+        event.metadata.list = Some("travel-id".to_string());
+        event.metadata.tag = Some(vec!["fun".to_string()]);
+        event.priority = Priority::Low;
+        event.task_time = local_time(2026, 12, 1, 8, 0);
+        let lists = HashMap::from([("travel-id".to_string(), "Travel".to_string())]);
+        let columns = [&[CsvColumn::Id], CsvColumn::DEFAULT].concat();
+        let events = [event.clone()];
+        let text = collect(|out| write_csv(out, &events, &columns, &lists)).unwrap();
+
+        let preview = preview_csv(&text).unwrap();
+        assert!(preview.mapping.iter().all(|c| c.is_some()));
+        let mut report = ImportReport::default();
+        let drafts = parse_csv(&text, &preview.mapping, &mut report).unwrap();
+        let draft = &drafts[0];
+        assert_eq!(draft.uid.as_deref(), Some(event.metadata.uuid.as_str()));
+        assert_eq!(draft.title, event.title);
+        assert_eq!(draft.list.as_deref(), Some("Travel"));
+        assert_eq!(draft.tags, vec!["fun"]);
+        assert_eq!(draft.priority, Priority::Low);
+        assert_eq!(draft.task_time, event.task_time);
+        assert_eq!(draft.description, None, "默认列不包含内容");
+    }
+}
@@ -0,0 +1,419 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use super::{Draft, ImportReport};
+use crate::entity::event::Priority;
+
+/// 一行内容：属性名、参数与值
+#[derive(Debug, PartialEq)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 展开折行：以空格或制表符开头的行是上一行的延续
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// 解析 `NAME;PARAM=VALUE:value`，引号内的 `:` 和 `;` 不作分隔
+fn parse_line(line: &str) -> Option<Property> {
+    let mut quoted = false;
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut value_start = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&line[start..i]);
+                start = i + 1;
+            }
+            ':' if !quoted => {
+                parts.push(&line[start..i]);
+                value_start = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = &line[value_start?..];
+    let mut parts = parts.into_iter();
+    let name = parts.next()?.to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.to_ascii_uppercase(),
+                value.trim_matches('"').to_string(),
+            )
+        })
+        .collect();
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// 反转义文本值
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 按未转义的逗号拆分列表值（如 CATEGORIES）
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+    for c in value.chars() {
+        if escaped {
+            current.push('\\');
+            current.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ',' {
+            items.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    items.push(current);
+    items
+        .iter()
+        .map(|item| unescape(item).trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// 将 DATE 或 DATE-TIME 值转换为毫秒时间戳
+///
+/// 以 `Z` 结尾为UTC时间；带 TZID 参数时按该时区解析，无法识别的时区与浮动时间均按本地时间处理；
+/// 纯日期取当天本地零点。
+fn parse_time(property: &Property) -> Option<u64> {
+    let value = property.value.trim();
+    let is_date = property.param("VALUE") == Some("DATE") || value.len() == 8;
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let local = Local
+            .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+            .earliest()?;
+        return Some(local.timestamp_millis() as u64);
+    }
+
+    let millis = if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        Utc.from_utc_datetime(&naive).timestamp_millis()
+    } else {
+        let naive = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        match property
+            .param("TZID")
+            .and_then(|tzid| tzid.parse::<Tz>().ok())
+        {
+            Some(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()?
+                .timestamp_millis(),
+            None => Local
+                .from_local_datetime(&naive)
+                .earliest()?
+                .timestamp_millis(),
+        }
+    };
+    u64::try_from(millis).ok()
+}
+
+/// ICS 优先级：1-4 高，5 中，6-9 低，0 未定义
+fn parse_priority(value: &str) -> Priority {
+    match value.trim().parse::<u8>() {
+        Ok(1..=4) => Priority::High,
+        Ok(5) => Priority::Medium,
+        Ok(6..=9) => Priority::Low,
+        _ => Priority::Undefined,
+    }
+}
+
+/// 将一个 VTODO 或 VEVENT 的属性转换为事件，无法导入时返回跳过原因
+fn to_draft(component: &str, properties: &[Property]) -> Result<Draft, String> {
+    let find = |name: &str| properties.iter().find(|p| p.name == name);
+
+    let title = find("SUMMARY")
+        .map(|p| unescape(&p.value).trim().to_string())
+        .filter(|title| !title.is_empty())
+        .ok_or_else(|| "缺少SUMMARY".to_string())?;
+    let mut draft = Draft::new(&title);
+
+    let status = find("STATUS").map(|p| p.value.trim().to_ascii_uppercase());
+    if status.as_deref() == Some("CANCELLED") {
+        return Err("已取消".to_string());
+    }
+
+    draft.uid = find("UID").map(|p| p.value.trim().to_string());
+    // 缺少 DESCRIPTION 表示正文为空
+    draft.description = Some(
+        find("DESCRIPTION")
+            .map(|p| unescape(&p.value))
+            .unwrap_or_default(),
+    );
+
+    // 待办以 DUE 为截止时间，没有时退回 DTSTART
+    let time = match component {
+        "VTODO" => find("DUE").or_else(|| find("DTSTART")),
+        _ => find("DTSTART"),
+    };
+    if let Some(time) = time {
+        draft.task_time =
+            Some(parse_time(time).ok_or_else(|| format!("无法解析时间: {}", time.value))?);
+    }
+
+    draft.priority = find("PRIORITY")
+        .map(|p| parse_priority(&p.value))
+        .unwrap_or(Priority::Undefined);
+    draft.finished = status.as_deref() == Some("COMPLETED") || find("COMPLETED").is_some();
+    for categories in properties.iter().filter(|p| p.name == "CATEGORIES") {
+        for tag in split_list(&categories.value) {
+            if !draft.tags.contains(&tag) {
+                draft.tags.push(tag);
+            }
+        }
+    }
+    draft.recurrence = find("RRULE").map(|p| p.value.trim().to_string());
+    Ok(draft)
+}
+
+/// 解析 ICS 文本中的 VTODO 与 VEVENT
+///
+/// 嵌套组件（如 VALARM）的属性会被忽略，无法导入的条目记录在导入报告中。
+pub fn parse_ics(text: &str, report: &mut ImportReport) -> Vec<Draft> {
+    let mut drafts = Vec::new();
+    // (组件名, 属性)，仅收集最外层 VTODO/VEVENT 的属性
+    let mut current: Option<(String, Vec<Property>)> = None;
+    let mut depth = 0;
+
+    for line in unfold(text) {
+        let Some(property) = parse_line(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.trim().to_ascii_uppercase();
+                if current.is_some() {
+                    depth += 1;
+                } else if component == "VTODO" || component == "VEVENT" {
+                    current = Some((component, Vec::new()));
+                    depth = 0;
+                }
+            }
+            "END" if current.is_some() => {
+                if depth > 0 {
+                    depth -= 1;
+                    continue;
+                }
+                if let Some((component, properties)) = current.take() {
+                    match to_draft(&component, &properties) {
+                        Ok(draft) => drafts.push(draft),
+                        Err(reason) => {
+                            let title = properties
+                                .iter()
+                                .find(|p| p.name == "SUMMARY" || p.name == "UID")
+                                .map(|p| unescape(&p.value))
+                                .unwrap_or_else(|| component.clone());
+                            report.skip(&title, &reason);
+                        }
+                    }
+                }
+            }
+            _ => {
+                if let Some((_, properties)) = current.as_mut().filter(|_| depth == 0) {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    drafts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unfold_and_unescape() {
+        let text = "BEGIN:VTODO\r\nSUMMARY:Write a rather long\r\n  summary\r\nDESCRIPTION:a\\, b\\; c\\nnext\\\\line\r\nEND:VTODO\r\n";
+        let lines = unfold(text);
+        assert_eq!(lines[1], "SUMMARY:Write a rather long summary");
+        let description = parse_line(&lines[2]).unwrap();
+        assert_eq!(unescape(&description.value), "a, b; c\nnext\\line");
+    }
+
+    #[test]
+    fn test_parse_line_params() {
+        let property =
+            parse_line("DTSTART;TZID=\"America/New_York\";VALUE=DATE-TIME:20261014T090000")
+                .unwrap();
+        assert_eq!(property.name, "DTSTART");
+        assert_eq!(property.param("tzid"), Some("America/New_York"));
+        assert_eq!(property.value, "20261014T090000");
+
+        // 引号内的冒号不是值的开始
+        let property = parse_line("ATTENDEE;CN=\"Doe: John\":mailto:john@example.com").unwrap();
+        assert_eq!(property.param("CN"), Some("Doe: John"));
+        assert_eq!(property.value, "mailto:john@example.com");
+    }
+
+    #[test]
+    fn test_parse_time() {
+        let utc = parse_line("DUE:20261014T090000Z").unwrap();
+        assert_eq!(parse_time(&utc), Some(1_791_968_400_000));
+
+        // 纽约10月为夏令时 UTC-4
+        let tz = parse_line("DUE;TZID=America/New_York:20261014T050000").unwrap();
+        assert_eq!(parse_time(&tz), Some(1_791_968_400_000));
+
+        let date = parse_line("DUE;VALUE=DATE:20261014").unwrap();
+        let expected = Local
+            .with_ymd_and_hms(2026, 10, 14, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis() as u64;
+        assert_eq!(parse_time(&date), Some(expected));
+
+        let invalid = parse_line("DUE:tomorrow").unwrap();
+        assert_eq!(parse_time(&invalid), None);
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let text = "BEGIN:VCALENDAR\r\n\
+VERSION:2.0\r\n\
+BEGIN:VTODO\r\n\
+UID:todo-1@example.com\r\n\
+SUMMARY:Pay rent\r\n\
+DESCRIPTION:Transfer to landlord\r\n\
+DUE:20261014T090000Z\r\n\
+PRIORITY:1\r\n\
+STATUS:COMPLETED\r\n\
+CATEGORIES:Home,Finance\r\n\
+CATEGORIES:Monthly\r\n\
+RRULE:FREQ=MONTHLY;BYMONTHDAY=1\r\n\
+BEGIN:VALARM\r\n\
+ACTION:DISPLAY\r\n\
+DESCRIPTION:Reminder\r\n\
+END:VALARM\r\n\
+END:VTODO\r\n\
+BEGIN:VEVENT\r\n\
+UID:event-1@example.com\r\n\
+SUMMARY:Standup\r\n\
+DTSTART:20261014T090000Z\r\n\
+PRIORITY:7\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:event-2@example.com\r\n\
+SUMMARY:Cancelled meeting\r\n\
+STATUS:CANCELLED\r\n\
+END:VEVENT\r\n\
+BEGIN:VTODO\r\n\
+UID:todo-2@example.com\r\n\
+END:VTODO\r\n\
+END:VCALENDAR\r\n";
+
+        let mut report = ImportReport::default();
+        let drafts = parse_ics(text, &mut report);
+        assert_eq!(drafts.len(), 2);
+
+        let todo = &drafts[0];
+        assert_eq!(todo.uid.as_deref(), Some("todo-1@example.com"));
+        assert_eq!(todo.title, "Pay rent");
+        assert_eq!(
+            todo.description.as_deref(),
+            Some("Transfer to landlord"),
+            "VALARM 的描述不应覆盖待办"
+        );
+        assert_eq!(todo.task_time, Some(1_791_968_400_000));
+        assert_eq!(todo.priority, Priority::High);
+        assert!(todo.finished);
+        assert_eq!(todo.tags, vec!["Home", "Finance", "Monthly"]);
+        assert_eq!(
+            todo.recurrence.as_deref(),
+            Some("FREQ=MONTHLY;BYMONTHDAY=1")
+        );
+
+        let event = &drafts[1];
+        assert_eq!(event.title, "Standup");
+        assert_eq!(event.priority, Priority::Low);
+        assert!(!event.finished);
+
+        let reasons: Vec<&str> = report.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["已取消", "缺少SUMMARY"]);
+        assert_eq!(report.skipped[1].title, "todo-2@example.com");
+    }
+
+    #[test]
+    fn test_split_list_keeps_escaped_commas() {
+        assert_eq!(split_list("a\\,b, c ,,d"), vec!["a,b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        use crate::entity::Event;
+        use crate::function::export::ics::{write_events_to_ics, IcsMode};
+        use crate::function::export::pipeline::collect;
+
+        let mut event = Event::new("Call bank, then; fax", "Ask about the 利率");
+        event.metadata.tag = Some(vec!["finance".to_string(), "a,b".to_string()]);
+        event.priority = Priority::Medium;
+        event.task_time = Some(1_792_000_000_000);
+        event.recurrence = Some("FREQ=WEEKLY;INTERVAL=2".to_string());
+        let mut done = Event::new("Book flights", "");
+        done.finished = true;
+
+        let events = [event.clone(), done];
+        let text = collect(|out| write_events_to_ics(&events, IcsMode::Todo, out)).unwrap();
+        let mut report = ImportReport::default();
+        let drafts = parse_ics(&text, &mut report);
+        assert!(report.skipped.is_empty());
+        let draft = &drafts[0];
+        assert_eq!(draft.uid.as_deref(), Some(event.metadata.uuid.as_str()));
+        assert_eq!(draft.title, event.title);
+        assert_eq!(draft.description.as_deref(), Some("Ask about the 利率"));
+        assert_eq!(draft.tags, vec!["finance", "a,b"]);
+        assert_eq!(draft.priority, Priority::Medium);
+        assert_eq!(draft.task_time, event.task_time);
+        assert_eq!(draft.recurrence, event.recurrence);
+        assert!(!draft.finished);
+        assert!(drafts[1].finished);
+        assert_eq!(drafts[1].priority, Priority::Undefined);
+    }
+}
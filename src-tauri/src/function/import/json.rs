@@ -0,0 +1,261 @@
+use anyhow::{anyhow, bail, Result};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+use super::{create_content, ImportReport, ImportedItem};
+use crate::entity::embedding::Embedding;
+use crate::entity::{Entity, Event, List, Repository, Storage, Tag};
+use crate::function::export::json::{Archive, Attachment, FORMAT, VERSION};
+
+/// JSON导入方式
+#[derive(Serialize, Deserialize, TS, Clone, Copy, Debug, PartialEq)]
+#[ts(export)]
+pub enum ImportMode {
+    /// 保留现有数据，UUID相同的事件与列表被覆盖
+    Merge,
+    /// 先清空现有事件、列表与标签
+    Replace,
+}
+
+/// 解析导出文件，先检查格式标识与版本以便给出明确的错误
+pub fn parse_archive(text: &str) -> Result<Archive> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    if value.get("format").and_then(|f| f.as_str()) != Some(FORMAT) {
+        bail!("不是ToDoPulse的JSON导出文件");
+    }
+    let version = value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| anyhow!("导出文件缺少版本号"))?;
+    if version > VERSION as u64 {
+        bail!("导出文件版本 {} 高于当前支持的版本 {}", version, VERSION);
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+// 解码附件，附件名中的路径部分会被忽略
+//
+// 只接受内联数据，不读取 `path` 指向的文件，避免导入文件读取本机的任意文件。
+fn decode_attachments(
+    title: &str,
+    attachments: &[Attachment],
+    report: &mut ImportReport,
+) -> Result<Vec<(PathBuf, Vec<u8>)>> {
+    let mut files = Vec::new();
+    for attachment in attachments {
+        let Some(name) = Path::new(&attachment.name).file_name() else {
+            continue;
+        };
+        match &attachment.data {
+            Some(data) => {
+                let bytes = general_purpose::STANDARD.decode(data).map_err(|e| {
+                    anyhow!("事件 {} 的附件 {} 无法解码: {}", title, attachment.name, e)
+                })?;
+                files.push((PathBuf::from(name), bytes));
+            }
+            None if attachment.path.is_some() => report.skip(
+                &format!("{}/{}", title, attachment.name),
+                "附件只能以内联数据导入",
+            ),
+            None => {}
+        }
+    }
+    Ok(files)
+}
+
+// 将附件写入事件内容目录
+fn write_attachments(content: &Path, files: &[(PathBuf, Vec<u8>)]) -> Result<()> {
+    let dir = content
+        .parent()
+        .ok_or_else(|| anyhow!("无法获取事件内容目录"))?;
+    for (name, bytes) in files {
+        fs::write(dir.join(name), bytes)?;
+    }
+    Ok(())
+}
+
+// 删除所有事件（包括内容目录与向量）、列表与标签
+fn clear(storage: &Storage) -> Result<()> {
+    for event in Repository::<Event>::get_all(storage)? {
+        if let Some(content_dir) = PathBuf::from(&event.content).parent() {
+            if fs::remove_dir_all(content_dir).is_err() {
+                log::error!("Failed to delete directory: {}", content_dir.display());
+            }
+        }
+        Repository::<Event>::delete(storage, &event.metadata.uuid)?;
+        Repository::<Embedding>::delete(storage, &event.metadata.uuid)?;
+    }
+    for list in Repository::<List>::get_all(storage)? {
+        Repository::<List>::delete(storage, &list.uuid)?;
+    }
+    for tag in Repository::<Tag>::get_all(storage)? {
+        Repository::<Tag>::delete(storage, &String::from_utf8_lossy(&tag.id_bytes()))?;
+    }
+    Ok(())
+}
+
+/// 按导入方式写入导出文件中的数据，事件保留原有UUID
+///
+/// 所有附件先解码完成再改动数据，文件有误时现有数据保持不变。
+pub fn import_archive(
+    storage: &Storage,
+    archive: Archive,
+    mode: ImportMode,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let mut events = Vec::new();
+    for archived in archive.events {
+        let files = decode_attachments(&archived.event.title, &archived.attachments, report)?;
+        events.push((archived.event, archived.markdown, files));
+    }
+
+    if mode == ImportMode::Replace {
+        clear(storage)?;
+    }
+
+    let tags = Repository::<Tag>::get_all(storage)?;
+    for tag in archive.tags {
+        if !tags.iter().any(|t| t.name == tag.name) {
+            Repository::<Tag>::add(storage, &Tag::new(tag.name, tag.color))?;
+        }
+    }
+    for list in &archive.lists {
+        Repository::<List>::add(storage, list)?;
+    }
+
+    for (mut event, markdown, files) in events {
+        let existing = Repository::<Event>::get_by_name(storage, &event.metadata.uuid)?;
+        let created = match existing {
+            Some(existing) => {
+                event.content = existing.content.clone();
+                let content = fs::read_to_string(&existing.content).unwrap_or_default();
+                if existing.value() == event.value() && content == markdown {
+                    report.skip(&event.title, "与已有事件相同");
+                    continue;
+                }
+                fs::write(&event.content, &markdown)?;
+                false
+            }
+            None => {
+                event.content = create_content(&event.title, &markdown)?;
+                true
+            }
+        };
+        write_attachments(Path::new(&event.content), &files)?;
+        Repository::<Event>::add(storage, &event)?;
+
+        let item = ImportedItem {
+            id: event.metadata.uuid,
+            title: event.title,
+        };
+        if created {
+            report.created.push(item);
+        } else {
+            report.updated.push(item);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::tag::TagColor;
+    use crate::function::export::json::{ArchivedEvent, ArchivedTag};
+    use crate::test::temp_storage;
+
+    fn sample() -> Archive {
+        let mut event = Event::new("Pay rent", "");
+        event.recurrence = Some("FREQ=MONTHLY".to_string());
+        event.metadata.blocked_by = vec!["other".to_string()];
+        Archive {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            exported_at: "2026-10-14T09:00:00+00:00".to_string(),
+            lists: vec![List::new("Home", "home")],
+            tags: vec![ArchivedTag {
+                name: "Finance".to_string(),
+                color: TagColor::Warning,
+            }],
+            events: vec![ArchivedEvent {
+                event,
+                markdown: "![receipt](./receipt.png)".to_string(),
+                attachments: vec![Attachment {
+                    name: "receipt.png".to_string(),
+                    data: Some(general_purpose::STANDARD.encode(b"png")),
+                    path: None,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = serde_json::to_string(&sample()).unwrap();
+        let archive = parse_archive(&text).unwrap();
+        let event = &archive.events[0];
+        assert_eq!(event.event.title, "Pay rent");
+        assert_eq!(event.event.recurrence.as_deref(), Some("FREQ=MONTHLY"));
+        assert_eq!(event.event.metadata.blocked_by, vec!["other"]);
+        assert_eq!(event.attachments[0].name, "receipt.png");
+        assert_eq!(archive.lists[0].title, "Home");
+        assert!(
+            text.contains("\"priority\":\"Undefined\""),
+            "优先级不应本地化"
+        );
+    }
+
+    #[test]
+    fn test_rejects_unknown_files() {
+        let mut value = serde_json::to_value(sample()).unwrap();
+        value["version"] = serde_json::json!(VERSION + 1);
+        let Err(err) = parse_archive(&value.to_string()) else {
+            panic!("版本更高的文件应被拒绝");
+        };
+        assert!(err.to_string().contains("高于当前支持的版本"));
+
+        // 旧版导出没有格式标识
+        let legacy = r#"{"metadata":{"count":0},"events":[]}"#;
+        assert!(parse_archive(legacy).is_err());
+        assert!(parse_archive("not json").is_err());
+    }
+
+    #[test]
+    fn test_decode_attachments() {
+        let attachments = vec![
+            Attachment {
+                name: "../../receipt.png".to_string(),
+                data: Some(general_purpose::STANDARD.encode(b"png")),
+                path: None,
+            },
+            Attachment {
+                name: "id_rsa".to_string(),
+                data: None,
+                path: Some("/home/user/.ssh/id_rsa".to_string()),
+            },
+        ];
+        let mut report = ImportReport::default();
+        let files = decode_attachments("Pay rent", &attachments, &mut report).unwrap();
+        assert_eq!(files, vec![(PathBuf::from("receipt.png"), b"png".to_vec())]);
+        assert_eq!(report.skipped[0].title, "Pay rent/id_rsa");
+    }
+
+    #[test]
+    fn test_replace_keeps_data_on_invalid_archive() {
+        let (storage, _dir) = temp_storage();
+        let list = List::new("Work", "work");
+        Repository::<List>::add(&storage, &list).unwrap();
+
+        let mut archive = sample();
+        archive.events[0].attachments[0].data = Some("not base64!".to_string());
+        let mut report = ImportReport::default();
+        let err = import_archive(&storage, archive, ImportMode::Replace, &mut report).unwrap_err();
+        assert!(err.to_string().contains("receipt.png"));
+        assert!(Repository::<List>::get_by_name(&storage, &list.uuid)
+            .unwrap()
+            .is_some());
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::todotxt::unescape_tag;
+use super::{Draft, ImportReport};
+use crate::entity::event::Priority;
+use crate::function::convert::{parse_date, simple_rrule, Frequency};
+
+// Obsidian Tasks 中带日期的字段
+const DUE: &[&str] = &["📅", "📆", "🗓"];
+const SCHEDULED: &str = "⏳";
+const START: &str = "🛫";
+const DATE_FIELDS: &[&str] = &["📅", "📆", "🗓", "⏳", "⌛", "🛫", "✅", "❌", "➕"];
+const RECURRENCE: &str = "🔁";
+const ID: &str = "🆔";
+const DEPENDS_ON: &str = "⛔";
+
+fn priority_of(token: &str) -> Option<Priority> {
+    match token {
+        "🔺" | "⏫" => Some(Priority::High),
+        "🔼" => Some(Priority::Medium),
+        "🔽" | "⏬" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_field(token: &str) -> bool {
+    DATE_FIELDS.contains(&token)
+        || [RECURRENCE, ID, DEPENDS_ON].contains(&token)
+        || priority_of(token).is_some()
+}
+
+/// 将 `every 2 weeks` 之类的重复规则转换为 RRULE，无法识别时返回 None
+pub fn every_to_rrule(text: &str) -> Option<String> {
+    let words: Vec<String> = text.split_whitespace().map(|w| w.to_lowercase()).collect();
+    let (interval, unit) = match words.as_slice() {
+        [every, unit] if every == "every" => (1, unit.as_str()),
+        [every, n, unit] if every == "every" => (n.parse::<u32>().ok()?, unit.as_str()),
+        _ => return None,
+    };
+    let freq = Frequency::from_word(unit.trim_end_matches('s'))?;
+    Some(simple_rrule(freq, interval))
+}
+
+/// 解析一行 Obsidian Tasks 任务，不是任务行时返回 None，无法导入时返回跳过原因
+fn parse_task(line: &str) -> Option<Result<Draft, (String, String)>> {
+    static TASK: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*[-*+]\s+\[(.)\]\s+(.*)$").unwrap());
+    let captures = TASK.captures(line)?;
+    let status = &captures[1];
+    let body = captures[2].replace('\u{FE0F}', "");
+
+    let mut draft = Draft::new("");
+    draft.lossy = true;
+    let mut title = Vec::new();
+    let (mut due, mut scheduled, mut start) = (None, None, None);
+    let mut tokens = body.split_whitespace().peekable();
+    while let Some(token) = tokens.next() {
+        if let Some(priority) = priority_of(token) {
+            draft.priority = priority;
+        } else if DATE_FIELDS.contains(&token) {
+            let date = tokens.next().and_then(parse_date);
+            if DUE.contains(&token) {
+                due = date;
+            } else if token == SCHEDULED {
+                scheduled = date;
+            } else if token == START {
+                start = date;
+            }
+        } else if token == RECURRENCE {
+            let mut rule = Vec::new();
+            while let Some(word) = tokens.next_if(|t| !is_field(t) && !t.starts_with('#')) {
+                rule.push(word);
+            }
+            draft.recurrence = every_to_rrule(&rule.join(" "));
+        } else if token == ID {
+            draft.uid = tokens.next().map(|id| id.to_string());
+        } else if token == DEPENDS_ON {
+            tokens.next();
+        } else if let Some(word) = token.strip_prefix('\\').filter(|w| !w.is_empty()) {
+            title.push(word);
+        } else if let Some(tag) = token.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            let tag = unescape_tag(tag);
+            if !draft.tags.contains(&tag) {
+                draft.tags.push(tag);
+            }
+        } else {
+            title.push(token);
+        }
+    }
+
+    draft.title = title.join(" ");
+    draft.task_time = due.or(scheduled).or(start);
+    draft.finished = status.eq_ignore_ascii_case("x");
+    if draft.title.is_empty() {
+        return Some(Err((body.trim().to_string(), "缺少标题".to_string())));
+    }
+    if status == "-" {
+        return Some(Err((draft.title, "已取消".to_string())));
+    }
+    Some(Ok(draft))
+}
+
+/// 解析 Markdown 文本中的 Obsidian Tasks 任务行，其余内容被忽略
+pub fn parse_tasks(text: &str, report: &mut ImportReport) -> Vec<Draft> {
+    let mut drafts = Vec::new();
+    for line in text.lines() {
+        match parse_task(line) {
+            Some(Ok(draft)) => drafts.push(draft),
+            Some(Err((title, reason))) => report.skip(&title, &reason),
+            None => {}
+        }
+    }
+    drafts
+}
+
+/// 收集文件或文件夹中的Markdown文件，跳过隐藏目录（如 `.obsidian`）
+pub fn collect_markdown(path: &Path) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            files.extend(collect_markdown(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "md") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn local_date(y: i32, m: u32, d: u32) -> u64 {
+        Local
+            .with_ymd_and_hms(y, m, d, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis() as u64
+    }
+
+    #[test]
+    fn test_parse_task_line() {
+        let draft =
+            parse_task("- [ ] Pay rent #home #finance ⏫ 🔁 every month 📅 2026-10-20 🆔 abc123")
+                .unwrap()
+                .unwrap();
+        assert_eq!(draft.title, "Pay rent");
+        assert_eq!(draft.tags, vec!["home", "finance"]);
+        assert_eq!(draft.priority, Priority::High);
+        assert_eq!(draft.recurrence.as_deref(), Some("FREQ=MONTHLY"));
+        assert_eq!(draft.task_time, Some(local_date(2026, 10, 20)));
+        assert_eq!(draft.uid.as_deref(), Some("abc123"));
+        assert!(!draft.finished);
+    }
+
+    #[test]
+    fn test_parse_task_status_and_dates() {
+        let done = parse_task("  * [x] Water plants 🔽 ⏳ 2026-10-18 ✅ 2026-10-18")
+            .unwrap()
+            .unwrap();
+        assert!(done.finished);
+        assert_eq!(done.priority, Priority::Low);
+        assert_eq!(
+            done.task_time,
+            Some(local_date(2026, 10, 18)),
+            "没有截止日期时使用计划日期"
+        );
+
+        // 变体选择符不影响识别
+        let medium = parse_task("- [ ] Call mom 🔼 📅\u{FE0F} 2026-10-21")
+            .unwrap()
+            .unwrap();
+        assert_eq!(medium.priority, Priority::Medium);
+        assert_eq!(medium.task_time, Some(local_date(2026, 10, 21)));
+
+        assert!(parse_task("Just a note").is_none());
+        assert!(parse_task("- plain bullet").is_none());
+    }
+
+    #[test]
+    fn test_parse_tasks_report() {
+        let text = "# Inbox\n\n- [ ] Valid task\n- [-] Dropped task\n- [ ] #only-tag\nSome prose\n";
+        let mut report = ImportReport::default();
+        let drafts = parse_tasks(text, &mut report);
+        assert_eq!(drafts.len(), 1);
+        let reasons: Vec<&str> = report.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["已取消", "缺少标题"]);
+    }
+
+    #[test]
+    fn test_every_to_rrule() {
+        assert_eq!(every_to_rrule("every day").as_deref(), Some("FREQ=DAILY"));
+        assert_eq!(
+            every_to_rrule("every 2 weeks").as_deref(),
+            Some("FREQ=WEEKLY;INTERVAL=2")
+        );
+        assert_eq!(every_to_rrule("every weekday"), None);
+    }
+
+    #[test]
+    fn test_export_round_trip_escapes() {
+        use crate::entity::Event;
+        use crate::function::export::md::write_tasks;
+        use crate::function::export::pipeline::collect;
+
+        let mut event = Event::new("Fix #42 before 📅 release \\o/ ⏫", "");
+        event.metadata.tag = Some(vec!["on hold".to_string(), "100%".to_string()]);
+        let text = collect(|out| write_tasks(std::slice::from_ref(&event), out)).unwrap();
+        assert!(text.contains("#on%20hold #100%25"));
+
+        let mut report = ImportReport::default();
+        let drafts = parse_tasks(&text, &mut report);
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].title, event.title);
+        assert_eq!(drafts[0].tags, vec!["on hold", "100%"]);
+        assert_eq!(drafts[0].priority, Priority::Undefined);
+        assert_eq!(drafts[0].task_time, None);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        use crate::entity::{Event, Repository};
+        use crate::function::export::md::write_tasks;
+        use crate::function::export::pipeline::collect;
+        use crate::function::import::apply;
+        use crate::test::temp_storage;
+
+        let (storage, _dir) = temp_storage();
+
+        let mut event = Event::new("Renew passport", "");
+        event.metadata.tag = Some(vec!["admin".to_string()]);
+        event.priority = Priority::Medium;
+        event.recurrence = Some("FREQ=YEARLY;INTERVAL=10".to_string());
+        event.task_time = Some(local_date(2026, 11, 2));
+        let mut standup = Event::new("Standup", "");
+        standup.recurrence = Some("FREQ=WEEKLY;BYDAY=MO,WE,FR".to_string());
+        standup.task_time = Some(
+            Local
+                .with_ymd_and_hms(2026, 11, 2, 9, 30, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        );
+        let mut done = Event::new("Book flights", "");
+        done.finished = true;
+        for event in [&event, &standup, &done] {
+            Repository::<Event>::add(&storage, event).unwrap();
+        }
+
+        let events = [event.clone(), standup.clone(), done.clone()];
+        let text = collect(|out| write_tasks(&events, out)).unwrap();
+        let mut report = ImportReport::default();
+        let drafts = parse_tasks(&text, &mut report);
+        assert!(report.skipped.is_empty());
+        assert_eq!(drafts.len(), 3);
+
+        let draft = &drafts[0];
+        assert_eq!(draft.uid.as_deref(), Some(event.metadata.uuid.as_str()));
+        assert_eq!(draft.title, event.title);
+        assert_eq!(draft.tags, vec!["admin"]);
+        assert_eq!(draft.priority, Priority::Medium);
+        assert_eq!(draft.recurrence, event.recurrence);
+        assert_eq!(draft.task_time, event.task_time);
+        assert_eq!(drafts[1].task_time, Some(local_date(2026, 11, 2)));
+        assert_eq!(drafts[1].recurrence, None, "BYDAY 无法用 🔁 表示");
+        assert!(drafts[2].finished);
+        assert_eq!(drafts[2].priority, Priority::Undefined);
+
+        // 未修改的任务重新导入时保留具体时间和无法表示的重复规则
+        apply(&storage, drafts, None, &mut report).unwrap();
+        assert!(report.updated.is_empty());
+        assert_eq!(report.skipped.len(), 3);
+
+        // 只改动标题时同样保留
+        let text = text.replace("Standup", "Daily standup");
+        let mut report = ImportReport::default();
+        apply(&storage, parse_tasks(&text, &mut report), None, &mut report).unwrap();
+        assert_eq!(report.updated.len(), 1);
+        let updated: Event = Repository::get_by_name(&storage, &standup.metadata.uuid)
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.title, "Daily standup");
+        assert_eq!(updated.task_time, standup.task_time);
+        assert_eq!(updated.recurrence, standup.recurrence);
+    }
+}
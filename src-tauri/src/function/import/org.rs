@@ -0,0 +1,594 @@
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+use super::todotxt::rec_to_rrule;
+use super::{Draft, ImportReport};
+use crate::entity::event::Priority;
+
+static HEADLINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\*+)\s+(.*?)\s*$").unwrap());
+static TAGS: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*?)\s*:([\w@#%:]+):$").unwrap());
+static PLANNING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(DEADLINE|SCHEDULED|CLOSED):\s*([<\[][^>\]]*[>\]])").unwrap());
+static TIMESTAMP: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^[<\[](\d{4}-\d{2}-\d{2})(?:\s+[^\d\s>\]]+)?(?:\s+(\d{1,2}:\d{2})(?:-\d{1,2}:\d{2})?)?(?:\s+\.?\+?(\+\d+[hdwmy]))?(?:\s+-\d+[hdwmy])?[>\]]$",
+    )
+    .unwrap()
+});
+static DRAWER: Lazy<Regex> = Lazy::new(|| Regex::new(r"^:([\w-]+):$").unwrap());
+static PROPERTY: Lazy<Regex> = Lazy::new(|| Regex::new(r"^:([^:\s]+):\s*(.*?)$").unwrap());
+static LIST_ITEM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\s*)(?:[-+]|(\d+)[.)])\s+(?:\[([ xX-])\]\s+)?(.*)$").unwrap());
+static TABLE_RULE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\|[-+]+\|$").unwrap());
+static TOKEN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\[\[([^\]]+)\](?:\[([^\]]+)\])?\]|~(\S(?:[^~]*?\S)?)~|=(\S(?:[^=]*?\S)?)=")
+        .unwrap()
+});
+
+// 未声明 `#+TODO:` 时识别的关键字
+const OPEN: &[&str] = &["TODO", "NEXT", "STARTED", "WAITING", "HOLD"];
+const DONE: &[&str] = &["DONE", "CANCELLED", "CANCELED"];
+
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp"];
+
+/// 标题中可用的待办关键字，`#+TODO: A B | C` 中竖线之后为完成状态，没有竖线时最后一个为完成状态
+struct Keywords {
+    open: Vec<String>,
+    done: Vec<String>,
+}
+
+impl Keywords {
+    fn new() -> Self {
+        Self {
+            open: OPEN.iter().map(|k| k.to_string()).collect(),
+            done: DONE.iter().map(|k| k.to_string()).collect(),
+        }
+    }
+
+    fn declare(&mut self, value: &str) {
+        // 关键字可以带快捷键，如 `WAIT(w)`
+        let words: Vec<String> = value
+            .split_whitespace()
+            .map(|w| w.split('(').next().unwrap_or(w).to_string())
+            .collect();
+        let (open, done) = match words.iter().position(|w| w == "|") {
+            Some(bar) => (words[..bar].to_vec(), words[bar + 1..].to_vec()),
+            None => match words.split_last() {
+                Some((last, rest)) => (rest.to_vec(), vec![last.clone()]),
+                None => return,
+            },
+        };
+        self.open.extend(open);
+        self.done.extend(done);
+    }
+
+    /// 返回是否已完成，不是关键字时返回 None
+    fn finished(&self, word: &str) -> Option<bool> {
+        if self.open.iter().any(|k| k == word) {
+            Some(false)
+        } else if self.done.iter().any(|k| k == word) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// 解析 org 时间戳，返回毫秒时间戳与重复标记对应的 RRULE，只有日期时为本地零点
+fn parse_timestamp(text: &str) -> Option<(u64, Option<String>)> {
+    let caps = TIMESTAMP.captures(text.trim())?;
+    let date = NaiveDate::parse_from_str(&caps[1], "%Y-%m-%d").ok()?;
+    let time = match caps.get(2) {
+        Some(time) => NaiveTime::parse_from_str(time.as_str(), "%H:%M").ok()?,
+        None => NaiveTime::MIN,
+    };
+    let local = Local.from_local_datetime(&date.and_time(time)).earliest()?;
+    let rrule = caps
+        .get(3)
+        .and_then(|repeater| rec_to_rrule(repeater.as_str()));
+    Some((local.timestamp_millis() as u64, rrule))
+}
+
+/// 解析 `:EFFORT:` 属性，支持 `H:MM` 与分钟数
+fn parse_effort(text: &str) -> Option<u32> {
+    match text.split_once(':') {
+        Some((hours, minutes)) => {
+            Some(hours.parse::<u32>().ok()? * 60 + minutes.parse::<u32>().ok()?)
+        }
+        None => text.parse().ok(),
+    }
+}
+
+/// `[#A]` 为高优先级，`[#B]` 为中，其余字母为低
+fn parse_priority(text: &str) -> Option<(Priority, &str)> {
+    let rest = text.strip_prefix("[#")?;
+    let mut chars = rest.chars();
+    let letter = chars.next()?;
+    let rest = chars.as_str().strip_prefix(']')?;
+    let priority = match letter {
+        'A' => Priority::High,
+        'B' => Priority::Medium,
+        _ => Priority::Low,
+    };
+    Some((priority, rest.trim_start()))
+}
+
+/// 分离标题末尾的 `:tag1:tag2:`
+fn split_tags(text: &str) -> (&str, Vec<String>) {
+    match TAGS.captures(text) {
+        Some(caps) if caps.get(1).is_some_and(|t| !t.as_str().is_empty()) => (
+            caps.get(1).map_or("", |t| t.as_str()),
+            caps[2]
+                .split(':')
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ),
+        _ => (text, Vec::new()),
+    }
+}
+
+fn opens(chars: &[char], i: usize) -> bool {
+    let before = i == 0 || chars[i - 1].is_whitespace() || "-('\"{*~".contains(chars[i - 1]);
+    before && chars.get(i + 1).is_some_and(|c| !c.is_whitespace())
+}
+
+fn closes(chars: &[char], j: usize) -> bool {
+    let after = chars
+        .get(j + 1)
+        .is_none_or(|c| c.is_whitespace() || "-.,;:!?'\")}[*~".contains(*c));
+    after && !chars[j - 1].is_whitespace()
+}
+
+/// 将一种 org 强调标记替换为 Markdown 标记
+fn convert_markup(text: &str, marker: char, markdown: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let close = (i + 2..chars.len()).find(|&j| chars[j] == marker && closes(&chars, j));
+        if let Some(j) = close.filter(|_| chars[i] == marker && opens(&chars, i)) {
+            out.push_str(markdown);
+            out.extend(&chars[i + 1..j]);
+            out.push_str(markdown);
+            i = j + 1;
+            continue;
+        }
+        out.push(chars[i]);
+        i += 1;
+    }
+    out
+}
+
+fn emphasis(text: &str) -> String {
+    let text = convert_markup(text, '*', "**");
+    let text = convert_markup(&text, '/', "*");
+    convert_markup(&text, '+', "~~")
+}
+
+fn link(target: &str, description: Option<&str>) -> String {
+    let path = target.strip_prefix("file:").unwrap_or(target);
+    match description {
+        Some(description) => format!("[{}]({})", emphasis(description), path),
+        None => {
+            let is_image = path
+                .rsplit_once('.')
+                .is_some_and(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+            if is_image {
+                format!("![]({})", path)
+            } else if path.contains("://") {
+                format!("<{}>", path)
+            } else {
+                format!("[{}]({})", path, path)
+            }
+        }
+    }
+}
+
+/// 转换行内标记，链接与代码先替换为占位符，避免其中的字符被当作强调标记
+fn inline(text: &str) -> String {
+    let mut tokens = Vec::new();
+    let mut marked = String::new();
+    let mut last = 0;
+    for caps in TOKEN.captures_iter(text) {
+        let whole = caps.get(0).unwrap();
+        let token = if let Some(target) = caps.get(1) {
+            link(target.as_str(), caps.get(2).map(|d| d.as_str()))
+        } else {
+            let chars: Vec<char> = text.chars().collect();
+            let start = text[..whole.start()].chars().count();
+            let end = start + whole.as_str().chars().count() - 1;
+            if !opens(&chars, start) || !closes(&chars, end) {
+                continue;
+            }
+            let code = caps.get(3).or(caps.get(4)).map_or("", |c| c.as_str());
+            format!("`{}`", code)
+        };
+        marked.push_str(&text[last..whole.start()]);
+        marked.push_str(&format!("\u{E000}{}\u{E001}", tokens.len()));
+        tokens.push(token);
+        last = whole.end();
+    }
+    marked.push_str(&text[last..]);
+
+    let mut out = emphasis(&marked);
+    for (i, token) in tokens.iter().enumerate() {
+        out = out.replace(&format!("\u{E000}{}\u{E001}", i), token);
+    }
+    out
+}
+
+/// 将 org 正文转换为 Markdown，`level` 为所属事件标题的层级，更深的标题转换为 Markdown 标题
+fn org_to_markdown(lines: &[&str], level: usize) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut markdown = Vec::new();
+    let mut in_src = false;
+    let mut in_quote = false;
+    for line in lines {
+        let line = line.get(indent..).unwrap_or("").trim_end();
+        let upper = line.trim_start().to_uppercase();
+
+        if in_src {
+            if upper.starts_with("#+END_SRC") || upper.starts_with("#+END_EXAMPLE") {
+                in_src = false;
+                markdown.push("```".to_string());
+            } else if line.starts_with(",*") || line.starts_with(",#+") {
+                markdown.push(line[1..].to_string());
+            } else {
+                markdown.push(line.to_string());
+            }
+            continue;
+        }
+        if let Some(lang) = upper.strip_prefix("#+BEGIN_SRC") {
+            in_src = true;
+            let lang = line.trim_start()[line.trim_start().len() - lang.len()..].trim();
+            let lang = lang.split_whitespace().next().unwrap_or("");
+            markdown.push(format!("```{}", lang));
+            continue;
+        }
+        if upper.starts_with("#+BEGIN_EXAMPLE") {
+            in_src = true;
+            markdown.push("```".to_string());
+            continue;
+        }
+        if upper.starts_with("#+BEGIN_QUOTE") || upper.starts_with("#+END_QUOTE") {
+            in_quote = upper.starts_with("#+BEGIN_QUOTE");
+            continue;
+        }
+        if line.trim_start().starts_with("#+") {
+            continue;
+        }
+
+        let converted = if let Some(caps) = HEADLINE.captures(line) {
+            let depth = caps[1].len().saturating_sub(level).clamp(1, 6);
+            format!("{} {}", "#".repeat(depth), inline(&caps[2]))
+        } else if line.len() >= 5 && line.chars().all(|c| c == '-') {
+            "---".to_string()
+        } else if TABLE_RULE.is_match(line) {
+            let columns = line.matches('+').count() + 1;
+            format!("|{}", "---|".repeat(columns))
+        } else if let Some(caps) = LIST_ITEM.captures(line) {
+            let bullet = match caps.get(2) {
+                Some(n) => format!("{}.", n.as_str()),
+                None => "-".to_string(),
+            };
+            let checkbox = match caps.get(3).map(|c| c.as_str()) {
+                Some("X") | Some("x") => "[x] ",
+                Some(_) => "[ ] ",
+                None => "",
+            };
+            format!("{}{} {}{}", &caps[1], bullet, checkbox, inline(&caps[4]))
+        } else {
+            // 导出时为避免被当作标题而加的空格
+            let line = match line.strip_prefix(' ') {
+                Some(rest) if rest.starts_with('*') || rest.starts_with("#+") => rest,
+                _ => line,
+            };
+            match line.strip_suffix("\\\\") {
+                Some(line) => format!("{}\\", inline(line)),
+                None => inline(line),
+            }
+        };
+
+        if in_quote {
+            if converted.is_empty() {
+                markdown.push(">".to_string());
+            } else {
+                markdown.push(format!("> {}", converted));
+            }
+        } else {
+            markdown.push(converted);
+        }
+    }
+    markdown.join("\n").trim_matches('\n').to_string()
+}
+
+/// 正在解析的事件标题及其下的内容
+struct Entry<'a> {
+    draft: Draft,
+    level: usize,
+    planning: HashMap<String, String>,
+    properties: HashMap<String, String>,
+    drawer: Option<bool>, // 是否在抽屉中，属性抽屉为 true
+    body: Vec<&'a str>,
+}
+
+impl<'a> Entry<'a> {
+    fn line(&mut self, line: &'a str) {
+        let trimmed = line.trim();
+        if let Some(properties) = self.drawer {
+            if trimmed.eq_ignore_ascii_case(":END:") {
+                self.drawer = None;
+            } else if let Some(caps) = PROPERTY.captures(trimmed).filter(|_| properties) {
+                self.properties
+                    .insert(caps[1].to_uppercase(), caps[2].to_string());
+            }
+            return;
+        }
+        if self.body.is_empty() {
+            if let Some(caps) = DRAWER.captures(trimmed) {
+                self.drawer = Some(caps[1].eq_ignore_ascii_case("PROPERTIES"));
+                return;
+            }
+            let mut planning = PLANNING.captures_iter(trimmed).peekable();
+            if planning.peek().is_some() {
+                for caps in planning {
+                    self.planning
+                        .insert(caps[1].to_string(), caps[2].to_string());
+                }
+                return;
+            }
+            if trimmed.is_empty() {
+                return;
+            }
+        }
+        self.body.push(line);
+    }
+
+    fn finish(self) -> Result<Draft, String> {
+        let mut draft = self.draft;
+        if draft.title.is_empty() {
+            return Err("缺少标题".to_string());
+        }
+        let deadline = self
+            .planning
+            .get("DEADLINE")
+            .or(self.planning.get("SCHEDULED"));
+        if let Some(deadline) = deadline {
+            let (due, rrule) =
+                parse_timestamp(deadline).ok_or(format!("无法解析截止时间: {}", deadline))?;
+            draft.task_time = Some(due);
+            draft.recurrence = rrule;
+        }
+        if let Some(id) = self.properties.get("ID").filter(|id| !id.is_empty()) {
+            draft.uid = Some(id.clone());
+        }
+        draft.created = self
+            .properties
+            .get("CREATED")
+            .and_then(|created| parse_timestamp(created))
+            .map(|(created, _)| created);
+        draft.estimate = self
+            .properties
+            .get("EFFORT")
+            .and_then(|effort| parse_effort(effort));
+        if let Some(rrule) = self.properties.get("RRULE").filter(|r| !r.is_empty()) {
+            draft.recurrence = Some(rrule.clone());
+        }
+        draft.description = Some(org_to_markdown(&self.body, self.level));
+        Ok(draft)
+    }
+}
+
+fn finish(entry: Option<Entry>, drafts: &mut Vec<Draft>, report: &mut ImportReport) {
+    if let Some(entry) = entry {
+        let title = entry.draft.title.clone();
+        match entry.finish() {
+            Ok(draft) => drafts.push(draft),
+            Err(reason) => report.skip(&title, &reason),
+        }
+    }
+}
+
+/// 解析 org 文件
+///
+/// 带待办关键字的标题为事件，没有关键字的一级标题为列表；事件下更深的普通标题作为正文的一部分。
+/// 截止时间取 `DEADLINE`，没有时取 `SCHEDULED`；`:ID:` 属性作为来源UID，重新导入时据此匹配已有事件。
+pub fn parse_org(text: &str, report: &mut ImportReport) -> Vec<Draft> {
+    let mut keywords = Keywords::new();
+    let mut list: Option<String> = None;
+    let mut current: Option<Entry> = None;
+    let mut drafts = Vec::new();
+
+    for line in text.lines() {
+        if let Some(caps) = HEADLINE.captures(line) {
+            let level = caps[1].len();
+            let text = caps.get(2).map_or("", |t| t.as_str());
+            let (word, rest) = text.split_once(' ').unwrap_or((text, ""));
+            let finished = keywords.finished(word);
+
+            if finished.is_none() && current.as_ref().is_some_and(|e| level > e.level) {
+                current.as_mut().unwrap().body.push(line);
+                continue;
+            }
+            finish(current.take(), &mut drafts, report);
+
+            match finished {
+                Some(finished) => {
+                    let mut draft = Draft::new("");
+                    draft.finished = finished;
+                    let rest = rest.trim_start();
+                    let rest = match parse_priority(rest) {
+                        Some((priority, rest)) => {
+                            draft.priority = priority;
+                            rest
+                        }
+                        None => rest,
+                    };
+                    let (title, tags) = split_tags(rest);
+                    draft.title = title.trim().to_string();
+                    draft.tags = tags;
+                    draft.list = list.clone();
+                    current = Some(Entry {
+                        draft,
+                        level,
+                        planning: HashMap::new(),
+                        properties: HashMap::new(),
+                        drawer: None,
+                        body: Vec::new(),
+                    });
+                }
+                None if level == 1 => {
+                    let title = split_tags(text).0.trim();
+                    list = (!title.is_empty()).then(|| title.to_string());
+                }
+                None => {}
+            }
+            continue;
+        }
+
+        match current.as_mut() {
+            Some(entry) => entry.line(line),
+            None => {
+                let upper = line.trim_start().to_uppercase();
+                for prefix in ["#+TODO:", "#+SEQ_TODO:", "#+TYP_TODO:"] {
+                    if upper.starts_with(prefix) {
+                        keywords.declare(&line.trim_start()[prefix.len()..]);
+                    }
+                }
+            }
+        }
+    }
+    finish(current, &mut drafts, report);
+    drafts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function::export::org::markdown_to_org;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> Option<u64> {
+        Some(
+            Local
+                .with_ymd_and_hms(y, m, d, h, min, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        )
+    }
+
+    #[test]
+    fn test_parse_org() {
+        let text = "#+TITLE: Plans\n\
+                    #+TODO: TODO WAIT(w) | DONE DROPPED\n\
+                    \n\
+                    * TODO Loose task\n\
+                    * Home :house:\n\
+                    ** WAIT [#B] Fix sink :plumbing:urgent:\n\
+                    SCHEDULED: <2026-10-21 Wed 09:00> DEADLINE: <2026-10-25 Sun 18:00 .+1w>\n\
+                    :PROPERTIES:\n\
+                    :ID:       abc-123\n\
+                    :CREATED:  [2026-10-10 Sat 09:30]\n\
+                    :EFFORT:   0:45\n\
+                    :END:\n\
+                    :LOGBOOK:\n\
+                    - State \"WAIT\" from \"TODO\"\n\
+                    :END:\n\
+                    Call *before* noon.\n\
+                    *** Parts\n\
+                    - washer\n\
+                    ** DROPPED Old idea\n\
+                    ** TODO\n\
+                    * Work\n\
+                    ** TODO [#A] Report\n\
+                    DEADLINE: <2026-10-32 Fri>\n";
+        let mut report = ImportReport::default();
+        let drafts = parse_org(text, &mut report);
+        assert_eq!(drafts.len(), 3);
+
+        assert_eq!(drafts[0].title, "Loose task");
+        assert_eq!(drafts[0].list, None);
+
+        let sink = &drafts[1];
+        assert_eq!(sink.title, "Fix sink");
+        assert_eq!(sink.list.as_deref(), Some("Home"));
+        assert!(!sink.finished);
+        assert_eq!(sink.priority, Priority::Medium);
+        assert_eq!(sink.tags, vec!["plumbing", "urgent"]);
+        assert_eq!(sink.task_time, local(2026, 10, 25, 18, 0));
+        assert_eq!(sink.recurrence.as_deref(), Some("FREQ=WEEKLY"));
+        assert_eq!(sink.uid.as_deref(), Some("abc-123"));
+        assert_eq!(sink.created, local(2026, 10, 10, 9, 30));
+        assert_eq!(sink.estimate, Some(45));
+        assert_eq!(
+            sink.description.as_deref(),
+            Some("Call **before** noon.\n# Parts\n- washer")
+        );
+
+        assert_eq!(drafts[2].title, "Old idea");
+        assert!(drafts[2].finished);
+
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].reason, "缺少标题");
+        assert_eq!(report.skipped[1].title, "Report");
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("<2026-11-02 Mon>"),
+            Some((local(2026, 11, 2, 0, 0).unwrap(), None))
+        );
+        assert_eq!(
+            parse_timestamp("<2026-11-02 Mon 10:00-11:30 +2m -3d>"),
+            Some((
+                local(2026, 11, 2, 10, 0).unwrap(),
+                Some("FREQ=MONTHLY;INTERVAL=2".to_string())
+            ))
+        );
+        assert_eq!(
+            parse_timestamp("<2026-11-02 Mon 10:00 +1h>").unwrap().1,
+            None
+        );
+        assert_eq!(parse_timestamp("2026-11-02"), None);
+    }
+
+    #[test]
+    fn test_inline() {
+        assert_eq!(
+            inline("see [[https://a.example/x/y/][the /docs/]] or ~a/b/c~ now"),
+            "see [the *docs*](https://a.example/x/y/) or `a/b/c` now"
+        );
+        assert_eq!(
+            inline("[[file:scan.png]] and/or 1+1=2"),
+            "![](scan.png) and/or 1+1=2"
+        );
+        assert_eq!(inline("*bold [[x.md][link]]*"), "**bold [link](x.md)**");
+    }
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let markdown = "Intro with **bold**, *em*, ~~gone~~ and `code`.\n\n\
+                        ## Steps\n\n\
+                        - [ ] call [bank](https://bank.example)\n\
+                        - [x] see ![](scan.png)\n  \
+                          - nested\n\n\
+                        1. first\n2. second\n\n\
+                        ```rust\nfn main() {}\n* not a heading\n```\n\n\
+                        > quoted\n\n\
+                        | a | b |\n|---|---|\n| 1 | 2 |";
+        let org = format!("* TODO Round trip\n{}", markdown_to_org(markdown, 1));
+        let mut report = ImportReport::default();
+        let drafts = parse_org(&org, &mut report);
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].description.as_deref(), Some(markdown));
+    }
+}
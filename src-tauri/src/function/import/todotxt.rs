@@ -0,0 +1,248 @@
+use super::{Draft, ImportReport};
+use crate::entity::event::Priority;
+use crate::function::convert::{parse_date, simple_rrule, Frequency};
+
+/// 还原导出时转义的标签，`%20` 为空格、`%25` 为 `%`
+pub(super) fn unescape_tag(text: &str) -> String {
+    text.split("%25")
+        .map(|part| part.replace("%20", " "))
+        .collect::<Vec<_>>()
+        .join("%")
+}
+
+/// `(A)` 为高优先级，`(B)` 为中，其余字母为低
+fn parse_priority(text: &str) -> Option<Priority> {
+    let mut chars = text.chars();
+    let letter = chars.next()?;
+    if chars.next().is_some() || !letter.is_ascii_uppercase() {
+        return None;
+    }
+    Some(match letter {
+        'A' => Priority::High,
+        'B' => Priority::Medium,
+        _ => Priority::Low,
+    })
+}
+
+/// 将 `rec:` 扩展的值（如 `2w`、`+1m`）转换为 RRULE，工作日重复（`b`）不受支持
+pub(super) fn rec_to_rrule(text: &str) -> Option<String> {
+    let text = text.strip_prefix('+').unwrap_or(text);
+    let unit = text.chars().last()?;
+    let interval = match &text[..text.len() - unit.len_utf8()] {
+        "" => 1,
+        n => n.parse::<u32>().ok()?,
+    };
+    let freq = Frequency::from_letter(unit)?;
+    Some(simple_rrule(freq, interval))
+}
+
+/// 解析一行 todo.txt，空行返回 None
+///
+/// 第一个 `+project` 作为列表，`@context` 还原转义后作为标签；识别 `due:`、`rec:`、`pri:` 与 `id:` 扩展，
+/// 其余 `key:value` 保留在标题中。
+fn parse_line(line: &str) -> Option<Result<Draft, String>> {
+    let mut tokens = line.split_whitespace().peekable();
+    tokens.peek()?;
+
+    let mut draft = Draft::new("");
+    draft.lossy = true;
+    if tokens.next_if_eq(&"x").is_some() {
+        draft.finished = true;
+        // 完成日期之后才是创建日期
+        if tokens.next_if(|t| parse_date(t).is_some()).is_some() {
+            draft.created = tokens
+                .next_if(|t| parse_date(t).is_some())
+                .and_then(parse_date);
+        }
+    }
+    if let Some(priority) = tokens
+        .peek()
+        .and_then(|t| t.strip_prefix('(')?.strip_suffix(')'))
+        .and_then(parse_priority)
+    {
+        draft.priority = priority;
+        tokens.next();
+    }
+    if !draft.finished {
+        draft.created = tokens
+            .next_if(|t| parse_date(t).is_some())
+            .and_then(parse_date);
+    }
+
+    let mut title = Vec::new();
+    for token in tokens {
+        if let Some(project) = token.strip_prefix('+').filter(|p| !p.is_empty()) {
+            if draft.list.is_none() {
+                draft.list = Some(project.to_string());
+            }
+        } else if let Some(context) = token.strip_prefix('@').filter(|c| !c.is_empty()) {
+            let tag = unescape_tag(context);
+            if !draft.tags.contains(&tag) {
+                draft.tags.push(tag);
+            }
+        } else if let Some((key, value)) = token.split_once(':').filter(|(_, v)| !v.is_empty()) {
+            match key {
+                "due" => match parse_date(value) {
+                    Some(due) => draft.task_time = Some(due),
+                    None => return Some(Err(format!("无法解析截止日期: {}", value))),
+                },
+                "rec" => draft.recurrence = rec_to_rrule(value),
+                "pri" => draft.priority = parse_priority(value).unwrap_or(Priority::Undefined),
+                "id" => draft.uid = Some(value.to_string()),
+                _ => title.push(token),
+            }
+        } else {
+            title.push(token);
+        }
+    }
+
+    draft.title = title.join(" ");
+    if draft.title.is_empty() {
+        return Some(Err("缺少标题".to_string()));
+    }
+    Some(Ok(draft))
+}
+
+/// 解析 todo.txt 文本，每行一个任务
+pub fn parse_todotxt(text: &str, report: &mut ImportReport) -> Vec<Draft> {
+    let mut drafts = Vec::new();
+    for line in text.lines() {
+        match parse_line(line) {
+            Some(Ok(draft)) => drafts.push(draft),
+            Some(Err(reason)) => report.skip(line.trim(), &reason),
+            None => {}
+        }
+    }
+    drafts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn local_date(y: i32, m: u32, d: u32) -> Option<u64> {
+        Some(
+            Local
+                .with_ymd_and_hms(y, m, d, 0, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        )
+    }
+
+    #[test]
+    fn test_parse_open_task() {
+        let draft = parse_line(
+            "(A) 2026-10-10 Call plumber +Home_Repairs @phone due:2026-10-20 rec:2w id:abc",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(draft.title, "Call plumber");
+        assert_eq!(draft.priority, Priority::High);
+        assert_eq!(draft.created, local_date(2026, 10, 10));
+        assert_eq!(draft.list.as_deref(), Some("Home_Repairs"));
+        assert_eq!(draft.tags, vec!["phone"]);
+        assert_eq!(draft.task_time, local_date(2026, 10, 20));
+        assert_eq!(draft.recurrence.as_deref(), Some("FREQ=WEEKLY;INTERVAL=2"));
+        assert_eq!(draft.uid.as_deref(), Some("abc"));
+        assert!(!draft.finished);
+    }
+
+    #[test]
+    fn test_parse_completed_task() {
+        let draft = parse_line("x 2026-10-15 2026-10-01 Renew passport pri:B note:urgent")
+            .unwrap()
+            .unwrap();
+        assert!(draft.finished);
+        assert_eq!(draft.created, local_date(2026, 10, 1));
+        assert_eq!(draft.priority, Priority::Medium);
+        assert_eq!(
+            draft.title, "Renew passport note:urgent",
+            "未知扩展保留在标题中"
+        );
+
+        // 小写字母开头的 x 只是标题的一部分
+        let draft = parse_line("xylophone lessons").unwrap().unwrap();
+        assert!(!draft.finished);
+        assert_eq!(draft.title, "xylophone lessons");
+    }
+
+    #[test]
+    fn test_parse_todotxt_report() {
+        let text = "(B) Buy milk\n\n@home\nFix bike due:someday\n";
+        let mut report = ImportReport::default();
+        let drafts = parse_todotxt(text, &mut report);
+        assert_eq!(drafts.len(), 1);
+        assert_eq!(drafts[0].priority, Priority::Medium);
+        let reasons: Vec<&str> = report.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(reasons, vec!["缺少标题", "无法解析截止日期: someday"]);
+    }
+
+    #[test]
+    fn test_unescape_tag() {
+        use crate::function::export::todotxt::escape_tag;
+
+        for tag in ["on hold", "100%", "50%20off", "plain"] {
+            assert_eq!(unescape_tag(&escape_tag(tag)), tag);
+        }
+        assert_eq!(unescape_tag("on_hold"), "on_hold");
+    }
+
+    #[test]
+    fn test_rec_to_rrule() {
+        assert_eq!(rec_to_rrule("d").as_deref(), Some("FREQ=DAILY"));
+        assert_eq!(rec_to_rrule("+1m").as_deref(), Some("FREQ=MONTHLY"));
+        assert_eq!(
+            rec_to_rrule("3y").as_deref(),
+            Some("FREQ=YEARLY;INTERVAL=3")
+        );
+        assert_eq!(rec_to_rrule("5b"), None);
+    }
+
+    #[test]
+    fn test_export_round_trip_keeps_content() {
+        use crate::entity::{Event, Repository};
+        use crate::function::export::pipeline::collect;
+        use crate::function::export::todotxt::write_todotxt;
+        use crate::function::import::apply;
+        use crate::test::temp_storage;
+        use std::fs;
+
+        let (storage, dir) = temp_storage();
+
+        let content = dir.path().join("Call plumber.md");
+        fs::write(&content, "Ask about the **boiler**").unwrap();
+        let mut event = Event::new("Call plumber", &content.to_string_lossy());
+        event.priority = Priority::High;
+        event.metadata.tag = Some(vec!["on hold".to_string(), "100%".to_string()]);
+        event.recurrence = Some("FREQ=MONTHLY;BYMONTHDAY=1".to_string());
+        event.task_time = Some(
+            Local
+                .with_ymd_and_hms(2026, 10, 20, 18, 0, 0)
+                .unwrap()
+                .timestamp_millis() as u64,
+        );
+        Repository::<Event>::add(&storage, &event).unwrap();
+
+        // todo.txt 没有正文，在其他应用中修改优先级后重新导入
+        let events = [event.clone()];
+        let text = collect(|out| write_todotxt(&storage, &events, out)).unwrap();
+        let text = text.replacen("(A)", "(B)", 1);
+        let mut report = ImportReport::default();
+        let drafts = parse_todotxt(&text, &mut report);
+        apply(&storage, drafts, None, &mut report).unwrap();
+
+        assert_eq!(report.updated.len(), 1);
+        let updated: Event = Repository::get_by_name(&storage, &event.metadata.uuid)
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.priority, Priority::Medium);
+        assert_eq!(updated.metadata.tag, event.metadata.tag);
+        assert_eq!(updated.task_time, event.task_time, "日期未变时保留时间");
+        assert_eq!(updated.recurrence, event.recurrence);
+        assert_eq!(
+            fs::read_to_string(&content).unwrap(),
+            "Ask about the **boiler**"
+        );
+    }
+}
@@ -0,0 +1,638 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::ops::DerefMut;
+use tauri::State;
+use ts_rs::TS;
+
+use crate::entity::event::{prepare_event, Priority};
+use crate::entity::tag::{add_tag, TagColor};
+use crate::entity::{Event, List, Repository, StorageState};
+use crate::error::ErrorKind;
+use crate::utils::tag_exists;
+
+/// 快速添加的解析结果，用于在提交前向用户预览
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export)]
+pub struct QuickAddPreview {
+    pub title: String,
+    /// 截止时间，毫秒时间戳字符串，未解析到时为空字符串
+    pub ddl: String,
+    /// RFC 5545 RRULE
+    pub recurrence: Option<String>,
+    pub tags: Vec<String>,
+    pub priority: Priority,
+    /// 输入中 `@` 后的列表名
+    pub list_name: Option<String>,
+    /// 按名称匹配到的列表ID，未找到时为 None
+    pub listid: Option<String>,
+}
+
+/// 重复规则
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Recurrence {
+    Daily,
+    Weekdays,
+    Weekly(Option<Weekday>),
+    Monthly(Option<u32>),
+    Yearly,
+}
+
+impl Recurrence {
+    fn to_rrule(self) -> String {
+        match self {
+            Recurrence::Daily => "FREQ=DAILY".to_string(),
+            Recurrence::Weekdays => "FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_string(),
+            Recurrence::Weekly(None) => "FREQ=WEEKLY".to_string(),
+            Recurrence::Weekly(Some(day)) => format!("FREQ=WEEKLY;BYDAY={}", weekday_code(day)),
+            Recurrence::Monthly(None) => "FREQ=MONTHLY".to_string(),
+            Recurrence::Monthly(Some(day)) => format!("FREQ=MONTHLY;BYMONTHDAY={}", day),
+            Recurrence::Yearly => "FREQ=YEARLY".to_string(),
+        }
+    }
+
+    fn matches(self, date: NaiveDate) -> bool {
+        match self {
+            Recurrence::Weekdays => date.weekday().number_from_monday() <= 5,
+            Recurrence::Weekly(Some(day)) => date.weekday() == day,
+            Recurrence::Monthly(Some(day)) => date.day() == day,
+            _ => true,
+        }
+    }
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// 纯文本解析结果，不依赖数据库
+struct Parsed {
+    title: String,
+    due: Option<NaiveDateTime>,
+    recurrence: Option<Recurrence>,
+    tags: Vec<String>,
+    priority: Priority,
+    list_name: Option<String>,
+}
+
+const EN_WEEKDAY: &str = r"(mon(?:day)?|tue(?:s|sday)?|wed(?:nesday)?|thu(?:rs|rsday)?|fri(?:day)?|sat(?:urday)?|sun(?:day)?)";
+const CN_NUM: &str = r"([0-9]{1,2}|[零一二两三四五六七八九十]{1,3})";
+
+type Rule<T> = (Regex, fn(&Captures, NaiveDate) -> Option<T>);
+
+fn rule<T>(pattern: &str, apply: fn(&Captures, NaiveDate) -> Option<T>) -> Rule<T> {
+    (Regex::new(pattern).expect("invalid quick add pattern"), apply)
+}
+
+static RECURRENCE_RULES: Lazy<Vec<Rule<Recurrence>>> = Lazy::new(|| {
+    vec![
+        rule(
+            r"(?i)\b(?:every\s+month|monthly)(?:\s+on\s+the\s+([0-9]{1,2})(?:st|nd|rd|th)?)?\b",
+            |c, _| Some(Recurrence::Monthly(c.get(1).and_then(|m| m.as_str().parse().ok()))),
+        ),
+        rule(
+            &format!(r"(?i)\b(?:every\s+week|weekly)(?:\s+on\s+{})?\b", EN_WEEKDAY),
+            |c, _| Some(Recurrence::Weekly(c.get(1).and_then(|m| en_weekday(m.as_str())))),
+        ),
+        rule(&format!(r"(?i)\bevery\s+{}\b", EN_WEEKDAY), |c, _| {
+            Some(Recurrence::Weekly(en_weekday(&c[1])))
+        }),
+        rule(r"(?i)\bevery\s+weekday\b", |_, _| Some(Recurrence::Weekdays)),
+        rule(r"(?i)\b(?:every\s+day|daily)\b", |_, _| Some(Recurrence::Daily)),
+        rule(r"(?i)\b(?:every\s+year|yearly|annually)\b", |_, _| Some(Recurrence::Yearly)),
+        rule(&format!(r"每个?月\s*{}\s*[号日]", CN_NUM), |c, _| {
+            Some(Recurrence::Monthly(Some(cn_number(&c[1])?)))
+        }),
+        rule(r"每个?(?:周|星期|礼拜)([一二三四五六日天])", |c, _| {
+            Some(Recurrence::Weekly(cn_weekday(&c[1])))
+        }),
+        rule(r"每个?工作日", |_, _| Some(Recurrence::Weekdays)),
+        rule(r"每(?:天|日)", |_, _| Some(Recurrence::Daily)),
+        rule(r"每个?(?:周|星期|礼拜)", |_, _| Some(Recurrence::Weekly(None))),
+        rule(r"每个?月", |_, _| Some(Recurrence::Monthly(None))),
+        rule(r"每年", |_, _| Some(Recurrence::Yearly)),
+    ]
+});
+
+static DATE_RULES: Lazy<Vec<Rule<NaiveDate>>> = Lazy::new(|| {
+    vec![
+        rule(
+            r"(?i)\b(?:(?:on|by|due)\s+)?([0-9]{4})-([0-9]{1,2})-([0-9]{1,2})\b",
+            |c, _| NaiveDate::from_ymd_opt(c[1].parse().ok()?, c[2].parse().ok()?, c[3].parse().ok()?),
+        ),
+        rule(&format!(r"(?:([0-9]{{4}})年)?{}月{}[日号]", CN_NUM, CN_NUM), |c, today| {
+            let month = cn_number(&c[2])?;
+            let day = cn_number(&c[3])?;
+            match c.get(1) {
+                Some(year) => NaiveDate::from_ymd_opt(year.as_str().parse().ok()?, month, day),
+                None => upcoming_month_day(today, month, day),
+            }
+        }),
+        rule(
+            r"(?i)\b(?:(?:on|by|due)\s+)?(?:the\s+)?day\s+after\s+tomorrow\b",
+            |_, today| Some(today + Duration::days(2)),
+        ),
+        rule(r"(?i)\b(?:(?:by|due)\s+)?(?:tomorrow|tmr)\b", |_, today| {
+            Some(today + Duration::days(1))
+        }),
+        rule(r"(?i)\b(?:(?:by|due)\s+)?(?:today|tonight)\b", |_, today| Some(today)),
+        rule(r"大后天", |_, today| Some(today + Duration::days(3))),
+        rule(r"后天", |_, today| Some(today + Duration::days(2))),
+        rule(r"明天|明日|明早|明晚", |_, today| Some(today + Duration::days(1))),
+        rule(r"今天|今日|今晚", |_, today| Some(today)),
+        rule(r"(?i)\bin\s+([0-9]+)\s+(day|week)s?\b", |c, today| {
+            let n: i64 = c[1].parse().ok()?;
+            let days = if c[2].eq_ignore_ascii_case("week") { n * 7 } else { n };
+            Some(today + Duration::days(days))
+        }),
+        rule(r"(?i)\bnext\s+week\b", |_, today| Some(week_start(today) + Duration::days(7))),
+        rule(
+            &format!(r"(?i)\b(?:(next|this)\s+|(?:on|by|due)\s+)?{}\b", EN_WEEKDAY),
+            |c, today| {
+                let day = en_weekday(&c[2])?;
+                match c.get(1).map(|m| m.as_str().to_ascii_lowercase()) {
+                    Some(prefix) if prefix == "next" => Some(weekday_in_week(today, 1, day)),
+                    _ => Some(upcoming_weekday(today, day)),
+                }
+            },
+        ),
+        rule(r"(下下|下个?|本|这个?)?(?:周|星期|礼拜)([一二三四五六日天])", |c, today| {
+            let day = cn_weekday(&c[2])?;
+            match c.get(1).map(|m| m.as_str()) {
+                Some("下下") => Some(weekday_in_week(today, 2, day)),
+                Some("下") | Some("下个") => Some(weekday_in_week(today, 1, day)),
+                _ => Some(upcoming_weekday(today, day)),
+            }
+        }),
+        rule(r"(?i)\b(?:(?:on|by|due)\s+)?([0-9]{1,2})/([0-9]{1,2})\b", |c, today| {
+            upcoming_month_day(today, c[1].parse().ok()?, c[2].parse().ok()?)
+        }),
+    ]
+});
+
+static TIME_RULES: Lazy<Vec<Rule<NaiveTime>>> = Lazy::new(|| {
+    vec![
+        rule(
+            &format!(
+                r"(早上|早晨|上午|中午|下午|晚上|傍晚|凌晨)?{}[点點时](?:(半)|(一刻)|(三刻)|{}分?)?",
+                CN_NUM, CN_NUM
+            ),
+            |c, _| {
+                let mut hour = cn_number(&c[2])?;
+                let minute = if c.get(3).is_some() {
+                    30
+                } else if c.get(4).is_some() {
+                    15
+                } else if c.get(5).is_some() {
+                    45
+                } else {
+                    c.get(6).map_or(Some(0), |m| cn_number(m.as_str()))?
+                };
+                match c.get(1).map(|m| m.as_str()) {
+                    Some("下午") | Some("晚上") | Some("傍晚") if hour < 12 => hour += 12,
+                    Some("中午") if hour < 11 => hour += 12,
+                    _ => {}
+                }
+                NaiveTime::from_hms_opt(hour, minute, 0)
+            },
+        ),
+        rule(r"(?i)\b(?:at\s+)?([0-9]{1,2})(?::([0-9]{2}))?\s*(am|pm)\b", |c, _| {
+            let hour: u32 = c[1].parse().ok()?;
+            let minute: u32 = c.get(2).map_or(Some(0), |m| m.as_str().parse().ok())?;
+            if hour == 0 || hour > 12 {
+                return None;
+            }
+            let hour = match (hour, c[3].eq_ignore_ascii_case("pm")) {
+                (12, false) => 0,
+                (12, true) => 12,
+                (hour, true) => hour + 12,
+                (hour, false) => hour,
+            };
+            NaiveTime::from_hms_opt(hour, minute, 0)
+        }),
+        rule(r"(?i)\b(?:at\s+)?([0-9]{1,2}):([0-9]{2})\b", |c, _| {
+            NaiveTime::from_hms_opt(c[1].parse().ok()?, c[2].parse().ok()?, 0)
+        }),
+    ]
+});
+
+fn en_weekday(text: &str) -> Option<Weekday> {
+    let lower = text.to_ascii_lowercase();
+    let day = match lower.get(..3)? {
+        "mon" => Weekday::Mon,
+        "tue" => Weekday::Tue,
+        "wed" => Weekday::Wed,
+        "thu" => Weekday::Thu,
+        "fri" => Weekday::Fri,
+        "sat" => Weekday::Sat,
+        "sun" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(day)
+}
+
+fn cn_weekday(text: &str) -> Option<Weekday> {
+    let day = match text {
+        "一" => Weekday::Mon,
+        "二" => Weekday::Tue,
+        "三" => Weekday::Wed,
+        "四" => Weekday::Thu,
+        "五" => Weekday::Fri,
+        "六" => Weekday::Sat,
+        "日" | "天" => Weekday::Sun,
+        _ => return None,
+    };
+    Some(day)
+}
+
+/// 解析阿拉伯数字或不超过九十九的中文数字
+fn cn_number(text: &str) -> Option<u32> {
+    if let Ok(n) = text.parse::<u32>() {
+        return Some(n);
+    }
+    let digit = |c: char| match c {
+        '零' => Some(0),
+        '一' => Some(1),
+        '二' | '两' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        _ => None,
+    };
+    let chars: Vec<char> = text.chars().collect();
+    match chars.iter().position(|&c| c == '十') {
+        None if chars.len() == 1 => digit(chars[0]),
+        None => None,
+        Some(pos) => {
+            let tens = match pos {
+                0 => 1,
+                1 => digit(chars[0])?,
+                _ => return None,
+            };
+            let ones = match &chars[pos + 1..] {
+                [] => 0,
+                [c] => digit(*c)?,
+                _ => return None,
+            };
+            Some(tens * 10 + ones)
+        }
+    }
+}
+
+/// 本周（以周一为一周开始）的第一天
+fn week_start(today: NaiveDate) -> NaiveDate {
+    today - Duration::days(today.weekday().num_days_from_monday() as i64)
+}
+
+/// 距今 `weeks_ahead` 周的那一周中的指定星期
+fn weekday_in_week(today: NaiveDate, weeks_ahead: i64, day: Weekday) -> NaiveDate {
+    week_start(today) + Duration::days(weeks_ahead * 7 + day.num_days_from_monday() as i64)
+}
+
+/// 从今天起（含今天）最近的指定星期
+fn upcoming_weekday(today: NaiveDate, day: Weekday) -> NaiveDate {
+    let diff = (7 + day.num_days_from_monday() as i64
+        - today.weekday().num_days_from_monday() as i64)
+        % 7;
+    today + Duration::days(diff)
+}
+
+/// 今年的指定月日，若已过去则取明年
+fn upcoming_month_day(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date < today {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
+
+/// 依次尝试规则，返回第一个匹配的结果并从文本中移除匹配片段
+fn take_first<T>(text: &mut String, rules: &[Rule<T>], today: NaiveDate) -> Option<T> {
+    for (pattern, apply) in rules {
+        let Some(caps) = pattern.captures(text.as_str()) else {
+            continue;
+        };
+        if let Some(value) = apply(&caps, today) {
+            let range = caps.get(0).map_or(0..0, |m| m.range());
+            text.replace_range(range, " ");
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// 解析 `!` 开头的优先级标记，单独的 `!!` 为高、`!` 为中，先匹配较长的标记
+fn parse_priority(word: &str) -> Option<Priority> {
+    let priority = match word {
+        "!!" => Priority::High,
+        "!" => Priority::Medium,
+        _ => match word.strip_prefix('!')?.to_lowercase().as_str() {
+            "high" | "h" | "1" | "高" => Priority::High,
+            "medium" | "med" | "m" | "2" | "中" => Priority::Medium,
+            "low" | "l" | "3" | "低" => Priority::Low,
+            _ => return None,
+        },
+    };
+    Some(priority)
+}
+
+/// 解析快速添加文本
+///
+/// 支持 `#标签`、`!high`/`!中` 优先级、`@列表` 以及中英文的日期、时间和重复规则。
+fn parse(text: &str, now: NaiveDateTime) -> Parsed {
+    let mut tags = Vec::new();
+    let mut priority = Priority::Undefined;
+    let mut list_name = None;
+    let mut words = Vec::new();
+
+    // 先提取带符号前缀的标记
+    for word in text.split_whitespace() {
+        if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        } else if let Some(name) = word.strip_prefix('@').filter(|n| !n.is_empty()) {
+            list_name = Some(name.to_string());
+        } else if let Some(p) = parse_priority(word) {
+            priority = p;
+        } else {
+            words.push(word);
+        }
+    }
+    let mut rest = words.join(" ");
+
+    let today = now.date();
+    let recurrence = take_first(&mut rest, RECURRENCE_RULES.as_slice(), today);
+    let date = take_first(&mut rest, DATE_RULES.as_slice(), today);
+    let time = take_first(&mut rest, TIME_RULES.as_slice(), today);
+
+    let due = match (date, time, recurrence) {
+        (Some(date), time, _) => Some(date.and_time(time.unwrap_or_else(midnight))),
+        (None, time, Some(rule)) => {
+            // 从今天起寻找第一个满足重复规则的日期
+            let all_day = time.is_none();
+            let time = time.unwrap_or_else(midnight);
+            (0..400)
+                .map(|offset| (today + Duration::days(offset)).and_time(time))
+                .find(|due| rule.matches(due.date()) && (*due >= now || (all_day && due.date() == today)))
+        }
+        (None, Some(time), None) => {
+            let due = today.and_time(time);
+            Some(if due < now { due + Duration::days(1) } else { due })
+        }
+        (None, None, None) => None,
+    };
+
+    let title = rest
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == ',' || c == '，' || c.is_whitespace())
+        .to_string();
+
+    Parsed {
+        title,
+        due,
+        recurrence,
+        tags,
+        priority,
+        list_name,
+    }
+}
+
+fn midnight() -> NaiveTime {
+    NaiveTime::from_hms_opt(0, 0, 0).unwrap()
+}
+
+fn to_millis(due: NaiveDateTime) -> Option<u64> {
+    Local
+        .from_local_datetime(&due)
+        .earliest()
+        .map(|dt| dt.timestamp_millis() as u64)
+}
+
+/// Parses a quick-add line into a preview
+///
+/// Splits free text such as `Pay rent every month on the 1st 9am #finance !high @Household`
+/// into title, due date, recurrence, tags, priority and list. Chinese expressions like
+/// `明天下午三点` and `下周五` are supported. Nothing is written to the database.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `text` - The raw quick-add input
+///
+/// # Returns
+/// * `Result<QuickAddPreview, ErrorKind>` - The parsed preview or an error
+#[tauri::command]
+pub async fn quick_add(
+    state: State<'_, StorageState>,
+    text: &str,
+) -> Result<QuickAddPreview, ErrorKind> {
+    let parsed = parse(text, Local::now().naive_local());
+    if parsed.title.is_empty() {
+        return Err(ErrorKind::InvalidInput);
+    }
+
+    // 按名称查找列表
+    let listid = match &parsed.list_name {
+        Some(name) => {
+            let mut guard = state.0.lock().await;
+            let storage = guard.deref_mut();
+            Repository::<List>::filter(storage, |list| list.title.eq_ignore_ascii_case(name))?
+                .into_iter()
+                .next()
+                .map(|list| list.uuid)
+        }
+        None => None,
+    };
+
+    Ok(QuickAddPreview {
+        title: parsed.title,
+        ddl: parsed
+            .due
+            .and_then(to_millis)
+            .map(|ms| ms.to_string())
+            .unwrap_or_default(),
+        recurrence: parsed.recurrence.map(Recurrence::to_rrule),
+        tags: parsed.tags,
+        priority: parsed.priority,
+        list_name: parsed.list_name,
+        listid,
+    })
+}
+
+/// Creates an event from a (possibly edited) quick-add preview
+///
+/// The event is built like in `add_event`, so generated tags behave as usual, and is
+/// stored once with the recurrence from the preview. Tags from the preview are created
+/// if missing and merged with any generated tags.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `preview` - The preview returned by `quick_add`, optionally edited by the user
+///
+/// # Returns
+/// * `Result<Event, ErrorKind>` - The created event or an error
+#[tauri::command]
+pub async fn commit_quick_add(
+    state: State<'_, StorageState>,
+    preview: QuickAddPreview,
+) -> Result<Event, ErrorKind> {
+    let mut event = prepare_event(
+        state.clone(),
+        &preview.title,
+        preview.listid.as_deref(),
+        preview.priority,
+        &preview.ddl,
+    )
+    .await?;
+
+    for tag in &preview.tags {
+        if !tag_exists(&state, tag).await {
+            add_tag(state.clone(), tag.clone(), TagColor::Primary).await?;
+        }
+    }
+
+    let mut tags = event.metadata.tag.take().unwrap_or_default();
+    for tag in preview.tags {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    event.metadata.tag = if tags.is_empty() { None } else { Some(tags) };
+    event.recurrence = preview.recurrence;
+
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    Repository::<Event>::add(storage, &event)?;
+
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-10-14 是周三
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> Option<NaiveDateTime> {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, min, 0)
+    }
+
+    #[test]
+    fn test_parse_full_example() {
+        let parsed = parse("Pay rent every month on the 1st 9am #finance !high @Household", now());
+        assert_eq!(parsed.title, "Pay rent");
+        assert_eq!(parsed.tags, vec!["finance".to_string()]);
+        assert!(matches!(parsed.priority, Priority::High));
+        assert_eq!(parsed.list_name.as_deref(), Some("Household"));
+        assert_eq!(parsed.recurrence, Some(Recurrence::Monthly(Some(1))));
+        assert_eq!(parsed.recurrence.unwrap().to_rrule(), "FREQ=MONTHLY;BYMONTHDAY=1");
+        assert_eq!(parsed.due, at(2026, 11, 1, 9, 0));
+    }
+
+    #[test]
+    fn test_parse_chinese_dates() {
+        let parsed = parse("明天下午三点开会", now());
+        assert_eq!(parsed.title, "开会");
+        assert_eq!(parsed.due, at(2026, 10, 15, 15, 0));
+
+        let parsed = parse("下周五提交报告 !高", now());
+        assert_eq!(parsed.title, "提交报告");
+        assert!(matches!(parsed.priority, Priority::High));
+        assert_eq!(parsed.due, at(2026, 10, 23, 0, 0));
+
+        let parsed = parse("周五晚上八点半聚餐", now());
+        assert_eq!(parsed.title, "聚餐");
+        assert_eq!(parsed.due, at(2026, 10, 16, 20, 30));
+
+        let parsed = parse("每周一上午十点例会", now());
+        assert_eq!(parsed.title, "例会");
+        assert_eq!(parsed.recurrence, Some(Recurrence::Weekly(Some(Weekday::Mon))));
+        assert_eq!(parsed.due, at(2026, 10, 19, 10, 0));
+
+        let parsed = parse("12月25日 买礼物", now());
+        assert_eq!(parsed.title, "买礼物");
+        assert_eq!(parsed.due, at(2026, 12, 25, 0, 0));
+    }
+
+    #[test]
+    fn test_parse_english_dates() {
+        let parsed = parse("Call mom tomorrow at 18:30", now());
+        assert_eq!(parsed.title, "Call mom");
+        assert_eq!(parsed.due, at(2026, 10, 15, 18, 30));
+
+        let parsed = parse("Submit report next friday", now());
+        assert_eq!(parsed.title, "Submit report");
+        assert_eq!(parsed.due, at(2026, 10, 23, 0, 0));
+
+        let parsed = parse("Review PR on friday", now());
+        assert_eq!(parsed.due, at(2026, 10, 16, 0, 0));
+
+        let parsed = parse("Renew passport 2027-01-05 !low", now());
+        assert_eq!(parsed.title, "Renew passport");
+        assert!(matches!(parsed.priority, Priority::Low));
+        assert_eq!(parsed.due, at(2027, 1, 5, 0, 0));
+
+        // 时间已过则顺延到明天
+        let parsed = parse("Stretch 9am", now());
+        assert_eq!(parsed.due, at(2026, 10, 15, 9, 0));
+
+        // 没有 am/pm 或分钟的数字不是时间
+        let parsed = parse("Book table at 5 Main St", now());
+        assert_eq!(parsed.title, "Book table at 5 Main St");
+        assert!(parsed.due.is_none());
+    }
+
+    #[test]
+    fn test_parse_plain_title() {
+        let parsed = parse("Buy milk #home #home", now());
+        assert_eq!(parsed.title, "Buy milk");
+        assert_eq!(parsed.tags, vec!["home".to_string()]);
+        assert!(parsed.due.is_none());
+        assert!(parsed.recurrence.is_none());
+        assert!(matches!(parsed.priority, Priority::Undefined));
+    }
+
+    #[test]
+    fn test_parse_priority_markers() {
+        assert!(matches!(parse("Ship release !!", now()).priority, Priority::High));
+        assert!(matches!(parse("Ship release !", now()).priority, Priority::Medium));
+        assert!(matches!(parse("Ship release !m", now()).priority, Priority::Medium));
+        let parsed = parse("Ship release !!!", now());
+        assert_eq!(parsed.title, "Ship release !!!");
+        assert!(matches!(parsed.priority, Priority::Undefined));
+    }
+
+    #[test]
+    fn test_cn_number() {
+        assert_eq!(cn_number("三"), Some(3));
+        assert_eq!(cn_number("十"), Some(10));
+        assert_eq!(cn_number("十一"), Some(11));
+        assert_eq!(cn_number("二十"), Some(20));
+        assert_eq!(cn_number("三十一"), Some(31));
+        assert_eq!(cn_number("两"), Some(2));
+        assert_eq!(cn_number("15"), Some(15));
+        assert_eq!(cn_number("十十"), None);
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::DerefMut;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+use tokio::time::Duration;
+
+use crate::entity::subscription::{self, OverlayItem, Subscription};
+use crate::entity::{Repository, Storage, StorageState};
+use crate::function::import::ics::parse_ics;
+use crate::function::import::{Draft, ImportReport};
+use crate::utils::manager::interval_task::IntervalTask;
+use crate::utils::manager::tasker::TaskManager;
+
+const DEFAULT_REFRESH: u32 = 60;
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+// 定时刷新尚未结束时跳过下一次检查
+static REFRESHING: AtomicBool = AtomicBool::new(false);
+
+fn now() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+fn is_remote(source: &str) -> bool {
+    ["http://", "https://", "webcal://"]
+        .iter()
+        .any(|scheme| source.starts_with(scheme))
+}
+
+/// 读取订阅源的内容，webcal 地址按 https 请求
+async fn fetch(source: &str) -> Result<String> {
+    if !is_remote(source) {
+        return Ok(tokio::fs::read_to_string(source).await?);
+    }
+    let url = match source.strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => source.to_string(),
+    };
+    let text = reqwest::get(&url).await?.error_for_status()?.text().await?;
+    Ok(text)
+}
+
+/// 将解析出的条目转换为订阅条目，没有UID的条目以标题与时间生成标识
+fn overlay_items(subscription: &str, drafts: Vec<Draft>) -> Vec<OverlayItem> {
+    let mut items: Vec<OverlayItem> = Vec::with_capacity(drafts.len());
+    for draft in drafts {
+        let uid = draft.uid.clone().unwrap_or_else(|| {
+            let mut hasher = DefaultHasher::new();
+            draft.title.hash(&mut hasher);
+            draft.task_time.hash(&mut hasher);
+            format!("{:016x}", hasher.finish())
+        });
+        let uuid = OverlayItem::id(subscription, &uid);
+        // 重复实例（RECURRENCE-ID）与主条目共用UID，只保留第一个
+        if items.iter().any(|item| item.uuid == uuid) {
+            continue;
+        }
+        items.push(OverlayItem {
+            uuid,
+            subscription: subscription.to_string(),
+            title: draft.title,
+            description: draft.description.unwrap_or_default(),
+            start: draft.task_time,
+            finished: draft.finished,
+            priority: draft.priority,
+            tags: draft.tags,
+            recurrence: draft.recurrence,
+        });
+    }
+    items
+}
+
+/// 用读取结果更新订阅，失败时保留原有条目并记录原因
+///
+/// 每次都记录尝试时间，只有成功时才更新 `last_refreshed`。
+fn apply(storage: &Storage, uuid: &str, fetched: Result<String>, now: u64) -> Result<Subscription> {
+    let items = fetched.and_then(|text| {
+        if !text.contains("BEGIN:VCALENDAR") {
+            return Err(anyhow!("不是有效的ICS日历"));
+        }
+        let mut report = ImportReport::default();
+        Ok(overlay_items(uuid, parse_ics(&text, &mut report)))
+    });
+    let error = match items {
+        Ok(items) => {
+            subscription::replace_items(storage, uuid, &items)?;
+            None
+        }
+        Err(e) => Some(e.to_string()),
+    };
+    Repository::<Subscription>::update(storage, uuid, |subscription| {
+        subscription.last_attempt = Some(now);
+        if error.is_none() {
+            subscription.last_refreshed = Some(now);
+        }
+        subscription.error = error;
+        Ok(())
+    })?;
+    Repository::<Subscription>::get_by_name(storage, uuid)?
+        .ok_or_else(|| anyhow!("订阅不存在: {}", uuid))
+}
+
+/// 刷新一个订阅，读取订阅源时不占用数据库
+async fn refresh(state: &StorageState, uuid: &str) -> Result<Subscription> {
+    let source = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        Repository::<Subscription>::get_by_name(storage, uuid)?
+            .ok_or_else(|| anyhow!("订阅不存在: {}", uuid))?
+            .source
+    };
+    let fetched = fetch(&source).await;
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    // 读取期间订阅可能已被删除
+    if Repository::<Subscription>::get_by_name(storage, uuid)?.is_none() {
+        return Err(anyhow!("订阅不存在: {}", uuid));
+    }
+    let subscription = apply(storage, uuid, fetched, now())?;
+    if let Some(e) = &subscription.error {
+        log::warn!(
+            "Failed to refresh subscription {}: {}",
+            subscription.name,
+            e
+        );
+    }
+    Ok(subscription)
+}
+
+/// 刷新所有到期的订阅
+async fn refresh_due(app: &AppHandle) -> Result<()> {
+    let state = app.state::<StorageState>();
+    let due: Vec<String> = {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        let now = now();
+        Repository::<Subscription>::filter(storage, |subscription| subscription.is_due(now))?
+            .into_iter()
+            .map(|subscription| subscription.uuid)
+            .collect()
+    };
+    for uuid in due {
+        refresh(&state, &uuid).await?;
+    }
+    Ok(())
+}
+
+/// Subscribes to a read-only ICS calendar
+///
+/// The calendar is fetched right away and then refreshed in the background
+/// every `refresh` minutes. Its items show up in date filters such as `today`
+/// and `this_week` but cannot be edited or deleted. A failed first fetch still
+/// keeps the subscription, with the reason in its `error` field.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `name` - Display name of the calendar
+/// * `source` - `http(s)://` or `webcal://` URL, or path to a local `.ics` file
+/// * `color` - Color used for the calendar's items
+/// * `refresh` - Optional refresh interval in minutes, defaults to 60
+///
+/// # Returns
+/// * `Result<Subscription, String>` - The new subscription or error message
+#[tauri::command]
+pub async fn add_subscription(
+    state: State<'_, StorageState>,
+    name: String,
+    source: String,
+    color: String,
+    refresh: Option<u32>,
+) -> Result<Subscription, String> {
+    let source = source.trim().to_string();
+    if !is_remote(&source) && !Path::new(&source).is_file() {
+        return Err(format!("无效的订阅源: {}", source));
+    }
+    let subscription = Subscription::new(
+        &name,
+        &source,
+        &color,
+        refresh.unwrap_or(DEFAULT_REFRESH).max(1),
+    );
+    {
+        let mut guard = state.0.lock().await;
+        let storage = guard.deref_mut();
+        Repository::<Subscription>::add(storage, &subscription)
+            .map_err(|e| format!("添加订阅失败: {}", e))?;
+    }
+    self::refresh(&state, &subscription.uuid)
+        .await
+        .map_err(|e| format!("刷新订阅失败: {}", e))
+}
+
+/// Gets all calendar subscriptions
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+///
+/// # Returns
+/// * `Result<Vec<Subscription>, String>` - All subscriptions or error message
+#[tauri::command]
+pub async fn get_subscriptions(
+    state: State<'_, StorageState>,
+) -> Result<Vec<Subscription>, String> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    Repository::<Subscription>::get_all(storage).map_err(|e| format!("获取订阅失败: {}", e))
+}
+
+/// Removes a calendar subscription together with its items
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Identifier of the subscription
+///
+/// # Returns
+/// * `Result<(), String>` - Success or error message
+#[tauri::command]
+pub async fn delete_subscription(state: State<'_, StorageState>, uuid: &str) -> Result<(), String> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    subscription::replace_items(storage, uuid, &[])
+        .and_then(|_| Repository::<Subscription>::delete(storage, uuid))
+        .map_err(|e| format!("删除订阅失败: {}", e))
+}
+
+/// Fetches a subscribed calendar again without waiting for its schedule
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `uuid` - Identifier of the subscription
+///
+/// # Returns
+/// * `Result<Subscription, String>` - The subscription with its refresh time or error, or error message
+#[tauri::command]
+pub async fn refresh_subscription(
+    state: State<'_, StorageState>,
+    uuid: &str,
+) -> Result<Subscription, String> {
+    refresh(&state, uuid)
+        .await
+        .map_err(|e| format!("刷新订阅失败: {}", e))
+}
+
+/// 启动订阅日历的定时刷新任务
+pub async fn setup(app: AppHandle) {
+    let task = IntervalTask::new("subscription_task", CHECK_INTERVAL, move || {
+        if REFRESHING.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = refresh_due(&app).await {
+                log::error!("Subscription refresh failed: {}", e);
+            }
+            REFRESHING.store(false, Ordering::SeqCst);
+        });
+    });
+    TaskManager::start(Box::new(task)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::event::Priority;
+    use crate::entity::Entity;
+    use crate::test::temp_storage;
+
+    const CALENDAR: &str = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\nUID:holiday-1\r\nSUMMARY:New Year\r\nDTSTART;VALUE=DATE:20250101\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nUID:holiday-1\r\nRECURRENCE-ID;VALUE=DATE:20250101\r\nSUMMARY:Moved\r\nDTSTART;VALUE=DATE:20250102\r\nEND:VEVENT\r\n\
+BEGIN:VEVENT\r\nSUMMARY:No uid\r\nEND:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_overlay_items() {
+        let mut report = ImportReport::default();
+        let items = overlay_items("s", parse_ics(CALENDAR, &mut report));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].uuid, "s:holiday-1");
+        assert_eq!(items[0].title, "New Year");
+        assert_eq!(items[0].priority, Priority::Undefined);
+        assert!(items[1].uuid.starts_with("s:"));
+        assert_eq!(items[1].title, "No uid");
+    }
+
+    #[test]
+    fn test_apply() {
+        let (storage, _dir) = temp_storage();
+        let txn = storage.db.begin_write().unwrap();
+        txn.open_table(OverlayItem::table_def()).unwrap();
+        txn.commit().unwrap();
+        let holidays = Subscription::new("Holidays", "holidays.ics", "red", 60);
+        Repository::<Subscription>::add(&storage, &holidays).unwrap();
+
+        let refreshed = apply(&storage, &holidays.uuid, Ok(CALENDAR.to_string()), 10).unwrap();
+        assert_eq!(refreshed.last_refreshed, Some(10));
+        assert_eq!(refreshed.error, None);
+        assert_eq!(
+            Repository::<OverlayItem>::get_all(&storage).unwrap().len(),
+            2
+        );
+
+        // 读取失败或内容无效时保留原有条目
+        let failed = apply(&storage, &holidays.uuid, Ok("<html>".to_string()), 20).unwrap();
+        assert_eq!(failed.last_refreshed, Some(10));
+        assert_eq!(failed.last_attempt, Some(20));
+        assert!(failed.error.is_some());
+        assert_eq!(
+            Repository::<OverlayItem>::get_all(&storage).unwrap().len(),
+            2
+        );
+
+        let recovered = apply(
+            &storage,
+            &holidays.uuid,
+            Ok("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n".to_string()),
+            30,
+        )
+        .unwrap();
+        assert_eq!(recovered.last_refreshed, Some(30));
+        assert_eq!(recovered.error, None);
+        assert!(Repository::<OverlayItem>::get_all(&storage)
+            .unwrap()
+            .is_empty());
+    }
+}
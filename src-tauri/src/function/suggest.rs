@@ -0,0 +1,343 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::ops::DerefMut;
+use tauri::State;
+use ts_rs::TS;
+
+use crate::entity::tag::TagColor;
+use crate::entity::{Event, Repository, Storage, StorageState, Tag};
+use crate::error::ErrorKind;
+use crate::utils::TAG_MODEL_CACHE;
+
+const MAX_SUGGESTIONS: usize = 3;
+const MODEL_CACHE_KEY: &str = "tag_model";
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "with", "from", "this", "that", "to", "of", "in", "on", "at", "an", "is",
+    "be", "it", "or", "by",
+];
+
+/// 批量补全标签时的单条建议
+#[derive(Serialize, Deserialize, TS, Clone)]
+#[ts(export)]
+pub struct TagSuggestion {
+    pub id: String,
+    pub title: String,
+    pub tags: Vec<String>,
+}
+
+/// 基于已打标签事件训练的朴素贝叶斯标签建议器
+///
+/// 每个标签独立做一对多判别，分数为对数几率，大于零才会被建议。
+#[derive(Clone, Default)]
+pub struct TagSuggester {
+    docs: usize,
+    vocab: HashSet<String>,
+    tag_docs: HashMap<String, usize>,
+    tag_tokens: HashMap<String, HashMap<String, usize>>,
+    tag_totals: HashMap<String, usize>,
+    all_tokens: HashMap<String, usize>,
+    all_total: usize,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{4e00}'..='\u{9fff}' | '\u{3400}'..='\u{4dbf}')
+}
+
+/// 将文本切分为词项：英文按单词，中文按相邻字的二元组
+pub fn tokenize(text: &str) -> Vec<String> {
+    fn flush_word(word: &mut String, tokens: &mut Vec<String>) {
+        if word.chars().count() >= 2 && !STOP_WORDS.contains(&word.as_str()) {
+            tokens.push(word.clone());
+        }
+        word.clear();
+    }
+    fn flush_cjk(run: &mut Vec<char>, tokens: &mut Vec<String>) {
+        if run.len() == 1 {
+            tokens.push(run[0].to_string());
+        } else {
+            tokens.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+        }
+        run.clear();
+    }
+
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut run = Vec::new();
+    for c in text.chars() {
+        if is_cjk(c) {
+            flush_word(&mut word, &mut tokens);
+            run.push(c);
+        } else if c.is_alphanumeric() {
+            flush_cjk(&mut run, &mut tokens);
+            word.extend(c.to_lowercase());
+        } else {
+            flush_word(&mut word, &mut tokens);
+            flush_cjk(&mut run, &mut tokens);
+        }
+    }
+    flush_word(&mut word, &mut tokens);
+    flush_cjk(&mut run, &mut tokens);
+    tokens
+}
+
+impl TagSuggester {
+    /// 用 (文本, 标签) 样本训练，只保留 `known_tags` 中的标签
+    pub fn train<'a>(
+        samples: impl IntoIterator<Item = (&'a str, &'a [String])>,
+        known_tags: &[String],
+    ) -> Self {
+        let known: HashSet<&String> = known_tags.iter().collect();
+        let mut model = TagSuggester::default();
+        for (text, tags) in samples {
+            let tags: HashSet<&String> = tags.iter().filter(|t| known.contains(t)).collect();
+            if tags.is_empty() {
+                continue;
+            }
+            let tokens = tokenize(text);
+            model.docs += 1;
+            for token in &tokens {
+                model.vocab.insert(token.clone());
+                *model.all_tokens.entry(token.clone()).or_default() += 1;
+            }
+            model.all_total += tokens.len();
+            for tag in tags {
+                *model.tag_docs.entry(tag.clone()).or_default() += 1;
+                *model.tag_totals.entry(tag.clone()).or_default() += tokens.len();
+                let counts = model.tag_tokens.entry(tag.clone()).or_default();
+                for token in &tokens {
+                    *counts.entry(token.clone()).or_default() += 1;
+                }
+            }
+        }
+        model
+    }
+
+    /// 为文本建议至多 `limit` 个标签，按置信度从高到低排列
+    pub fn suggest(&self, text: &str, limit: usize) -> Vec<String> {
+        let tokens: Vec<String> = tokenize(text)
+            .into_iter()
+            .filter(|t| self.vocab.contains(t))
+            .collect();
+        if tokens.is_empty() || self.docs == 0 {
+            return Vec::new();
+        }
+
+        let vocab = self.vocab.len() as f64;
+        let mut scored: Vec<(f64, &String)> = self
+            .tag_docs
+            .iter()
+            .map(|(tag, &n_tag)| {
+                let n_rest = self.docs - n_tag;
+                let total_tag = self.tag_totals.get(tag).copied().unwrap_or(0) as f64;
+                let total_rest = (self.all_total as f64 - total_tag).max(0.0);
+                let counts = &self.tag_tokens[tag];
+
+                let mut score = ((n_tag as f64 + 1.0) / (n_rest as f64 + 1.0)).ln();
+                for token in &tokens {
+                    let in_tag = counts.get(token).copied().unwrap_or(0) as f64;
+                    let in_rest = self.all_tokens.get(token).copied().unwrap_or(0) as f64 - in_tag;
+                    score += ((in_tag + 1.0) / (total_tag + vocab)).ln()
+                        - ((in_rest + 1.0) / (total_rest + vocab)).ln();
+                }
+                (score, tag)
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, tag)| tag.clone())
+            .collect()
+    }
+}
+
+/// 事件用于训练和预测的文本：标题加Markdown内容
+fn event_text(event: &Event) -> String {
+    let content = fs::read_to_string(&event.content).unwrap_or_default();
+    format!("{}\n{}", event.title, content)
+}
+
+fn train_from_storage(storage: &Storage) -> Result<TagSuggester> {
+    let known_tags: Vec<String> = Repository::<Tag>::get_all(storage)?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+    let tagged = Repository::<Event>::filter(storage, |event| {
+        event
+            .metadata
+            .tag
+            .as_ref()
+            .is_some_and(|tags| !tags.is_empty())
+    })?;
+    let samples: Vec<(String, Vec<String>)> = tagged
+        .iter()
+        .map(|event| {
+            (
+                event_text(event),
+                event.metadata.tag.clone().unwrap_or_default(),
+            )
+        })
+        .collect();
+    Ok(TagSuggester::train(
+        samples
+            .iter()
+            .map(|(text, tags)| (text.as_str(), tags.as_slice())),
+        &known_tags,
+    ))
+}
+
+/// 获取（必要时重新训练）缓存中的标签模型
+fn model(storage: &Storage) -> Result<TagSuggester> {
+    if let Some(model) = TAG_MODEL_CACHE.get(MODEL_CACHE_KEY) {
+        return Ok(model);
+    }
+    let model = train_from_storage(storage)?;
+    TAG_MODEL_CACHE.set(MODEL_CACHE_KEY, model.clone());
+    Ok(model)
+}
+
+/// 在未启用大模型时为新事件标题建议标签
+pub async fn suggest_for_title(
+    state: &State<'_, StorageState>,
+    title: &str,
+) -> Result<Vec<String>> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    Ok(model(storage)?.suggest(title, MAX_SUGGESTIONS))
+}
+
+/// Suggests tags for every untagged event
+///
+/// Trains the local suggester on already-tagged events and proposes tags from the
+/// existing tag table for events that have none. Nothing is written; pass the
+/// reviewed suggestions to `apply_tag_suggestions` to store them.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+///
+/// # Returns
+/// * `Result<Vec<TagSuggestion>, ErrorKind>` - Suggestions for events with at least one match
+#[tauri::command]
+pub async fn suggest_untagged_tags(
+    state: State<'_, StorageState>,
+) -> Result<Vec<TagSuggestion>, ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    let model = train_from_storage(storage)?;
+    TAG_MODEL_CACHE.set(MODEL_CACHE_KEY, model.clone());
+
+    let untagged = Repository::<Event>::filter(storage, |event| {
+        event
+            .metadata
+            .tag
+            .as_ref()
+            .is_none_or(|tags| tags.is_empty())
+    })?;
+    Ok(untagged
+        .iter()
+        .filter_map(|event| {
+            let tags = model.suggest(&event_text(event), MAX_SUGGESTIONS);
+            (!tags.is_empty()).then(|| TagSuggestion {
+                id: event.metadata.uuid.clone(),
+                title: event.title.clone(),
+                tags,
+            })
+        })
+        .collect())
+}
+
+/// Applies reviewed tag suggestions
+///
+/// Adds the given tags to each event, creating tags that were typed in during review.
+/// Suggestions for events that no longer exist are ignored.
+///
+/// # Parameters
+/// * `state` - Application state containing the database connection
+/// * `suggestions` - The accepted (and possibly edited) suggestions
+///
+/// # Returns
+/// * `Result<usize, ErrorKind>` - Number of events that were updated
+#[tauri::command]
+pub async fn apply_tag_suggestions(
+    state: State<'_, StorageState>,
+    suggestions: Vec<TagSuggestion>,
+) -> Result<usize, ErrorKind> {
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+
+    let mut existing: HashSet<String> = Repository::<Tag>::get_all(storage)?
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+    let mut updated = 0;
+    for suggestion in suggestions {
+        for tag in &suggestion.tags {
+            // 同一个新标签可能出现在多条建议中，只创建一次
+            if existing.insert(tag.clone()) {
+                Repository::<Tag>::add(storage, &Tag::new(tag.clone(), TagColor::Primary))?;
+            }
+        }
+        let Some(mut event) = Repository::<Event>::get_by_name(storage, &suggestion.id)? else {
+            continue;
+        };
+        let mut tags = event.metadata.tag.take().unwrap_or_default();
+        for tag in suggestion.tags {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        event.metadata.tag = Some(tags);
+        Repository::<Event>::add(storage, &event)?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("Pay the Rent!"), tags(&["pay", "rent"]));
+        assert_eq!(tokenize("交房租"), tags(&["交房", "房租"]));
+        assert_eq!(tokenize("买菜 milk"), tags(&["买菜", "milk"]));
+    }
+
+    #[test]
+    fn test_suggest() {
+        let finance = tags(&["finance"]);
+        let work = tags(&["work"]);
+        let samples = vec![
+            ("Pay rent", finance.as_slice()),
+            ("Pay electricity bill", finance.as_slice()),
+            ("交房租", finance.as_slice()),
+            ("Review pull request", work.as_slice()),
+            ("Write quarterly report", work.as_slice()),
+            ("准备周会报告", work.as_slice()),
+        ];
+        let known = tags(&["finance", "work", "home"]);
+        let model = TagSuggester::train(samples, &known);
+
+        assert_eq!(model.suggest("Pay water bill", 3), tags(&["finance"]));
+        assert_eq!(model.suggest("Review design report", 3), tags(&["work"]));
+        assert_eq!(model.suggest("下个月房租", 3), tags(&["finance"]));
+        assert!(model.suggest("completely unrelated", 3).is_empty());
+    }
+
+    #[test]
+    fn test_unknown_tags_are_ignored() {
+        let deleted = tags(&["deleted"]);
+        let model =
+            TagSuggester::train(vec![("Pay rent", deleted.as_slice())], &tags(&["finance"]));
+        assert!(model.suggest("Pay rent", 3).is_empty());
+    }
+}
@@ -1,13 +1,21 @@
+mod records;
+
+pub use records::RECORD_DIR;
+
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::ops::DerefMut;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::{AppHandle, Manager, State};
 use tokio::sync::Mutex;
 
-use crate::utils::config;
+use crate::entity::{Event, Repository, StorageState};
+use crate::utils::bus::{self, Change, ChangeKind, EntityKind};
+use crate::utils::{clear_all_caches, config, AppPaths};
 
-use webdav::sync_operations::perform_sync;
-use webdav::model::SyncSessionStatus;
+use webdav::sync_operations::{device_id, perform_sync};
+use webdav::model::{ConflictStrategy, SyncSession, SyncSessionStatus};
 use webdav::webdav::test_connection;
 
 // 同步状态
@@ -91,7 +99,75 @@ pub fn register_sync_commands(app: &tauri::App) -> Result<()> {
     Ok(())
 }
 
-pub async fn sync(state: State<'_, SyncState>) -> Result<(), String> {
+fn now() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+// 本设备的修改日志，位于配置目录中，不随数据目录同步
+fn modified_log() -> PathBuf {
+    AppPaths::config_dir().join("modified.log")
+}
+
+/// 记录本设备上每次仓库写入的时间，导出同步记录时作为修改时间
+pub fn track_changes() {
+    bus::subscribe(|change| {
+        if let Err(e) = records::touch(&modified_log(), change, now()) {
+            log::warn!("Failed to record modification of {}: {}", change.id, e);
+        }
+    });
+}
+
+// 将数据库中的变化导出为本设备的同步记录，随后的文件同步会上传它们
+async fn export_records(app: &AppHandle, device: &str) -> Result<()> {
+    let state = app.state::<StorageState>();
+    let guard = state.0.lock().await;
+    records::export_records(&guard, AppPaths::data_dir(), device, &modified_log(), now())
+}
+
+// 合并下载的各设备记录并写入数据库
+async fn merge_records(app: &AppHandle, device: &str) -> Result<usize> {
+    let state = app.state::<StorageState>();
+    let guard = state.0.lock().await;
+    // 先导出文件同步期间的修改，避免被合并结果覆盖
+    records::export_records(&guard, AppPaths::data_dir(), device, &modified_log(), now())?;
+    records::merge_records(&guard, AppPaths::data_dir(), device)
+}
+
+// 同步下载可能覆盖了事件内容，发布内容文件被下载的事件
+async fn publish_downloads(app: &AppHandle, session: &SyncSession) -> Result<()> {
+    let downloaded = session.downloaded_paths();
+    if downloaded.is_empty() {
+        return Ok(());
+    }
+
+    let state = app.state::<StorageState>();
+    let mut guard = state.0.lock().await;
+    let storage = guard.deref_mut();
+    clear_all_caches();
+
+    let mut changes = Vec::new();
+    for event in Repository::<Event>::get_all(storage)? {
+        if downloaded
+            .iter()
+            .any(|path| path == Path::new(&event.content))
+        {
+            changes.push(Change {
+                entity: EntityKind::Event,
+                kind: ChangeKind::Updated,
+                id: event.metadata.uuid,
+                scopes: event.metadata.list.into_iter().collect(),
+            });
+        }
+    }
+    drop(guard);
+
+    for change in &changes {
+        bus::publish(change);
+    }
+    Ok(())
+}
+
+pub async fn sync(app: AppHandle, state: State<'_, SyncState>) -> Result<(), String> {
     let webdav_config = match config::WebDav::load() {
         Ok(config) => config,
         Err(e) => {
@@ -115,8 +191,25 @@ pub async fn sync(state: State<'_, SyncState>) -> Result<(), String> {
 
     // 执行同步（在后台）
     let state_clone = state.inner().0.clone();
+    let strategy = ConflictStrategy::from_name(&webdav_config.conflict);
     tokio::spawn(async move {
-        let result = perform_sync().await;
+        // 数据库文件不参与文件同步，其中的记录逐条导出后随文件一起同步
+        let device = device_id().await;
+        if let Err(e) = export_records(&app, &device).await {
+            log::error!("导出同步记录失败: {}", e);
+        }
+        let mut result = perform_sync(strategy).await;
+        if let Ok(session) = &mut result {
+            if session.status != SyncSessionStatus::Aborted {
+                match merge_records(&app, &device).await {
+                    Ok(count) => log::info!("同步记录已合并，{} 条记录有变化", count),
+                    Err(e) => session.fail(format!("合并同步记录失败: {}", e)),
+                }
+            }
+            if let Err(e) = publish_downloads(&app, session).await {
+                log::error!("发布同步变更失败: {}", e);
+            }
+        }
 
         // 更新同步状态
         let mut manager = state_clone.lock().await;
@@ -151,15 +244,15 @@ pub async fn sync(state: State<'_, SyncState>) -> Result<(), String> {
 /// Only one sync operation can run at a time.
 ///
 /// # Parameters
-/// * `_app_handle` - Handle to the Tauri application
+/// * `app_handle` - Handle to the Tauri application, used to publish downloaded changes
 /// * `state` - Application state containing sync status information
 ///
 /// # Returns
 /// * `Result<(), String>` - Success or an error message if sync couldn't be started
 #[tauri::command]
-pub async fn sync_now(_app_handle: AppHandle, state: State<'_, SyncState>) -> Result<(), String> {
+pub async fn sync_now(app_handle: AppHandle, state: State<'_, SyncState>) -> Result<(), String> {
     log::info!("开始手动同步数据");
-    return sync(state).await.map_err(|e| {
+    return sync(app_handle, state).await.map_err(|e| {
         log::error!("手动同步失败: {}", e);
         format!("手动同步失败: {}", e)
     });
@@ -0,0 +1,619 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::entity::event::remove_event;
+use crate::entity::{Entity, Event, List, Repository, Storage, Tag};
+use crate::utils::bus::{Change, EntityKind};
+
+pub use webdav::sync_operations::RECORD_DIR;
+use webdav::sync_operations::{MAX_DELETE_RATIO, MIN_DELETE_CHECK};
+
+/// 一次修改的时间（毫秒时间戳）与设备，时间相同时按设备排序得到确定的结果
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Stamp {
+    pub time: u64,
+    pub device: String,
+}
+
+/// 记录中的一个字段及其最后一次修改
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Field {
+    value: Value,
+    stamp: Stamp,
+}
+
+/// 一条实体记录在某台设备上的已知状态
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+struct Record {
+    fields: BTreeMap<String, Field>,
+    #[serde(default)]
+    deleted: Option<Stamp>,
+}
+
+impl Record {
+    /// 合并另一设备的记录，每个字段与删除标记都保留较晚的修改
+    fn merge(&mut self, other: &Record) {
+        for (name, field) in &other.fields {
+            match self.fields.get(name) {
+                Some(current) if current.stamp >= field.stamp => {}
+                _ => {
+                    self.fields.insert(name.clone(), field.clone());
+                }
+            }
+        }
+        if other.deleted > self.deleted {
+            self.deleted = other.deleted.clone();
+        }
+    }
+
+    /// 删除晚于所有字段的修改，删除后又在其他设备上修改过的记录仍然保留
+    fn is_deleted(&self) -> bool {
+        match &self.deleted {
+            Some(deleted) => self.fields.values().all(|field| &field.stamp < deleted),
+            None => false,
+        }
+    }
+
+    /// 用实体的当前字段更新记录，值有变化的字段记为本次修改
+    fn update(&mut self, fields: BTreeMap<String, Value>, stamp: &Stamp) -> bool {
+        let mut changed = false;
+        for (name, value) in fields {
+            if self.fields.get(&name).map(|field| &field.value) != Some(&value) {
+                self.fields.insert(
+                    name,
+                    Field {
+                        value,
+                        stamp: stamp.clone(),
+                    },
+                );
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn values(&self) -> BTreeMap<String, Value> {
+        self.fields
+            .iter()
+            .map(|(name, field)| (name.clone(), field.value.clone()))
+            .collect()
+    }
+}
+
+/// 将实体展开为字段，嵌套的对象（如事件的 `metadata`）展开一层为 `metadata.list` 形式
+fn flatten(value: Value) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    if let Value::Object(map) = value {
+        for (key, value) in map {
+            match value {
+                Value::Object(inner) => {
+                    for (name, value) in inner {
+                        fields.insert(format!("{}.{}", key, name), value);
+                    }
+                }
+                value => {
+                    fields.insert(key, value);
+                }
+            }
+        }
+    }
+    fields
+}
+
+fn unflatten(fields: BTreeMap<String, Value>) -> Value {
+    let mut map = Map::new();
+    for (name, value) in fields {
+        match name.split_once('.') {
+            Some((key, inner)) => {
+                let object = map
+                    .entry(key.to_string())
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(object) = object {
+                    object.insert(inner.to_string(), value);
+                }
+            }
+            None => {
+                map.insert(name, value);
+            }
+        }
+    }
+    Value::Object(map)
+}
+
+/// 参与逐条同步的实体
+trait Synced: Entity {
+    /// 记录在设备目录下所在的子目录
+    const DIR: &'static str;
+    /// 用于提示信息的名称
+    const NAME: &'static str;
+
+    fn id(&self) -> String {
+        String::from_utf8_lossy(&self.id_bytes()).to_string()
+    }
+
+    /// 转换为记录中的字段
+    fn fields(&self, _data_dir: &Path) -> BTreeMap<String, Value> {
+        flatten(serde_json::to_value(self).unwrap_or_default())
+    }
+
+    /// 由合并后的字段还原实体
+    fn from_fields(fields: BTreeMap<String, Value>, _data_dir: &Path) -> Result<Self> {
+        Ok(serde_json::from_value(unflatten(fields))?)
+    }
+
+    /// 删除其他设备上已删除的实体
+    fn remove(storage: &Storage, id: &str) -> Result<()> {
+        Repository::<Self>::delete(storage, id)
+    }
+}
+
+impl Synced for List {
+    const DIR: &'static str = "lists";
+    const NAME: &'static str = "列表";
+}
+
+impl Synced for Tag {
+    const DIR: &'static str = "tags";
+    const NAME: &'static str = "标签";
+}
+
+impl Synced for Event {
+    const DIR: &'static str = "events";
+    const NAME: &'static str = "事件";
+
+    // 内容文件的路径因设备而异，记录中保存相对数据目录的路径
+    fn fields(&self, data_dir: &Path) -> BTreeMap<String, Value> {
+        let mut fields = flatten(serde_json::to_value(self).unwrap_or_default());
+        if let Ok(relative) = Path::new(&self.content).strip_prefix(data_dir) {
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            fields.insert("content".to_string(), Value::String(relative));
+        }
+        fields
+    }
+
+    fn from_fields(fields: BTreeMap<String, Value>, data_dir: &Path) -> Result<Self> {
+        let mut event: Event = serde_json::from_value(unflatten(fields))?;
+        let content = PathBuf::from(&event.content);
+        // 内容文件可能尚未同步到本机，读取时按空内容处理
+        if content.is_relative() {
+            event.content = data_dir.join(content).to_string_lossy().to_string();
+        }
+        Ok(event)
+    }
+
+    fn remove(storage: &Storage, id: &str) -> Result<()> {
+        remove_event(storage, id)
+    }
+}
+
+// 读取目录中的全部记录，目录不存在时为空，损坏的记录跳过
+fn read_records(dir: &Path) -> Result<HashMap<String, Record>> {
+    let mut records = HashMap::new();
+    if !dir.is_dir() {
+        return Ok(records);
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let Some(id) = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        match fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Ok(serde_json::from_str::<Record>(&text)?))
+        {
+            Ok(record) => {
+                records.insert(id, record);
+            }
+            Err(e) => log::warn!("跳过无法读取的同步记录 {}: {}", path.display(), e),
+        }
+    }
+    Ok(records)
+}
+
+// 先写入临时文件再替换，避免同步时读到不完整的记录
+fn write_record(dir: &Path, id: &str, record: &Record) -> Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}.json", id));
+    let temp = dir.join(format!("{}.json.tmp", id));
+    fs::write(&temp, serde_json::to_vec_pretty(record)?)?;
+    fs::rename(&temp, &path)?;
+    Ok(())
+}
+
+/// 追加一次仓库写入的时间到修改日志，每行为 `{DIR}/{id} {毫秒时间戳}`
+///
+/// 导出时以日志中的时间作为变化字段的修改时间，合并因此按修改发生的先后
+/// 而不是同步的先后取值。
+pub fn touch(log: &Path, change: &Change, time: u64) -> Result<()> {
+    let dir = match change.entity {
+        EntityKind::Event => Event::DIR,
+        EntityKind::List => List::DIR,
+        EntityKind::Tag => Tag::DIR,
+        EntityKind::Embedding | EntityKind::Subscription => return Ok(()),
+    };
+    let mut file = OpenOptions::new().create(true).append(true).open(log)?;
+    writeln!(file, "{}/{} {}", dir, change.id, time)?;
+    Ok(())
+}
+
+// 读取修改日志，同一实体取最后一行的时间
+fn read_times(log: &Path) -> HashMap<String, u64> {
+    fs::read_to_string(log)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let (key, time) = line.rsplit_once(' ')?;
+            Some((key.to_string(), time.parse().ok()?))
+        })
+        .collect()
+}
+
+// 将数据库中的新增、修改与删除写入本设备的记录，没有修改时间的变化记为 `now`
+fn export<T: Synced>(
+    storage: &Storage,
+    root: &Path,
+    data_dir: &Path,
+    device: &str,
+    times: &HashMap<String, u64>,
+    now: u64,
+) -> Result<()> {
+    let dir = root.join(device).join(T::DIR);
+    let stamp = |id: &str| Stamp {
+        time: times
+            .get(&format!("{}/{}", T::DIR, id))
+            .copied()
+            .unwrap_or(now),
+        device: device.to_string(),
+    };
+    let mut records = read_records(&dir)?;
+    let mut existing = HashSet::new();
+    for entity in Repository::<T>::get_all(storage)? {
+        let id = entity.id();
+        let record = records.entry(id.clone()).or_default();
+        if record.update(entity.fields(data_dir), &stamp(&id)) {
+            write_record(&dir, &id, record)?;
+        }
+        existing.insert(id);
+    }
+    for (id, record) in records.iter_mut() {
+        if !existing.contains(id) && !record.is_deleted() {
+            record.deleted = Some(stamp(id));
+            write_record(&dir, id, record)?;
+        }
+    }
+    Ok(())
+}
+
+// 合并所有设备的记录，写回本设备的记录并将结果应用到数据库，返回变化的实体数
+fn merge<T: Synced>(
+    storage: &Storage,
+    root: &Path,
+    data_dir: &Path,
+    device: &str,
+) -> Result<usize> {
+    let own_dir = root.join(device).join(T::DIR);
+    let own = read_records(&own_dir)?;
+    let mut merged = own.clone();
+    if root.is_dir() {
+        for entry in fs::read_dir(root)? {
+            let path = entry?.path();
+            if !path.is_dir() || path.file_name() == Some(OsStr::new(device)) {
+                continue;
+            }
+            for (id, record) in read_records(&path.join(T::DIR))? {
+                merged.entry(id).or_default().merge(&record);
+            }
+        }
+    }
+
+    // 删除过多时不做任何改动，避免其他设备重置数据后清空本机
+    let existing: HashSet<String> = Repository::<T>::get_all(storage)?
+        .iter()
+        .map(|entity| entity.id())
+        .collect();
+    let deletions = merged
+        .iter()
+        .filter(|(id, record)| record.is_deleted() && existing.contains(*id))
+        .count();
+    if deletions > MIN_DELETE_CHECK && deletions as f64 > existing.len() as f64 * MAX_DELETE_RATIO {
+        bail!(
+            "同步记录将删除 {}/{} 个{}，超过安全阈值，已停止合并",
+            deletions,
+            existing.len(),
+            T::NAME
+        );
+    }
+
+    let mut applied = 0;
+    for (id, record) in &merged {
+        if own.get(id) != Some(record) {
+            write_record(&own_dir, id, record)?;
+        }
+        let current = Repository::<T>::get_by_name(storage, id)?;
+        if record.is_deleted() {
+            if current.is_some() {
+                T::remove(storage, id)?;
+                applied += 1;
+            }
+            continue;
+        }
+        let fields = record.values();
+        if current.map(|entity| entity.fields(data_dir)).as_ref() == Some(&fields) {
+            continue;
+        }
+        match T::from_fields(fields, data_dir) {
+            Ok(entity) => {
+                Repository::<T>::add(storage, &entity)?;
+                applied += 1;
+            }
+            Err(e) => log::warn!("无法还原同步记录 {}/{}: {}", T::DIR, id, e),
+        }
+    }
+    Ok(applied)
+}
+
+/// 将数据库中的事件、列表与标签导出为本设备的同步记录
+///
+/// 与上次导出的记录逐字段比较，值有变化的字段记为本设备上的修改，修改时间取自
+/// 修改日志 `log`，日志中没有的（如升级前的修改）记为 `now`；数据库中已不存在的
+/// 实体记为删除。导出完成后清空日志。
+pub fn export_records(
+    storage: &Storage,
+    data_dir: &Path,
+    device: &str,
+    log: &Path,
+    now: u64,
+) -> Result<()> {
+    let root = data_dir.join(RECORD_DIR);
+    let times = read_times(log);
+    export::<List>(storage, &root, data_dir, device, &times, now)?;
+    export::<Tag>(storage, &root, data_dir, device, &times, now)?;
+    export::<Event>(storage, &root, data_dir, device, &times, now)?;
+    match fs::remove_file(log) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// 合并所有设备的同步记录，并通过仓库写入数据库
+///
+/// 每个字段取最后一次修改的值；删除晚于记录所有字段的修改时删除实体，
+/// 将删除的实体过多时返回错误且不改动该类实体。返回新增、修改或删除的实体数。
+pub fn merge_records(storage: &Storage, data_dir: &Path, device: &str) -> Result<usize> {
+    let root = data_dir.join(RECORD_DIR);
+    Ok(merge::<List>(storage, &root, data_dir, device)?
+        + merge::<Tag>(storage, &root, data_dir, device)?
+        + merge::<Event>(storage, &root, data_dir, device)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::storage_in;
+
+    // 模拟文件同步：将一台设备的记录复制到另一台
+    fn copy_records(from: &Path, to: &Path, device: &str) {
+        for kind in ["lists", "tags", "events"] {
+            let source = from.join(RECORD_DIR).join(device).join(kind);
+            let target = to.join(RECORD_DIR).join(device).join(kind);
+            fs::create_dir_all(&target).unwrap();
+            for entry in fs::read_dir(&source).into_iter().flatten() {
+                let entry = entry.unwrap();
+                fs::copy(entry.path(), target.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    fn stamp(time: u64, device: &str) -> Stamp {
+        Stamp {
+            time,
+            device: device.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_merge() {
+        let mut laptop = Record::default();
+        laptop.update(
+            BTreeMap::from([
+                ("title".to_string(), Value::from("Laptop")),
+                ("icon".to_string(), Value::from("a")),
+            ]),
+            &stamp(1, "laptop"),
+        );
+        let mut desktop = laptop.clone();
+        desktop.update(
+            BTreeMap::from([
+                ("title".to_string(), Value::from("Laptop")),
+                ("icon".to_string(), Value::from("b")),
+            ]),
+            &stamp(2, "desktop"),
+        );
+        laptop.update(
+            BTreeMap::from([
+                ("title".to_string(), Value::from("Renamed")),
+                ("icon".to_string(), Value::from("a")),
+            ]),
+            &stamp(3, "laptop"),
+        );
+
+        // 两端修改的不同字段都保留
+        laptop.merge(&desktop);
+        assert_eq!(laptop.fields["title"].value, "Renamed");
+        assert_eq!(laptop.fields["icon"].value, "b");
+
+        // 删除之后的修改使记录保留
+        laptop.deleted = Some(stamp(2, "desktop"));
+        assert!(!laptop.is_deleted());
+        laptop.deleted = Some(stamp(4, "desktop"));
+        assert!(laptop.is_deleted());
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut event = Event::new("Title", "/data/Title/Title.md");
+        event.metadata.list = Some("list".to_string());
+        let fields = flatten(serde_json::to_value(&event).unwrap());
+        assert_eq!(fields["metadata.list"], "list");
+        assert_eq!(fields["title"], "Title");
+
+        let restored: Event = serde_json::from_value(unflatten(fields)).unwrap();
+        assert_eq!(restored.metadata.uuid, event.metadata.uuid);
+        assert_eq!(restored.metadata.list, event.metadata.list);
+    }
+
+    #[test]
+    fn test_export_and_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let laptop_dir = dir.path().join("laptop");
+        let desktop_dir = dir.path().join("desktop");
+        let laptop = storage_in(&laptop_dir);
+        let desktop = storage_in(&desktop_dir);
+        let no_log = dir.path().join("modified.log");
+
+        let list = List::new("Work", "briefcase");
+        Repository::<List>::add(&laptop, &list).unwrap();
+        let content = laptop_dir.join("Report").join("Report.md");
+        let mut event = Event::new("Report", &content.to_string_lossy());
+        event.metadata.list = Some(list.uuid.clone());
+        Repository::<Event>::add(&laptop, &event).unwrap();
+        export_records(&laptop, &laptop_dir, "laptop", &no_log, 1).unwrap();
+
+        copy_records(&laptop_dir, &desktop_dir, "laptop");
+        assert_eq!(merge_records(&desktop, &desktop_dir, "desktop").unwrap(), 2);
+        let synced = Repository::<Event>::get_by_name(&desktop, &event.metadata.uuid)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            PathBuf::from(&synced.content),
+            desktop_dir.join("Report").join("Report.md")
+        );
+        // 内容文件随文件同步到达，合并时不创建
+        assert!(!Path::new(&synced.content).exists());
+        assert_eq!(merge_records(&desktop, &desktop_dir, "desktop").unwrap(), 0);
+
+        // 两台设备分别修改同一事件的不同字段
+        Repository::<Event>::update(&laptop, &event.metadata.uuid, |event| {
+            event.title = "Quarterly report".to_string();
+            Ok(())
+        })
+        .unwrap();
+        export_records(&laptop, &laptop_dir, "laptop", &no_log, 2).unwrap();
+        Repository::<Event>::update(&desktop, &event.metadata.uuid, |event| {
+            event.finished = true;
+            Ok(())
+        })
+        .unwrap();
+        export_records(&desktop, &desktop_dir, "desktop", &no_log, 3).unwrap();
+
+        copy_records(&laptop_dir, &desktop_dir, "laptop");
+        copy_records(&desktop_dir, &laptop_dir, "desktop");
+        merge_records(&laptop, &laptop_dir, "laptop").unwrap();
+        merge_records(&desktop, &desktop_dir, "desktop").unwrap();
+        for storage in [&laptop, &desktop] {
+            let merged = Repository::<Event>::get_by_name(storage, &event.metadata.uuid)
+                .unwrap()
+                .unwrap();
+            assert_eq!(merged.title, "Quarterly report");
+            assert!(merged.finished);
+        }
+
+        // 删除同步到另一台设备
+        Repository::<List>::delete(&desktop, &list.uuid).unwrap();
+        export_records(&desktop, &desktop_dir, "desktop", &no_log, 4).unwrap();
+        copy_records(&desktop_dir, &laptop_dir, "desktop");
+        merge_records(&laptop, &laptop_dir, "laptop").unwrap();
+        assert!(Repository::<List>::get_by_name(&laptop, &list.uuid)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_modification_time_wins() {
+        use crate::utils::bus::ChangeKind;
+
+        let dir = tempfile::tempdir().unwrap();
+        let laptop_dir = dir.path().join("laptop");
+        let desktop_dir = dir.path().join("desktop");
+        let laptop = storage_in(&laptop_dir);
+        let desktop = storage_in(&desktop_dir);
+        let laptop_log = dir.path().join("laptop.log");
+        let desktop_log = dir.path().join("desktop.log");
+
+        let list = List::new("Work", "briefcase");
+        Repository::<List>::add(&laptop, &list).unwrap();
+        export_records(&laptop, &laptop_dir, "laptop", &laptop_log, 1).unwrap();
+        copy_records(&laptop_dir, &desktop_dir, "laptop");
+        merge_records(&desktop, &desktop_dir, "desktop").unwrap();
+
+        // 笔记本先修改但后同步，台式机后修改的标题应保留
+        let rename = |storage: &Storage, log: &Path, title: &str, time: u64| {
+            Repository::<List>::update(storage, &list.uuid, |list| {
+                list.title = title.to_string();
+                Ok(())
+            })
+            .unwrap();
+            let change = Change {
+                entity: EntityKind::List,
+                kind: ChangeKind::Updated,
+                id: list.uuid.clone(),
+                scopes: Vec::new(),
+            };
+            touch(log, &change, time).unwrap();
+        };
+        rename(&laptop, &laptop_log, "Laptop", 10);
+        rename(&desktop, &desktop_log, "Desktop", 20);
+        export_records(&desktop, &desktop_dir, "desktop", &desktop_log, 30).unwrap();
+        export_records(&laptop, &laptop_dir, "laptop", &laptop_log, 40).unwrap();
+        assert!(!laptop_log.exists(), "导出后清空修改日志");
+
+        copy_records(&desktop_dir, &laptop_dir, "desktop");
+        merge_records(&laptop, &laptop_dir, "laptop").unwrap();
+        let merged = Repository::<List>::get_by_name(&laptop, &list.uuid)
+            .unwrap()
+            .unwrap();
+        assert_eq!(merged.title, "Desktop");
+    }
+
+    #[test]
+    fn test_merge_refuses_mass_deletion() {
+        let dir = tempfile::tempdir().unwrap();
+        let laptop_dir = dir.path().join("laptop");
+        let desktop_dir = dir.path().join("desktop");
+        let laptop = storage_in(&laptop_dir);
+        let desktop = storage_in(&desktop_dir);
+        let no_log = dir.path().join("modified.log");
+
+        let lists: Vec<List> = (0..12)
+            .map(|i| List::new(&format!("List {}", i), "folder"))
+            .collect();
+        for list in &lists {
+            Repository::<List>::add(&laptop, list).unwrap();
+        }
+        export_records(&laptop, &laptop_dir, "laptop", &no_log, 1).unwrap();
+        copy_records(&laptop_dir, &desktop_dir, "laptop");
+        merge_records(&desktop, &desktop_dir, "desktop").unwrap();
+
+        // 台式机的数据被清空后，笔记本不应跟着删除
+        for list in &lists {
+            Repository::<List>::delete(&desktop, &list.uuid).unwrap();
+        }
+        export_records(&desktop, &desktop_dir, "desktop", &no_log, 2).unwrap();
+        copy_records(&desktop_dir, &laptop_dir, "desktop");
+        let err = merge_records(&laptop, &laptop_dir, "laptop").unwrap_err();
+        assert!(err.to_string().contains("12/12"));
+        assert_eq!(Repository::<List>::get_all(&laptop).unwrap().len(), 12);
+    }
+}
@@ -1,5 +1,7 @@
 use crate::entity::{App, Storage, StorageState};
-use crate::function::{notify, sync};
+use crate::function::aigc::search;
+use crate::function::{backup, notify, subscribe, sync};
+use crate::utils::bus;
 use crate::utils::config;
 use crate::utils::logs;
 use crate::utils::manager::tasker;
@@ -30,9 +32,24 @@ pub async fn initialize_app(app: &tauri::App) -> Result<()> {
     let storage = Storage::new()?;
     app.manage(StorageState(Mutex::new(storage), Mutex::new(app_instance)));
 
+    // 将数据变更通知转发给前端
+    bus::forward_to_windows(app.handle().clone());
+
+    // 记录修改时间，同步时按修改的先后合并各设备的记录
+    sync::track_changes();
+
+    // 记录有变更的事件，语义搜索前只重新计算这些事件的向量
+    search::track_changes();
+
     // 注册同步功能命令
     sync::register_sync_commands(app)?;
 
+    // 设置每日自动备份
+    backup::setup(app.handle().clone()).await;
+
+    // 定时刷新订阅日历
+    subscribe::setup(app.handle().clone()).await;
+
     // 解析配置
     if let Err(e) = crate::utils::config::parse() {
         eprintln!("Error parsing config: {}", e);
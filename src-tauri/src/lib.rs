@@ -7,8 +7,8 @@ mod utils; // 通用工具函数
 #[cfg(test)]
 mod test;
 
-use entity::{event, list, tag};
-use function::{export, sync, upload, aigc};
+use entity::{dependency, event, list, tag};
+use function::{aigc, backup, caldav, export, import, sync, upload, quick_add, subscribe, suggest};
 use tauri_plugin_dialog;
 use utils::config;
 
@@ -50,6 +50,9 @@ pub fn run() -> std::io::Result<()> {
             event::update_event,
             event::delete_event,
             event::filter_events,
+            dependency::add_blocker,
+            dependency::remove_blocker,
+            dependency::event_blockers,
             list::new_list,
             list::get_lists,
             list::delete_list,
@@ -59,22 +62,54 @@ pub fn run() -> std::io::Result<()> {
             tag::get_tags,
             tag::delete_tag,
             tag::tag_content,
+            export::export,
+            export::list_export_formats,
             export::export_events,
             export::export_list_events,
             export::export_all_events,
             export::export_events_by_status,
             export::export_events_by_date_range,
+            export::export_csv,
+            export::export_html_site,
             export::save::get_export_directory,
             export::save::save_export_file,
             export::save::select_save_path,
+            export::template::list_export_templates,
+            import::import_events,
+            import::import_json,
+            import::import_markdown,
+            import::import_todotxt,
+            import::import_org,
+            import::preview_csv_import,
+            import::import_csv,
+            backup::create_backup,
+            backup::restore_backup,
             sync::test_webdav_connection,
             sync::sync_now,
             sync::get_sync_status,
+            caldav::caldav_sync,
+            caldav::test_caldav_connection,
+            subscribe::add_subscription,
+            subscribe::get_subscriptions,
+            subscribe::delete_subscription,
+            subscribe::refresh_subscription,
             config::update_config,
             config::get_config,
             upload::upload_file,
             upload::save_remote_image,
-            aigc::receive_generated_tags,
+            quick_add::quick_add,
+            quick_add::commit_quick_add,
+            suggest::suggest_untagged_tags,
+            suggest::apply_tag_suggestions,
+            aigc::assist::breakdown_event,
+            aigc::assist::accept_subtasks,
+            aigc::assist::summarize_content,
+            aigc::assist::apply_summary,
+            aigc::planner::plan_my_day,
+            aigc::planner::accept_plan,
+            aigc::search::semantic_search,
+            aigc::search::similar_events,
+            utils::cache_diagnostics,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,18 +1,16 @@
+use super::temp_storage;
 use crate::entity::*;
 use std::ops::DerefMut;
 use std::time::Instant;
 use tauri::test::{mock_app, MockRuntime};
 use tauri::Manager;
-use tempfile::tempdir;
 use tokio::sync::Mutex;
 
 // 创建测试环境，返回存储状态和临时目录
 fn setup() -> (StorageState<MockRuntime>, tempfile::TempDir) {
-    let temp_dir = tempdir().unwrap();
-    let db = redb::Database::create(temp_dir.path().join("test.db")).unwrap();
+    let (storage, temp_dir) = temp_storage();
     let app = mock_app();
     let app_handle = app.app_handle();
-    let storage = Storage { db };
     let state = StorageState::<MockRuntime>(
         Mutex::new(storage),
         Mutex::new(App::<MockRuntime>::new(&app_handle)),
@@ -122,3 +120,42 @@ async fn test_performance() {
     println!("性能测试耗时: {:?}", duration);
     assert!(duration.as_secs() < 5, "性能测试应该在5秒内完成");
 }
+
+#[tokio::test]
+async fn test_snapshot_changes() {
+    use crate::utils::bus::{ChangeKind, EntityKind};
+
+    let (state, _temp_dir) = setup();
+    let storage = get_storage(&state).await;
+
+    let mut kept = List::new("Kept", "icon.png");
+    let removed = List::new("Removed", "icon.png");
+    Repository::<List>::add(&*storage, &kept).unwrap();
+    Repository::<List>::add(&*storage, &removed).unwrap();
+    let before = storage.snapshot().expect("记录快照失败");
+
+    // 模拟同步下载带来的变化
+    kept.title = "Renamed".to_string();
+    let created = List::new("Created", "icon.png");
+    Repository::<List>::add(&*storage, &kept).unwrap();
+    Repository::<List>::add(&*storage, &created).unwrap();
+    Repository::<List>::delete(&*storage, &removed.uuid).unwrap();
+    let after = storage.snapshot().expect("记录快照失败");
+
+    let mut changes: Vec<(String, ChangeKind)> = before
+        .changes(&after)
+        .into_iter()
+        .inspect(|change| assert_eq!(change.entity, EntityKind::List))
+        .map(|change| (change.id, change.kind))
+        .collect();
+    changes.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut expected = vec![
+        (kept.uuid.clone(), ChangeKind::Updated),
+        (created.uuid.clone(), ChangeKind::Created),
+        (removed.uuid.clone(), ChangeKind::Deleted),
+    ];
+    expected.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(changes, expected);
+
+    assert!(before.changes(&before).is_empty(), "相同快照不应有变化");
+}
@@ -1,3 +1,30 @@
 mod debug;
 mod entity_test;
-mod config_test;
\ No newline at end of file
+mod config_test;
+
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+use crate::entity::{Entity, Event, List, Storage, Tag};
+
+/// 在临时目录中创建测试用的存储，返回的目录释放时删除数据库文件
+pub(crate) fn temp_storage() -> (Storage, TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = storage_in(dir.path());
+    (storage, dir)
+}
+
+/// 在指定目录中创建测试用的存储，并建好事件、列表与标签表
+pub(crate) fn storage_in(dir: &Path) -> Storage {
+    fs::create_dir_all(dir).unwrap();
+    let storage = Storage {
+        db: redb::Database::create(dir.join("test.db")).unwrap(),
+    };
+    let txn = storage.db.begin_write().unwrap();
+    txn.open_table(Event::table_def()).unwrap();
+    txn.open_table(List::table_def()).unwrap();
+    txn.open_table(Tag::table_def()).unwrap();
+    txn.commit().unwrap();
+    storage
+}
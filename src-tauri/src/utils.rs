@@ -1,3 +1,4 @@
+pub mod bus;
 mod cache;
 pub mod config;
 pub mod logs;
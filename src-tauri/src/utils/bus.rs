@@ -0,0 +1,71 @@
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use ts_rs::TS;
+
+use super::cache;
+
+/// 发生变化的实体类型
+#[derive(Serialize, Deserialize, TS, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[ts(export)]
+pub enum EntityKind {
+    Event,
+    List,
+    Tag,
+    Embedding,
+    Subscription,
+}
+
+/// 变化类型
+#[derive(Serialize, Deserialize, TS, Clone, Copy, Debug, PartialEq, Eq)]
+#[ts(export)]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// 仓库写入后发布的变更通知
+#[derive(Serialize, Deserialize, TS, Clone, Debug, PartialEq)]
+#[ts(export)]
+pub struct Change {
+    pub entity: EntityKind,
+    pub kind: ChangeKind,
+    pub id: String,
+    /// 变更前后实体所属的分组（事件所属的列表ID），用于按分组失效缓存
+    pub scopes: Vec<String>,
+}
+
+/// 前端监听变更通知的事件名
+pub const CHANGE_EVENT: &str = "entity-changed";
+
+type Subscriber = Box<dyn Fn(&Change) + Send + Sync>;
+
+// 缓存失效始终订阅，其余订阅者在运行时注册
+static SUBSCRIBERS: Lazy<RwLock<Vec<Subscriber>>> =
+    Lazy::new(|| RwLock::new(vec![Box::new(cache::invalidate) as Subscriber]));
+
+/// 订阅所有仓库写入
+pub fn subscribe(subscriber: impl Fn(&Change) + Send + Sync + 'static) {
+    SUBSCRIBERS.write().push(Box::new(subscriber));
+}
+
+/// 向所有订阅者发布变更，由 `Repository` 在写入提交后调用
+pub fn publish(change: &Change) {
+    for subscriber in SUBSCRIBERS.read().iter() {
+        subscriber(change);
+    }
+}
+
+/// 将事件、列表与标签的变更转发给所有窗口，向量等内部数据不转发
+pub fn forward_to_windows(app: AppHandle) {
+    subscribe(move |change| {
+        if change.entity == EntityKind::Embedding {
+            return;
+        }
+        if let Err(e) = app.emit(CHANGE_EVENT, change) {
+            log::warn!("Failed to emit change of {}: {}", change.id, e);
+        }
+    });
+}
@@ -1,45 +1,82 @@
 use once_cell::sync::Lazy;
+use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+use super::bus::{Change, EntityKind};
 
 // 缓存条目
 struct CacheEntry<T> {
     value: T,
     timestamp: Instant,
+    last_used: u64,
 }
 
-// 通用缓存实现
+// 通用缓存实现，条目超过TTL后失效，超过容量时淘汰最久未使用的条目
 struct Cache<T: Clone> {
     data: HashMap<String, CacheEntry<T>>,
     ttl: Duration,
+    capacity: usize,
+    tick: u64,
 }
 
 impl<T: Clone> Cache<T> {
-    fn new(ttl_seconds: u64) -> Self {
+    fn new(ttl_seconds: u64, capacity: usize) -> Self {
         Cache {
             data: HashMap::new(),
             ttl: Duration::from_secs(ttl_seconds),
+            capacity,
+            tick: 0,
         }
     }
 
-    fn get(&self, key: &str) -> Option<T> {
-        if let Some(entry) = self.data.get(key) {
-            if entry.timestamp.elapsed() < self.ttl {
-                return Some(entry.value.clone());
+    fn get(&mut self, key: &str) -> Option<T> {
+        self.tick += 1;
+        let tick = self.tick;
+        let ttl = self.ttl;
+        match self.data.get_mut(key) {
+            Some(entry) if entry.timestamp.elapsed() < ttl => {
+                entry.last_used = tick;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                self.data.remove(key);
+                None
             }
+            None => None,
         }
-        None
     }
 
-    fn set(&mut self, key: &str, value: T) {
+    // 返回被淘汰的条目数
+    fn set(&mut self, key: &str, value: T) -> u64 {
+        self.tick += 1;
         self.data.insert(
             key.to_string(),
             CacheEntry {
                 value,
                 timestamp: Instant::now(),
+                last_used: self.tick,
             },
         );
+        let mut evicted = 0;
+        while self.data.len() > self.capacity {
+            let oldest = self
+                .data
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            match oldest {
+                Some(oldest) => {
+                    self.data.remove(&oldest);
+                    evicted += 1;
+                }
+                None => break,
+            }
+        }
+        evicted
     }
 
     fn remove(&mut self, key: &str) {
@@ -51,26 +88,57 @@ impl<T: Clone> Cache<T> {
     }
 }
 
+/// 单个缓存的统计信息
+#[derive(Serialize, TS, Clone)]
+#[ts(export)]
+pub struct CacheStats {
+    pub name: String,
+    pub size: u32,
+    pub capacity: u32,
+    #[ts(type = "number")]
+    pub hits: u64,
+    #[ts(type = "number")]
+    pub misses: u64,
+    #[ts(type = "number")]
+    pub evictions: u64,
+}
+
 // 线程安全的缓存包装
 pub struct SafeCache<T: Clone + Send + 'static> {
+    name: &'static str,
     cache: Arc<Mutex<Cache<T>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
 }
 
 impl<T: Clone + Send + 'static> SafeCache<T> {
-    fn new(ttl_seconds: u64) -> Self {
+    fn new(name: &'static str, ttl_seconds: u64, capacity: usize) -> Self {
         SafeCache {
-            cache: Arc::new(Mutex::new(Cache::new(ttl_seconds))),
+            name,
+            cache: Arc::new(Mutex::new(Cache::new(ttl_seconds, capacity))),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
         }
     }
 
     pub fn get(&self, key: &str) -> Option<T> {
-        let cache = self.cache.lock().unwrap();
-        cache.get(key)
+        let mut cache = self.cache.lock().unwrap();
+        let value = cache.get(key);
+        let counter = if value.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
     }
 
     pub fn set(&self, key: &str, value: T) {
         let mut cache = self.cache.lock().unwrap();
-        cache.set(key, value);
+        let evicted = cache.set(key, value);
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
     }
 
     pub fn remove(&self, key: &str) {
@@ -82,27 +150,114 @@ impl<T: Clone + Send + 'static> SafeCache<T> {
         let mut cache = self.cache.lock().unwrap();
         cache.clear();
     }
+
+    pub fn stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap();
+        CacheStats {
+            name: self.name.to_string(),
+            size: cache.data.len() as u32,
+            capacity: cache.capacity as u32,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
 }
 
 // 事件缓存类型定义
-#[allow(dead_code)]
 pub type EventCache = SafeCache<crate::entity::event::FEvent>;
 pub type EventListCache = SafeCache<Vec<crate::entity::event::FEvent>>;
 pub type EventContentCache = SafeCache<String>;
 pub type ListCache = SafeCache<Vec<crate::entity::list::FList>>;
+pub type TagModelCache = SafeCache<crate::function::suggest::TagSuggester>;
 
 // 全局缓存实例
 #[allow(dead_code)]
-pub static EVENT_CACHE: Lazy<EventCache> = Lazy::new(|| SafeCache::new(300)); // 单个事件缓存
-pub static EVENT_LIST_CACHE: Lazy<EventListCache> = Lazy::new(|| SafeCache::new(300)); // 事件列表缓存
-pub static EVENT_CONTENT_CACHE: Lazy<EventContentCache> = Lazy::new(|| SafeCache::new(300)); // 事件内容缓存
-pub static LIST_CACHE: Lazy<ListCache> = Lazy::new(|| SafeCache::new(300)); // 列表缓存
+pub static EVENT_CACHE: Lazy<EventCache> = Lazy::new(|| SafeCache::new("event", 300, 1000)); // 单个事件缓存，按事件ID
+pub static EVENT_LIST_CACHE: Lazy<EventListCache> =
+    Lazy::new(|| SafeCache::new("event_list", 300, 64)); // 事件列表缓存，按列表ID
+pub static EVENT_CONTENT_CACHE: Lazy<EventContentCache> =
+    Lazy::new(|| SafeCache::new("event_content", 300, 200)); // 事件内容缓存，按事件ID
+pub static LIST_CACHE: Lazy<ListCache> = Lazy::new(|| SafeCache::new("list", 300, 1)); // 列表缓存
+pub static TAG_MODEL_CACHE: Lazy<TagModelCache> =
+    Lazy::new(|| SafeCache::new("tag_model", 300, 1)); // 本地标签模型缓存
+
+/// 根据仓库变更使相关缓存失效，订阅于变更总线
+pub(super) fn invalidate(change: &Change) {
+    match change.entity {
+        EntityKind::Event => {
+            EVENT_CACHE.remove(&change.id);
+            // 导入与同步会直接改写内容文件后发布更新，内容缓存随之失效
+            EVENT_CONTENT_CACHE.remove(&change.id);
+            for list_id in &change.scopes {
+                EVENT_LIST_CACHE.remove(list_id);
+            }
+        }
+        EntityKind::List => {
+            LIST_CACHE.clear();
+            EVENT_LIST_CACHE.remove(&change.id);
+        }
+        EntityKind::Tag => {
+            TAG_MODEL_CACHE.clear();
+            EVENT_LIST_CACHE.clear();
+        }
+        EntityKind::Embedding | EntityKind::Subscription => {}
+    }
+}
 
 // 清除所有缓存
-#[allow(dead_code)]
 pub fn clear_all_caches() {
     EVENT_CACHE.clear();
     EVENT_LIST_CACHE.clear();
     EVENT_CONTENT_CACHE.clear();
     LIST_CACHE.clear();
+    TAG_MODEL_CACHE.clear();
+}
+
+/// Returns size, capacity and hit/miss statistics of every cache
+///
+/// # Returns
+/// * `Vec<CacheStats>` - One entry per cache
+#[tauri::command]
+pub fn cache_diagnostics() -> Vec<CacheStats> {
+    vec![
+        EVENT_CACHE.stats(),
+        EVENT_LIST_CACHE.stats(),
+        EVENT_CONTENT_CACHE.stats(),
+        LIST_CACHE.stats(),
+        TAG_MODEL_CACHE.stats(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache: SafeCache<u32> = SafeCache::new("test", 300, 2);
+        cache.set("a", 1);
+        cache.set("b", 2);
+        // 访问 a 后 b 成为最久未使用的条目
+        assert_eq!(cache.get("a"), Some(1));
+        cache.set("c", 3);
+
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+
+        let stats = cache.stats();
+        assert_eq!(stats.size, 2);
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.evictions, 1);
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let cache: SafeCache<u32> = SafeCache::new("test", 0, 4);
+        cache.set("a", 1);
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.stats().size, 0);
+    }
 }
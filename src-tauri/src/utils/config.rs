@@ -23,12 +23,25 @@ switch = false
 name = "deepseek-v3"
 tokens = "4096"
 api = "https://api.deepseek.com/"
+embedding = "text-embedding-3-small"
 [webdav]
 enabled = false
 host = "https://example.com"
 username = "user"
 password = "password"
 remote_dir = "/ToDoPulse"
+conflict = "PreferLocal"
+[backup]
+switch = false
+time = "03:00"
+keep = 7
+dir = ""
+[caldav]
+enabled = false
+host = "https://example.com/calendars/user/"
+username = "user"
+password = "password"
+conflict = "PreferLocal"
 "#;
 
 // 解析DEFAULT_CONFIG以复用默认值
@@ -44,6 +57,8 @@ pub struct WebDav {
     pub username: String,
     pub password: String,
     pub remote_dir: String,
+    #[serde(default)]
+    pub conflict: String, // 两端都修改时的处理：PreferLocal、PreferRemote、KeepBoth 或 Skip
 }
 
 #[derive(Deserialize, Serialize, Clone, TS, F)]
@@ -57,6 +72,8 @@ pub struct Model {
     pub name: String,
     pub tokens: String,
     pub api: String,
+    #[serde(default)]
+    pub embedding: String, // 向量模型名称，用于语义搜索
 }
 
 #[derive(Deserialize, Serialize, Clone, TS, F)]
@@ -65,6 +82,23 @@ pub struct Info {
     pub time: Option<Vec<String>>,
 }
 
+#[derive(Deserialize, Serialize, Clone, TS, F)]
+pub struct Backup {
+    pub switch: bool,
+    pub time: String, // 每日自动备份的时间，格式为 "HH:MM"
+    pub keep: u32,    // 保留的自动备份数量
+    pub dir: String,  // 备份目录，为空时使用导出目录下的 backups
+}
+
+#[derive(Deserialize, Serialize, Clone, TS, F)]
+pub struct CalDav {
+    pub enabled: bool,
+    pub host: String, // 日历主集合的地址，各列表同步为其下的日历
+    pub username: String,
+    pub password: String,
+    pub conflict: String, // 两端都修改时的处理：PreferLocal、PreferRemote、KeepBoth 或 Skip
+}
+
 #[derive(Deserialize, Serialize, Clone, TS)]
 #[ts(export)]
 pub struct Config {
@@ -72,6 +106,21 @@ pub struct Config {
     pub info: Info,
     pub model: Model,
     pub webdav: WebDav,
+    #[serde(default)]
+    pub backup: Backup,
+    #[serde(default)]
+    pub caldav: CalDav,
+}
+
+impl Config {
+    /// 去除API密钥与WebDAV、CalDAV密码后的副本，用于备份
+    pub fn without_secrets(&self) -> Config {
+        let mut config = self.clone();
+        config.model.tokens.clear();
+        config.webdav.password.clear();
+        config.caldav.password.clear();
+        config
+    }
 }
 
 // Config也实现Default，使用各字段的Default实现
@@ -82,6 +131,8 @@ impl Default for Config {
             info: Info::default(),
             model: Model::default(),
             webdav: WebDav::default(),
+            backup: Backup::default(),
+            caldav: CalDav::default(),
         }
     }
 }
@@ -93,6 +144,8 @@ pub enum ConfigField {
     Info(Info),
     Model(Model),
     WebDav(WebDav),
+    Backup(Backup),
+    CalDav(CalDav),
 }
 
 impl ConfigField {
@@ -102,6 +155,8 @@ impl ConfigField {
             ConfigField::Info(info) => config.info = info.clone(),
             ConfigField::Model(model) => config.model = model.clone(),
             ConfigField::WebDav(webdav) => config.webdav = webdav.clone(),
+            ConfigField::Backup(backup) => config.backup = backup.clone(),
+            ConfigField::CalDav(caldav) => config.caldav = caldav.clone(),
         }
     }
 }
@@ -123,7 +178,7 @@ pub fn parse() -> Result<()> {
 
 /// Updates a specific section of the application configuration
 ///
-/// Modifies the specified configuration field (Theme, Info, Model, or WebDAV)
+/// Modifies the specified configuration field (Theme, Info, Model, WebDAV, Backup, or CalDAV)
 /// and writes the updated configuration to disk.
 ///
 /// # Parameters
@@ -178,6 +233,27 @@ pub fn get_config() -> Result<Config, ErrorKind> {
     Ok(config)
 }
 
+/// 用备份中的配置替换当前配置，备份时去除的密钥沿用当前的值
+pub fn restore(text: &str) -> Result<()> {
+    let mut restored: Config = toml::from_str(text)?;
+    if let Ok(current) = with_config(|config| config.clone()) {
+        if restored.model.tokens.is_empty() {
+            restored.model.tokens = current.model.tokens;
+        }
+        if restored.webdav.password.is_empty() {
+            restored.webdav.password = current.webdav.password;
+        }
+        if restored.caldav.password.is_empty() {
+            restored.caldav.password = current.caldav.password;
+        }
+    }
+    fs::write(
+        AppPaths::config_dir().join(CONFIG_FILE),
+        toml::to_string(&restored)?,
+    )?;
+    parse()
+}
+
 pub fn parse_with_path<P: AsRef<Path>>(custom_path: Option<P>) -> Result<()> {
     let config_path = match custom_path {
         Some(path) => {
@@ -238,6 +314,8 @@ fn merge_with_defaults(mut user_config: Config) -> (Config, bool) {
     user_config.info.fill_defaults_from(&default_config.info, &mut was_modified);
     user_config.model.fill_defaults_from(&default_config.model, &mut was_modified);
     user_config.webdav.fill_defaults_from(&default_config.webdav, &mut was_modified);
+    user_config.backup.fill_defaults_from(&default_config.backup, &mut was_modified);
+    user_config.caldav.fill_defaults_from(&default_config.caldav, &mut was_modified);
 
     (user_config, was_modified)
 }
@@ -0,0 +1,93 @@
+use super::tasker::Tasker;
+use async_trait::async_trait;
+use log::info;
+use std::sync::Arc;
+use tokio::pin;
+use tokio::sync::{oneshot, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+pub struct IntervalTask<F>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    name: String,
+    period: Duration,
+    func: Arc<F>,
+    stop_signal: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+impl<F> IntervalTask<F>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    /// 创建一个按固定间隔重复执行的任务，启动后立即执行一次
+    ///
+    /// # 参数
+    ///
+    /// * `name` - 任务名称
+    /// * `period` - 执行间隔
+    /// * `function` - 要执行的函数
+    pub fn new(name: impl Into<String>, period: Duration, function: F) -> Self {
+        IntervalTask {
+            name: name.into(),
+            period,
+            func: Arc::new(function),
+            stop_signal: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+#[async_trait]
+impl<F> Tasker for IntervalTask<F>
+where
+    F: Fn() + Send + Sync + 'static,
+{
+    fn start(&self) -> JoinHandle<()> {
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let stop_signal = self.stop_signal.clone();
+
+        tokio::spawn(async move {
+            let mut sender = stop_signal.lock().await;
+            *sender = Some(stop_tx);
+        });
+
+        let name = self.name.clone();
+        let period = self.period;
+        let f = self.func.clone();
+
+        tokio::spawn(async move {
+            info!("Started interval task: {}", name);
+
+            let mut ticker = interval(period);
+            // 休眠唤醒后只补执行一次
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            pin!(stop_rx);
+            loop {
+                tokio::select! {
+                    _ = &mut stop_rx => {
+                        info!("Stopping interval task: {}", name);
+                        break;
+                    }
+                    _ = ticker.tick() => f(),
+                }
+            }
+        })
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    async fn stop(&self) -> bool {
+        let mut stop_signal = self.stop_signal.lock().await;
+        if let Some(sender) = stop_signal.take() {
+            if sender.send(()).is_ok() {
+                info!("Interval task {} stopped successfully", self.name);
+                return true;
+            }
+        }
+        info!("Failed to stop interval task {}", self.name);
+        false
+    }
+}
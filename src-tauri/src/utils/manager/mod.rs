@@ -1,2 +1,3 @@
+pub mod interval_task;
 pub mod scheduled_task;
 pub mod tasker;
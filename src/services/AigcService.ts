@@ -1,28 +1,7 @@
 import { SettingService } from './SettingService';
 import OpenAI from 'openai';
-import { listen } from '@tauri-apps/api/event';
-import { invoke } from "@tauri-apps/api/core";
 
 class AigcService {
-    static {
-        // 监听来自 Rust 的标签生成请求
-        listen('aigc:generate-tags', async (event: { payload: [string, string[]] }) => {
-            try {
-                const [content, availableTags] = event.payload;
-                console.log('收到标签生成请求:', { content, availableTags });
-
-                const tags = await AigcService.generateTags(content, availableTags);
-                console.log('生成的标签:', tags);
-
-                if (tags) {
-                    // 调用 Rust 命令返回生成的标签
-                    await invoke('receive_generated_tags', { tags });
-                }
-            } catch (error) {
-                console.error('处理标签生成请求失败:', error);
-            }
-        });
-    }
     private static getOpenAIClient(apiKey: string, baseurl: string): OpenAI {
         return new OpenAI({
             baseURL: baseurl,
@@ -132,73 +111,6 @@ class AigcService {
             return null;
         }
     }
-
-    /**
-     * 调用AI API为内容生成标签
-     * @param content 原始内容
-     * @param availableTags 可选的标签列表
-     * @returns 生成的标签列表
-     */
-    static async generateTags(content: string, availableTags: string[]): Promise<string[] | null> {
-        try {
-            const settings = SettingService.getAigcSettings();
-            console.log('生成标签中', content, availableTags);
-            // 检查AI功能是否启用
-            if (!settings || !settings.switch) {
-                console.log('AI功能未启用');
-                return null;
-            }
-
-            // 获取API配置
-            const apiToken = settings.tokens;
-            const baseurl = settings.api;
-
-            if (!apiToken) {
-                console.error('未配置API Token');
-                return null;
-            }
-
-            const openai = this.getOpenAIClient(apiToken, baseurl);
-
-            // 构造标签生成提示词
-            const completion = await openai.chat.completions.create({
-                messages: [
-                    {
-                        role: "system",
-                        content: "你是一个专业的内容分类助手，善于为内容选择合适的标签。"
-                    },
-                    {
-                        role: "user",
-                        content: `
-            为以下内容选择合适的标签。请遵循以下规则：
-
-            1. 首先尝试从给出的标签列表中选择最相关的标签
-            2. 如果现有标签不足以准确描述内容的关键主题，可以创建新标签
-            3. 新标签应简洁（1-3个汉字，或者一个英文单词），具体且描述性强
-            4. 总共选择1-3个标签，优先使用现有标签
-            5. 标签应反映内容的核心主题、领域或性质，而非次要细节
-
-            
-            可选标签列表:
-            ${availableTags.join(',')}
-            
-            需要打标签的内容:
-            ${content}
-            
-            请只返回你选择的标签,多个标签之间用英文逗号分隔。`
-                    }
-                ],
-                model: "deepseek-chat",
-            });
-
-            const tags = completion.choices[0].message.content?.trim().split(',').filter(tag => tag.length > 0) || [];
-            return tags;
-
-        } catch (error) {
-            console.error('生成标签失败:', error);
-            return null;
-        }
-    }
 }
 
 export default AigcService;
\ No newline at end of file
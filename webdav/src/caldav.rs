@@ -0,0 +1,1324 @@
+use crate::model::ConflictStrategy;
+use crate::webdav::create_client;
+use crate::xml::{self, Element};
+use anyhow::{Result, anyhow};
+use log::{debug, warn};
+use reqwest::{Method, Response, header};
+use reqwest_dav::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+const XML_CONTENT: (&str, &str) = ("Content-Type", "application/xml; charset=utf-8");
+const ICS_CONTENT: (&str, &str) = ("Content-Type", "text/calendar; charset=utf-8");
+
+const CALENDAR_PROPS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:resourcetype/>
+    <d:displayname/>
+    <cs:getctag/>
+    <d:sync-token/>
+    <c:supported-calendar-component-set/>
+  </d:prop>
+</d:propfind>"#;
+
+const ETAG_PROPS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:">
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+</d:propfind>"#;
+
+const TODO_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VTODO"/>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#;
+
+fn sync_collection(token: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>{}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop>
+    <d:getetag/>
+  </d:prop>
+</d:sync-collection>"#,
+        xml::escape(token)
+    )
+}
+
+fn calendar_multiget(hrefs: &[String]) -> String {
+    let hrefs: String = hrefs
+        .iter()
+        .map(|href| format!("  <d:href>{}</d:href>\n", xml::escape(href)))
+        .collect();
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data/>
+  </d:prop>
+{}</c:calendar-multiget>"#,
+        hrefs
+    )
+}
+
+fn mkcalendar(name: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<c:mkcalendar xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:set>
+    <d:prop>
+      <d:displayname>{}</d:displayname>
+      <c:supported-calendar-component-set>
+        <c:comp name="VTODO"/>
+      </c:supported-calendar-component-set>
+    </d:prop>
+  </d:set>
+</c:mkcalendar>"#,
+        xml::escape(name)
+    )
+}
+
+/// 服务器上的日历集合
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    pub href: String,
+    pub name: String,
+    /// 集合标签，集合中任意资源变化时都会改变
+    pub ctag: Option<String>,
+    pub sync_token: Option<String>,
+    /// 是否可以存放 VTODO
+    pub todo: bool,
+}
+
+/// 日历中的一个资源
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resource {
+    pub href: String,
+    pub etag: String,
+    /// iCalendar 文本，只有 `fetch` 会填充
+    pub data: Option<String>,
+}
+
+/// 远程日历自上次同步以来的变化
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Changes {
+    /// 新增或修改的资源
+    pub changed: Vec<Resource>,
+    /// 已删除的资源
+    pub removed: Vec<String>,
+    /// `changed` 是完整列表，未出现在其中的已知资源都视为已删除
+    pub full: bool,
+    pub sync_token: Option<String>,
+}
+
+/// 条件写入的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum Written {
+    /// 写入成功，附带服务器返回的新 ETag
+    Done(Option<String>),
+    /// ETag 不匹配，资源已被其他客户端修改
+    Conflict,
+}
+
+/// multistatus 中的一条 response，`prop` 汇总了所有成功的 propstat
+struct DavResponse {
+    href: String,
+    status: u16,
+    prop: Element,
+}
+
+impl DavResponse {
+    fn prop(&self, name: &str) -> Option<String> {
+        self.prop
+            .child(name)
+            .map(|prop| prop.text().to_string())
+            .filter(|text| !text.is_empty())
+    }
+}
+
+fn status_code(line: &str) -> Option<u16> {
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn parse_multistatus(body: &str) -> Result<(Vec<DavResponse>, Option<String>)> {
+    let root = xml::parse(body)?;
+    if root.name != "multistatus" {
+        return Err(anyhow!("不是 multistatus 响应: <{}>", root.name));
+    }
+
+    let mut responses = Vec::new();
+    for response in root.children("response") {
+        let Some(href) = response.child("href") else {
+            continue;
+        };
+        let mut prop = Element::default();
+        for propstat in response.children("propstat") {
+            let ok = propstat
+                .child("status")
+                .and_then(|status| status_code(status.text()))
+                .is_none_or(|code| (200..300).contains(&code));
+            if let Some(found) = propstat.child("prop").filter(|_| ok) {
+                prop.children.extend(found.children.iter().cloned());
+            }
+        }
+        responses.push(DavResponse {
+            href: normalize_href(href.text()),
+            status: response
+                .child("status")
+                .and_then(|status| status_code(status.text()))
+                .unwrap_or(200),
+            prop,
+        });
+    }
+
+    let token = root
+        .child("sync-token")
+        .map(|token| token.text().to_string())
+        .filter(|token| !token.is_empty());
+    Ok((responses, token))
+}
+
+async fn multistatus(response: Response) -> Result<(Vec<DavResponse>, Option<String>)> {
+    let status = response.status();
+    let url = response.url().path().to_string();
+    if status.as_u16() != 207 {
+        return Err(anyhow!("{} 返回了意外的状态: {}", url, status));
+    }
+    parse_multistatus(&response.text().await?)
+}
+
+/// 规范化 href：去掉协议与主机部分并解码百分号编码
+pub fn normalize_href(href: &str) -> String {
+    let path = match href.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |i| &rest[i..]),
+        None => href,
+    };
+    urlencoding::decode(path)
+        .map(|path| path.into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn collection_href(href: &str) -> String {
+    format!("{}/", href.trim_end_matches('/'))
+}
+
+/// 为新事件生成的资源路径
+pub fn resource_href(calendar: &str, uid: &str) -> String {
+    let name: String = uid
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{}{}.ics", collection_href(calendar), name)
+}
+
+/// CalDAV 客户端，`home` 为日历主集合的路径
+pub struct CalDavClient {
+    client: Client,
+    home: String,
+}
+
+impl CalDavClient {
+    /// 连接到日历主集合，`url` 形如 `https://example.com/dav/calendars/user/`
+    pub async fn new(url: &str, username: &str, password: &str) -> Result<Self> {
+        let parsed = reqwest::Url::parse(url)?;
+        let origin = parsed.origin().ascii_serialization();
+        let client = create_client(&origin, username, password).await?;
+        Ok(Self {
+            client,
+            home: collection_href(&normalize_href(parsed.path())),
+        })
+    }
+
+    pub fn home(&self) -> &str {
+        &self.home
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        href: &str,
+        headers: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<Response> {
+        debug!("CalDAV 请求: {} {}", method, href);
+        let mut builder = self
+            .client
+            .start_request(Method::from_bytes(method.as_bytes())?, href)
+            .await?;
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+        Ok(builder.send().await?)
+    }
+
+    /// 列出主集合下的所有日历
+    pub async fn calendars(&self) -> Result<Vec<Calendar>> {
+        let response = self
+            .request(
+                "PROPFIND",
+                &self.home,
+                &[("Depth", "1"), XML_CONTENT],
+                Some(CALENDAR_PROPS.to_string()),
+            )
+            .await?;
+        let (responses, _) = multistatus(response).await?;
+
+        let calendars = responses
+            .into_iter()
+            .filter(|response| {
+                response
+                    .prop
+                    .child("resourcetype")
+                    .is_some_and(|kind| kind.child("calendar").is_some())
+            })
+            .map(|response| {
+                let href = collection_href(&response.href);
+                let name = response.prop("displayname").unwrap_or_else(|| {
+                    href.trim_end_matches('/')
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                });
+                // 没有声明支持的组件时，按 RFC 4791 视为支持全部组件
+                let todo = response
+                    .prop
+                    .child("supported-calendar-component-set")
+                    .is_none_or(|set| {
+                        set.children("comp")
+                            .any(|comp| comp.attr("name") == Some("VTODO"))
+                    });
+                Calendar {
+                    href,
+                    name,
+                    ctag: response.prop("getctag"),
+                    sync_token: response.prop("sync-token"),
+                    todo,
+                }
+            })
+            .collect();
+        Ok(calendars)
+    }
+
+    /// 在主集合下创建只存放 VTODO 的日历
+    pub async fn create_calendar(&self, slug: &str, name: &str) -> Result<Calendar> {
+        let href = collection_href(&format!("{}{}", self.home, slug));
+        let response = self
+            .request("MKCALENDAR", &href, &[XML_CONTENT], Some(mkcalendar(name)))
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("创建日历 {} 失败: {}", href, response.status()));
+        }
+        Ok(Calendar {
+            href,
+            name: name.to_string(),
+            ctag: None,
+            sync_token: None,
+            todo: true,
+        })
+    }
+
+    /// 查询日历自 `token` 以来的变化，没有 token 或 token 失效时返回完整列表
+    pub async fn changes(&self, calendar: &Calendar, token: Option<&str>) -> Result<Changes> {
+        if let Some(token) = token {
+            let response = self
+                .request(
+                    "REPORT",
+                    &calendar.href,
+                    &[("Depth", "0"), XML_CONTENT],
+                    Some(sync_collection(token)),
+                )
+                .await?;
+            match response.status().as_u16() {
+                207 => {
+                    let (responses, next) = multistatus(response).await?;
+                    let mut changes = Changes {
+                        sync_token: next.or_else(|| calendar.sync_token.clone()),
+                        ..Default::default()
+                    };
+                    for response in responses {
+                        if collection_href(&response.href) == calendar.href {
+                            continue;
+                        }
+                        if response.status == 404 {
+                            changes.removed.push(response.href);
+                        } else if let Some(etag) = response.prop("getetag") {
+                            changes.changed.push(Resource {
+                                href: response.href,
+                                etag,
+                                data: None,
+                            });
+                        }
+                    }
+                    return Ok(changes);
+                }
+                400 | 403 | 409 | 412 | 501 => {
+                    warn!(
+                        "日历 {} 的 sync-token 无法使用 ({}), 改为完整比较",
+                        calendar.href,
+                        response.status()
+                    );
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "查询日历 {} 的变化失败: {}",
+                        calendar.href,
+                        response.status()
+                    ));
+                }
+            }
+        }
+
+        let response = self
+            .request(
+                "REPORT",
+                &calendar.href,
+                &[("Depth", "1"), XML_CONTENT],
+                Some(TODO_QUERY.to_string()),
+            )
+            .await?;
+        let (responses, _) = multistatus(response).await?;
+        let changed = responses
+            .into_iter()
+            .filter(|response| collection_href(&response.href) != calendar.href)
+            .filter_map(|response| {
+                let etag = response.prop("getetag")?;
+                Some(Resource {
+                    href: response.href,
+                    etag,
+                    data: None,
+                })
+            })
+            .collect();
+        Ok(Changes {
+            changed,
+            removed: Vec::new(),
+            full: true,
+            sync_token: calendar.sync_token.clone(),
+        })
+    }
+
+    /// 批量下载资源的内容
+    pub async fn fetch(&self, calendar: &Calendar, hrefs: &[String]) -> Result<Vec<Resource>> {
+        if hrefs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let response = self
+            .request(
+                "REPORT",
+                &calendar.href,
+                &[("Depth", "1"), XML_CONTENT],
+                Some(calendar_multiget(hrefs)),
+            )
+            .await?;
+        let (responses, _) = multistatus(response).await?;
+        let resources = responses
+            .into_iter()
+            .filter(|response| (200..300).contains(&response.status))
+            .filter_map(|response| {
+                let etag = response.prop("getetag")?;
+                let data = response.prop("calendar-data")?;
+                Some(Resource {
+                    href: response.href,
+                    etag,
+                    data: Some(data),
+                })
+            })
+            .collect();
+        Ok(resources)
+    }
+
+    /// 查询单个资源当前的 ETag，资源不存在时返回 None
+    pub async fn etag(&self, href: &str) -> Result<Option<String>> {
+        let response = self
+            .request(
+                "PROPFIND",
+                href,
+                &[("Depth", "0"), XML_CONTENT],
+                Some(ETAG_PROPS.to_string()),
+            )
+            .await?;
+        if response.status().as_u16() == 404 {
+            return Ok(None);
+        }
+        let (responses, _) = multistatus(response).await?;
+        Ok(responses
+            .into_iter()
+            .next()
+            .and_then(|response| response.prop("getetag")))
+    }
+
+    /// 上传资源，`etag` 为 None 时只在资源不存在时创建
+    pub async fn put(&self, href: &str, ics: &str, etag: Option<&str>) -> Result<Written> {
+        let condition = match etag {
+            Some(etag) => ("If-Match", etag),
+            None => ("If-None-Match", "*"),
+        };
+        let response = self
+            .request(
+                "PUT",
+                href,
+                &[ICS_CONTENT, condition],
+                Some(ics.to_string()),
+            )
+            .await?;
+        match response.status().as_u16() {
+            412 => Ok(Written::Conflict),
+            _ if response.status().is_success() => {
+                // 弱 ETag 不能用于条件请求，丢弃后由调用方重新查询
+                let etag = response
+                    .headers()
+                    .get(header::ETAG)
+                    .and_then(|etag| etag.to_str().ok())
+                    .filter(|etag| !etag.starts_with("W/"))
+                    .map(|etag| etag.to_string());
+                Ok(Written::Done(etag))
+            }
+            _ => Err(anyhow!("上传 {} 失败: {}", href, response.status())),
+        }
+    }
+
+    /// 删除整个日历，日历已不存在也视为成功
+    pub async fn delete_calendar(&self, href: &str) -> Result<()> {
+        let response = self.request("DELETE", href, &[], None).await?;
+        match response.status().as_u16() {
+            404 => Ok(()),
+            _ if response.status().is_success() => Ok(()),
+            _ => Err(anyhow!("删除日历 {} 失败: {}", href, response.status())),
+        }
+    }
+
+    /// 删除资源，资源已不存在也视为成功
+    pub async fn delete(&self, href: &str, etag: &str) -> Result<Written> {
+        let response = self
+            .request("DELETE", href, &[("If-Match", etag)], None)
+            .await?;
+        match response.status().as_u16() {
+            412 => Ok(Written::Conflict),
+            404 => Ok(Written::Done(None)),
+            _ if response.status().is_success() => Ok(Written::Done(None)),
+            _ => Err(anyhow!("删除 {} 失败: {}", href, response.status())),
+        }
+    }
+}
+
+/// 已同步资源的记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemState {
+    pub href: String,
+    pub etag: String,
+    /// 上次同步时本地内容的哈希
+    pub hash: String,
+}
+
+/// 单个日历的同步记录，`items` 以本地事件 ID 为键
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalendarState {
+    pub href: String,
+    pub ctag: Option<String>,
+    pub sync_token: Option<String>,
+    pub items: HashMap<String, ItemState>,
+}
+
+/// CalDAV 同步记录，`calendars` 以本地列表 ID 为键
+///
+/// `deleted` 是本地已删除列表的墓碑，值为尚待删除的远程日历地址，
+/// 远程日历删除前不会再为它创建列表。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CalDavState {
+    pub calendars: HashMap<String, CalendarState>,
+    #[serde(default)]
+    pub deleted: HashMap<String, String>,
+}
+
+impl CalDavState {
+    /// 读取同步记录，文件不存在或损坏时从空记录开始
+    pub async fn load(path: &Path) -> Self {
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                warn!("CalDAV 同步记录损坏，将重新比较全部资源: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 先写入临时文件再替换，避免中断时留下不完整的记录
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let temp = path.with_extension("json.tmp");
+        tokio::fs::write(&temp, serde_json::to_string_pretty(self)?).await?;
+        tokio::fs::rename(&temp, path).await?;
+        Ok(())
+    }
+}
+
+/// 同步计划中的一步
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalDavAction {
+    /// 上传本地事件，`etag` 为 None 时创建新资源
+    Upload {
+        uid: String,
+        href: String,
+        etag: Option<String>,
+    },
+    /// 下载远程资源覆盖本地，`keep_local` 时先保留一份本地副本
+    Download { href: String, keep_local: bool },
+    /// 远程已删除，删除本地事件
+    DeleteLocal { uid: String },
+    /// 本地已删除，删除远程资源
+    DeleteRemote {
+        uid: String,
+        href: String,
+        etag: String,
+    },
+    /// 两端都已删除，只需清除记录
+    Forget { uid: String },
+    /// 两端都有修改，按策略跳过
+    Skip { uid: String },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    New,
+    Same,
+    Changed,
+    Removed,
+}
+
+/// 比较本地事件、上次同步记录与远程变化，生成同步计划
+///
+/// `local` 为本地事件 ID 到内容哈希的映射。
+pub fn plan(
+    calendar: &str,
+    local: &HashMap<String, String>,
+    saved: &CalendarState,
+    remote: &Changes,
+    strategy: ConflictStrategy,
+) -> Vec<CalDavAction> {
+    let listed: HashMap<String, &Resource> = remote
+        .changed
+        .iter()
+        .map(|resource| (normalize_href(&resource.href), resource))
+        .collect();
+    let removed: HashSet<String> = remote
+        .removed
+        .iter()
+        .map(|href| normalize_href(href))
+        .collect();
+
+    let mut uids: Vec<&String> = local.keys().chain(saved.items.keys()).collect();
+    uids.sort();
+    uids.dedup();
+
+    let mut actions = Vec::new();
+    let mut handled = HashSet::new();
+    for uid in uids {
+        let item = saved.items.get(uid);
+        let href = item
+            .map(|item| normalize_href(&item.href))
+            .unwrap_or_else(|| resource_href(calendar, uid));
+        handled.insert(href.clone());
+
+        let local_side = match (local.get(uid), item) {
+            (Some(_), None) => Side::New,
+            (Some(hash), Some(item)) if *hash != item.hash => Side::Changed,
+            (Some(_), Some(_)) => Side::Same,
+            (None, _) => Side::Removed,
+        };
+        let (remote_side, remote_etag) = match listed.get(&href) {
+            Some(resource) if item.is_some_and(|item| item.etag == resource.etag) => {
+                (Side::Same, None)
+            }
+            Some(resource) => (Side::Changed, Some(resource.etag.clone())),
+            None if removed.contains(&href) || (remote.full && item.is_some()) => {
+                (Side::Removed, None)
+            }
+            None => (Side::Same, None),
+        };
+
+        let uid = uid.clone();
+        let action = match (local_side, remote_side) {
+            (Side::Same, Side::Same) => continue,
+            (Side::New | Side::Changed, Side::Same) => CalDavAction::Upload {
+                uid,
+                href,
+                etag: item.map(|item| item.etag.clone()),
+            },
+            (Side::New | Side::Changed, Side::Changed) => match strategy {
+                ConflictStrategy::PreferLocal => CalDavAction::Upload {
+                    uid,
+                    href,
+                    etag: remote_etag,
+                },
+                ConflictStrategy::PreferRemote => CalDavAction::Download {
+                    href,
+                    keep_local: false,
+                },
+                ConflictStrategy::KeepBoth => CalDavAction::Download {
+                    href,
+                    keep_local: true,
+                },
+                ConflictStrategy::Skip => CalDavAction::Skip { uid },
+            },
+            // 本地有修改时不跟随远程删除，重新创建资源
+            (Side::New | Side::Changed, Side::Removed) => CalDavAction::Upload {
+                uid,
+                href,
+                etag: None,
+            },
+            (Side::Same, Side::Changed) | (Side::Removed, Side::Changed) => {
+                CalDavAction::Download {
+                    href,
+                    keep_local: false,
+                }
+            }
+            (Side::Same, Side::Removed) => CalDavAction::DeleteLocal { uid },
+            (Side::Removed, Side::Same) => match item {
+                Some(item) => CalDavAction::DeleteRemote {
+                    uid,
+                    href,
+                    etag: item.etag.clone(),
+                },
+                None => continue,
+            },
+            (Side::Removed, Side::Removed) => CalDavAction::Forget { uid },
+            (_, Side::New) => continue,
+        };
+        actions.push(action);
+    }
+
+    // 其余远程资源是其他客户端新建的
+    let mut added: Vec<&String> = listed
+        .keys()
+        .filter(|href| !handled.contains(*href))
+        .collect();
+    added.sort();
+    actions.extend(added.into_iter().map(|href| CalDavAction::Download {
+        href: href.clone(),
+        keep_local: false,
+    }));
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    const HOME: &str = "/dav/calendars/test/";
+
+    /// 内存中的日历集合，`log` 记录每次修改的资源，长度即当前的同步序号
+    #[derive(Default)]
+    struct Collection {
+        name: String,
+        items: HashMap<String, (String, String)>,
+        log: Vec<String>,
+    }
+
+    impl Collection {
+        fn token(&self) -> String {
+            format!("http://stand-in/sync/{}", self.log.len())
+        }
+    }
+
+    /// 最小的 CalDAV 替身服务器
+    #[derive(Default)]
+    struct StandIn {
+        calendars: HashMap<String, Collection>,
+        counter: u64,
+    }
+
+    struct Request {
+        method: String,
+        path: String,
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    type Reply = (u16, Vec<(String, String)>, String);
+
+    fn find<'a>(element: &'a Element, name: &str) -> Option<&'a Element> {
+        if element.name == name {
+            return Some(element);
+        }
+        element.children.iter().find_map(|child| find(child, name))
+    }
+
+    fn multistatus_reply(responses: &[String], token: Option<String>) -> Reply {
+        let token = token
+            .map(|token| format!("<d:sync-token>{}</d:sync-token>", token))
+            .unwrap_or_default();
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><d:multistatus xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/" xmlns:c="urn:ietf:params:xml:ns:caldav">{}{}</d:multistatus>"#,
+            responses.concat(),
+            token
+        );
+        (207, Vec::new(), body)
+    }
+
+    fn ok_response(href: &str, props: &str) -> String {
+        format!(
+            "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+            xml::escape(href),
+            props
+        )
+    }
+
+    fn missing_response(href: &str) -> String {
+        format!(
+            "<d:response><d:href>{}</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+            xml::escape(href)
+        )
+    }
+
+    fn parent(path: &str) -> String {
+        format!("{}/", path.rsplit_once('/').map_or("", |(dir, _)| dir))
+    }
+
+    impl StandIn {
+        fn handle(&mut self, request: &Request) -> Reply {
+            match request.method.as_str() {
+                "PROPFIND" => self.propfind(request),
+                "MKCALENDAR" => self.mkcalendar(request),
+                "REPORT" => self.report(request),
+                "PUT" => self.put(request),
+                "DELETE" => self.delete(request),
+                _ => (405, Vec::new(), String::new()),
+            }
+        }
+
+        fn propfind(&self, request: &Request) -> Reply {
+            if request.path == HOME {
+                let mut responses = vec![ok_response(
+                    HOME,
+                    "<d:resourcetype><d:collection/></d:resourcetype>",
+                )];
+                let mut hrefs: Vec<&String> = self.calendars.keys().collect();
+                hrefs.sort();
+                for href in hrefs {
+                    let calendar = &self.calendars[href];
+                    responses.push(ok_response(
+                        href,
+                        &format!(
+                            "<d:resourcetype><d:collection/><c:calendar/></d:resourcetype><d:displayname>{}</d:displayname><cs:getctag>{}</cs:getctag><d:sync-token>{}</d:sync-token><c:supported-calendar-component-set><c:comp name=\"VTODO\"/></c:supported-calendar-component-set>",
+                            xml::escape(&calendar.name),
+                            calendar.log.len(),
+                            calendar.token()
+                        ),
+                    ));
+                }
+                return multistatus_reply(&responses, None);
+            }
+            match self
+                .calendars
+                .get(&parent(&request.path))
+                .and_then(|calendar| calendar.items.get(&request.path))
+            {
+                Some((etag, _)) => multistatus_reply(
+                    &[ok_response(
+                        &request.path,
+                        &format!("<d:getetag>{}</d:getetag>", xml::escape(etag)),
+                    )],
+                    None,
+                ),
+                None => (404, Vec::new(), String::new()),
+            }
+        }
+
+        fn mkcalendar(&mut self, request: &Request) -> Reply {
+            if self.calendars.contains_key(&request.path) {
+                return (405, Vec::new(), String::new());
+            }
+            let body = xml::parse(&request.body).unwrap();
+            let name = find(&body, "displayname").map(|name| name.text().to_string());
+            self.calendars.insert(
+                request.path.clone(),
+                Collection {
+                    name: name.unwrap_or_default(),
+                    ..Default::default()
+                },
+            );
+            (201, Vec::new(), String::new())
+        }
+
+        fn report(&self, request: &Request) -> Reply {
+            let Some(calendar) = self.calendars.get(&request.path) else {
+                return (404, Vec::new(), String::new());
+            };
+            let body = xml::parse(&request.body).unwrap();
+            let etag = |href: &str, etag: &str| {
+                ok_response(
+                    href,
+                    &format!("<d:getetag>{}</d:getetag>", xml::escape(etag)),
+                )
+            };
+            match body.name.as_str() {
+                "calendar-query" => {
+                    let responses: Vec<String> = calendar
+                        .items
+                        .iter()
+                        .map(|(href, (tag, _))| etag(href, tag))
+                        .collect();
+                    multistatus_reply(&responses, None)
+                }
+                "sync-collection" => {
+                    let token = body
+                        .child("sync-token")
+                        .map(|token| token.text())
+                        .unwrap_or_default();
+                    let since = token
+                        .strip_prefix("http://stand-in/sync/")
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|n| *n <= calendar.log.len());
+                    let Some(since) = since else {
+                        return (
+                            403,
+                            Vec::new(),
+                            r#"<d:error xmlns:d="DAV:"><d:valid-sync-token/></d:error>"#
+                                .to_string(),
+                        );
+                    };
+                    let mut hrefs: Vec<&String> = calendar.log[since..].iter().collect();
+                    hrefs.sort();
+                    hrefs.dedup();
+                    let responses: Vec<String> = hrefs
+                        .into_iter()
+                        .map(|href| match calendar.items.get(href) {
+                            Some((tag, _)) => etag(href, tag),
+                            None => missing_response(href),
+                        })
+                        .collect();
+                    multistatus_reply(&responses, Some(calendar.token()))
+                }
+                "calendar-multiget" => {
+                    let responses: Vec<String> = body
+                        .children("href")
+                        .map(|href| {
+                            let href = normalize_href(href.text());
+                            match calendar.items.get(&href) {
+                                Some((tag, data)) => ok_response(
+                                    &href,
+                                    &format!(
+                                        "<d:getetag>{}</d:getetag><c:calendar-data>{}</c:calendar-data>",
+                                        xml::escape(tag),
+                                        xml::escape(data)
+                                    ),
+                                ),
+                                None => missing_response(&href),
+                            }
+                        })
+                        .collect();
+                    multistatus_reply(&responses, None)
+                }
+                _ => (400, Vec::new(), String::new()),
+            }
+        }
+
+        fn put(&mut self, request: &Request) -> Reply {
+            self.counter += 1;
+            let etag = format!("\"{}\"", self.counter);
+            let Some(calendar) = self.calendars.get_mut(&parent(&request.path)) else {
+                return (409, Vec::new(), String::new());
+            };
+            let current = calendar
+                .items
+                .get(&request.path)
+                .map(|(tag, _)| tag.clone());
+            let allowed = match (
+                request.headers.get("if-match"),
+                request.headers.get("if-none-match"),
+            ) {
+                (Some(expected), _) => current.as_ref() == Some(expected),
+                (None, Some(_)) => current.is_none(),
+                (None, None) => true,
+            };
+            if !allowed {
+                return (412, Vec::new(), String::new());
+            }
+            calendar
+                .items
+                .insert(request.path.clone(), (etag.clone(), request.body.clone()));
+            calendar.log.push(request.path.clone());
+            let status = if current.is_some() { 204 } else { 201 };
+            (status, vec![("ETag".to_string(), etag)], String::new())
+        }
+
+        fn delete(&mut self, request: &Request) -> Reply {
+            if self.calendars.remove(&request.path).is_some() {
+                return (204, Vec::new(), String::new());
+            }
+            let Some(calendar) = self.calendars.get_mut(&parent(&request.path)) else {
+                return (404, Vec::new(), String::new());
+            };
+            let Some((current, _)) = calendar.items.get(&request.path) else {
+                return (404, Vec::new(), String::new());
+            };
+            if request
+                .headers
+                .get("if-match")
+                .is_some_and(|expected| expected != current)
+            {
+                return (412, Vec::new(), String::new());
+            }
+            calendar.items.remove(&request.path);
+            calendar.log.push(request.path.clone());
+            (204, Vec::new(), String::new())
+        }
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let head_end = loop {
+            if let Some(i) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break i + 4;
+            }
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+        let mut lines = head.lines();
+        let mut first = lines.next()?.split_whitespace();
+        let method = first.next()?.to_string();
+        let path = normalize_href(first.next()?);
+        let headers: HashMap<String, String> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let length: usize = headers
+            .get("content-length")
+            .and_then(|length| length.parse().ok())
+            .unwrap_or(0);
+        while buffer.len() < head_end + length {
+            let n = stream.read(&mut chunk).await.ok()?;
+            if n == 0 {
+                return None;
+            }
+            buffer.extend_from_slice(&chunk[..n]);
+        }
+        let body = String::from_utf8_lossy(&buffer[head_end..head_end + length]).to_string();
+        Some(Request {
+            method,
+            path,
+            headers,
+            body,
+        })
+    }
+
+    /// 在本地端口上启动替身服务器，返回日历主集合的 URL
+    async fn serve(server: Arc<Mutex<StandIn>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    let (status, headers, body) = server.lock().unwrap().handle(&request);
+                    let mut head = format!(
+                        "HTTP/1.1 {} Stand-In\r\nContent-Length: {}\r\nConnection: close\r\n",
+                        status,
+                        body.len()
+                    );
+                    if !body.is_empty() {
+                        head.push_str("Content-Type: application/xml; charset=utf-8\r\n");
+                    }
+                    for (name, value) in headers {
+                        head.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    head.push_str("\r\n");
+                    let _ = stream.write_all(head.as_bytes()).await;
+                    let _ = stream.write_all(body.as_bytes()).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        format!("http://{}{}", address, HOME)
+    }
+
+    fn item(href: &str, etag: &str, hash: &str) -> ItemState {
+        ItemState {
+            href: href.to_string(),
+            etag: etag.to_string(),
+            hash: hash.to_string(),
+        }
+    }
+
+    fn resource(href: &str, etag: &str) -> Resource {
+        Resource {
+            href: href.to_string(),
+            etag: etag.to_string(),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn test_plan() {
+        let calendar = "/cal/work/";
+        let saved = CalendarState {
+            href: calendar.to_string(),
+            items: [
+                ("same", item("/cal/work/same.ics", "1", "h")),
+                ("local", item("/cal/work/local.ics", "1", "h")),
+                ("remote", item("/cal/work/remote.ics", "1", "h")),
+                ("both", item("/cal/work/both.ics", "1", "h")),
+                ("gone-local", item("/cal/work/gone-local.ics", "1", "h")),
+                ("gone-remote", item("/cal/work/gone-remote.ics", "1", "h")),
+                ("gone-both", item("/cal/work/gone-both.ics", "1", "h")),
+                ("edited-gone", item("/cal/work/edited-gone.ics", "1", "h")),
+            ]
+            .into_iter()
+            .map(|(uid, item)| (uid.to_string(), item))
+            .collect(),
+            ..Default::default()
+        };
+        let local: HashMap<String, String> = [
+            ("same", "h"),
+            ("local", "h2"),
+            ("remote", "h"),
+            ("both", "h2"),
+            ("gone-remote", "h"),
+            ("edited-gone", "h2"),
+            ("new", "h"),
+        ]
+        .into_iter()
+        .map(|(uid, hash)| (uid.to_string(), hash.to_string()))
+        .collect();
+        let remote = Changes {
+            changed: vec![
+                resource("/cal/work/remote.ics", "2"),
+                resource("/cal/work/both.ics", "2"),
+                resource("/cal/work/other%20client.ics", "1"),
+            ],
+            removed: vec![
+                "/cal/work/gone-remote.ics".to_string(),
+                "/cal/work/gone-both.ics".to_string(),
+                "/cal/work/edited-gone.ics".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        let actions = plan(
+            calendar,
+            &local,
+            &saved,
+            &remote,
+            ConflictStrategy::PreferLocal,
+        );
+        assert_eq!(
+            actions,
+            vec![
+                CalDavAction::Upload {
+                    uid: "both".to_string(),
+                    href: "/cal/work/both.ics".to_string(),
+                    etag: Some("2".to_string()),
+                },
+                CalDavAction::Upload {
+                    uid: "edited-gone".to_string(),
+                    href: "/cal/work/edited-gone.ics".to_string(),
+                    etag: None,
+                },
+                CalDavAction::Forget {
+                    uid: "gone-both".to_string()
+                },
+                CalDavAction::DeleteRemote {
+                    uid: "gone-local".to_string(),
+                    href: "/cal/work/gone-local.ics".to_string(),
+                    etag: "1".to_string(),
+                },
+                CalDavAction::DeleteLocal {
+                    uid: "gone-remote".to_string()
+                },
+                CalDavAction::Upload {
+                    uid: "local".to_string(),
+                    href: "/cal/work/local.ics".to_string(),
+                    etag: Some("1".to_string()),
+                },
+                CalDavAction::Upload {
+                    uid: "new".to_string(),
+                    href: "/cal/work/new.ics".to_string(),
+                    etag: None,
+                },
+                CalDavAction::Download {
+                    href: "/cal/work/remote.ics".to_string(),
+                    keep_local: false,
+                },
+                CalDavAction::Download {
+                    href: "/cal/work/other client.ics".to_string(),
+                    keep_local: false,
+                },
+            ]
+        );
+
+        let conflict = |strategy| {
+            plan(calendar, &local, &saved, &remote, strategy)
+                .into_iter()
+                .next()
+                .unwrap()
+        };
+        assert_eq!(
+            conflict(ConflictStrategy::PreferRemote),
+            CalDavAction::Download {
+                href: "/cal/work/both.ics".to_string(),
+                keep_local: false,
+            }
+        );
+        assert_eq!(
+            conflict(ConflictStrategy::KeepBoth),
+            CalDavAction::Download {
+                href: "/cal/work/both.ics".to_string(),
+                keep_local: true,
+            }
+        );
+        assert_eq!(
+            conflict(ConflictStrategy::Skip),
+            CalDavAction::Skip {
+                uid: "both".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_plan_full_listing() {
+        let calendar = "/cal/work/";
+        let saved = CalendarState {
+            href: calendar.to_string(),
+            items: [
+                ("kept".to_string(), item("/cal/work/kept.ics", "1", "h")),
+                (
+                    "dropped".to_string(),
+                    item("/cal/work/dropped.ics", "1", "h"),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let local: HashMap<String, String> = [
+            ("kept".to_string(), "h".to_string()),
+            ("dropped".to_string(), "h".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        // 完整列表中没有出现的已知资源视为远程已删除
+        let remote = Changes {
+            changed: vec![resource("/cal/work/kept.ics", "1")],
+            full: true,
+            ..Default::default()
+        };
+        let actions = plan(
+            calendar,
+            &local,
+            &saved,
+            &remote,
+            ConflictStrategy::PreferLocal,
+        );
+        assert_eq!(
+            actions,
+            vec![CalDavAction::DeleteLocal {
+                uid: "dropped".to_string()
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_against_stand_in() -> Result<()> {
+        let server = Arc::new(Mutex::new(StandIn::default()));
+        let url = serve(server.clone()).await;
+        let client = CalDavClient::new(&url, "user", "password").await?;
+        assert_eq!(client.home(), HOME);
+        assert!(client.calendars().await?.is_empty());
+
+        let created = client.create_calendar("list-1", "工作 & 学习").await?;
+        let calendars = client.calendars().await?;
+        assert_eq!(calendars.len(), 1);
+        let calendar = &calendars[0];
+        assert_eq!(calendar.href, created.href);
+        assert_eq!(calendar.name, "工作 & 学习");
+        assert!(calendar.todo);
+        assert_eq!(calendar.ctag.as_deref(), Some("0"));
+
+        // 初次同步得到完整列表
+        let changes = client.changes(calendar, None).await?;
+        assert!(changes.full);
+        assert!(changes.changed.is_empty());
+        let token = changes.sync_token.clone().unwrap();
+
+        let href = resource_href(&calendar.href, "event-1");
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:event-1\r\nSUMMARY:<写报告>\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let Written::Done(Some(etag)) = client.put(&href, ics, None).await? else {
+            panic!("创建资源失败");
+        };
+        // 资源已存在时不能再次创建，ETag 过期时不能覆盖
+        assert_eq!(client.put(&href, ics, None).await?, Written::Conflict);
+        assert_eq!(
+            client.put(&href, ics, Some("\"stale\"")).await?,
+            Written::Conflict
+        );
+        assert_eq!(client.etag(&href).await?, Some(etag.clone()));
+
+        let changes = client.changes(calendar, Some(&token)).await?;
+        assert!(!changes.full);
+        assert_eq!(changes.changed, vec![resource(&href, &etag)]);
+        let token = changes.sync_token.clone().unwrap();
+
+        let fetched = client.fetch(calendar, std::slice::from_ref(&href)).await?;
+        assert_eq!(fetched.len(), 1);
+        assert_eq!(fetched[0].etag, etag);
+        assert_eq!(fetched[0].data.as_deref(), Some(ics.trim()));
+
+        let Written::Done(Some(updated)) = client.put(&href, ics, Some(&etag)).await? else {
+            panic!("更新资源失败");
+        };
+        assert_eq!(client.delete(&href, &etag).await?, Written::Conflict);
+        assert_eq!(client.delete(&href, &updated).await?, Written::Done(None));
+        assert_eq!(client.etag(&href).await?, None);
+
+        let changes = client.changes(calendar, Some(&token)).await?;
+        assert_eq!(changes.removed, vec![href.clone()]);
+        assert!(changes.changed.is_empty());
+
+        // 失效的 sync-token 退回完整列表
+        let changes = client
+            .changes(calendar, Some("http://stand-in/sync/99"))
+            .await?;
+        assert!(changes.full);
+        assert!(changes.changed.is_empty());
+
+        client.delete_calendar(&calendar.href).await?;
+        assert!(client.calendars().await?.is_empty());
+        // 已不存在的日历同样视为删除成功
+        client.delete_calendar(&calendar.href).await?;
+        Ok(())
+    }
+}
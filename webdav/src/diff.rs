@@ -1,7 +1,11 @@
-use crate::model::{DiffEntry, DiffResult, DiffType, EntryState, FileSystemState};
+use crate::model::{
+    DiffEntry, DiffResult, DiffType, EntryState, FileSystemState, MergeEntry, MergeType,
+};
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc};
 use log::info;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 
 /// 差异比较配置
 pub struct DiffConfig {
@@ -128,14 +132,129 @@ fn check_same(
             (Some(h1), Some(h2)) if h1 == h2)))
 }
 
+/// 一端当前状态相对上次同步状态的变化，键为路径
+///
+/// `Added` 表示新建，`Deleted` 表示已删除，未出现的路径没有变化。
+fn side_changes(
+    base: &FileSystemState,
+    current: &FileSystemState,
+    config: &DiffConfig,
+) -> Result<HashMap<PathBuf, DiffType>> {
+    Ok(compare_states(current, base, config)?
+        .entries
+        .into_iter()
+        .map(|entry| (entry.path, entry.diff_type))
+        .collect())
+}
+
+// 两端的修改时间来自不同的时钟，跨端只比较类型、大小与哈希；
+// 远程状态没有哈希，文件哈希未知时无法确认内容相同，视为不同
+fn same_content(local: Option<&EntryState>, remote: Option<&EntryState>) -> bool {
+    match (local, remote) {
+        (None, None) => true,
+        (Some(local), Some(remote)) if local.is_directory() && remote.is_directory() => true,
+        (Some(local), Some(remote)) => {
+            local.entry_type == remote.entry_type
+                && local.size == remote.size
+                && matches!(
+                    (&local.content_hash, &remote.content_hash),
+                    (Some(h1), Some(h2)) if h1 == h2
+                )
+        }
+        _ => false,
+    }
+}
+
+/// 以上次同步的状态为基准对本地与远程做三方比较
+///
+/// 每个路径按两端各自相对基准的变化分类：仅一端变化时同步该端，
+/// 两端都变化且结果不同时为冲突。没有基准（首次同步）时传入空状态，
+/// 两端都存在的路径按内容是否相同分为一致或冲突。
+pub fn merge_states(
+    base_local: &FileSystemState,
+    local: &FileSystemState,
+    base_remote: &FileSystemState,
+    remote: &FileSystemState,
+    config: &DiffConfig,
+) -> Result<Vec<MergeEntry>> {
+    let local_changes = side_changes(base_local, local, config)?;
+    let remote_changes = side_changes(base_remote, remote, config)?;
+
+    let paths: BTreeSet<&PathBuf> = local
+        .entries
+        .keys()
+        .chain(remote.entries.keys())
+        .chain(base_local.entries.keys())
+        .chain(base_remote.entries.keys())
+        .collect();
+
+    let mut result = Vec::with_capacity(paths.len());
+    for path in paths {
+        let local_state = local.entries.get(path);
+        let remote_state = remote.entries.get(path);
+        let change = |changes: &HashMap<PathBuf, DiffType>| {
+            changes.get(path).cloned().unwrap_or(DiffType::Unchanged)
+        };
+        let local_change = change(&local_changes);
+        let remote_change = change(&remote_changes);
+
+        let merge_type = match (
+            local_change == DiffType::Unchanged,
+            remote_change == DiffType::Unchanged,
+        ) {
+            (true, true) => MergeType::Same,
+            (false, true) => MergeType::LocalChanged,
+            (true, false) => MergeType::RemoteChanged,
+            (false, false) if same_content(local_state, remote_state) => MergeType::Same,
+            (false, false) => MergeType::Conflict,
+        };
+
+        let Some(entry_type) = local_state
+            .or(remote_state)
+            .or_else(|| base_local.entries.get(path))
+            .or_else(|| base_remote.entries.get(path))
+            .map(|state| state.entry_type.clone())
+        else {
+            continue;
+        };
+        result.push(MergeEntry {
+            path: path.clone(),
+            entry_type,
+            merge_type,
+            local_change,
+            remote_change,
+            local_state: local_state.cloned(),
+            remote_state: remote_state.cloned(),
+        });
+    }
+
+    info!(
+        "三方比较完成: 本地变化 {}, 远程变化 {}, 冲突 {}",
+        result
+            .iter()
+            .filter(|e| e.merge_type == MergeType::LocalChanged)
+            .count(),
+        result
+            .iter()
+            .filter(|e| e.merge_type == MergeType::RemoteChanged)
+            .count(),
+        result
+            .iter()
+            .filter(|e| e.merge_type == MergeType::Conflict)
+            .count()
+    );
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::diff::{compare_states, DiffConfig};
-    use crate::model::{DiffType, EntryState, FileSystemState};
+    use crate::diff::{compare_states, merge_states, DiffConfig};
+    use crate::model::{DiffType, EntryState, FileSystemState, MergeType};
 
     use anyhow::Result;
     use chrono::{Duration, Utc};
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
     // Helper function to create a state from a list of EntryState
     fn create_state(entries: Vec<EntryState>) -> FileSystemState {
@@ -347,4 +466,111 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_merge_states() -> Result<()> {
+        // 场景：以上次同步为基准，分别在两端修改、删除和新建文件
+        let then = Utc::now() - Duration::hours(1);
+        let now = Utc::now();
+        let file = |path: &str, time, size| EntryState::new_file(path.into(), time, size);
+
+        let base = create_state(vec![
+            file("same.md", then, 1),
+            file("local.md", then, 1),
+            file("remote.md", then, 1),
+            file("both.md", then, 1),
+            file("both_equal.md", then, 1),
+            file("both_unknown.md", then, 1),
+            file("deleted.md", then, 1),
+        ]);
+        let local = create_state(vec![
+            file("same.md", then, 1),
+            file("local.md", now, 2),
+            file("remote.md", then, 1),
+            file("both.md", now, 2),
+            file("both_equal.md", now, 3).with_hash("abc".into()),
+            file("both_unknown.md", now, 3).with_hash("abc".into()),
+            file("new.md", now, 4),
+        ]);
+        let remote = create_state(vec![
+            // 远程的修改时间与本地不同，但相对基准没有变化
+            file("same.md", then, 1),
+            file("local.md", then, 1),
+            file("remote.md", now, 5),
+            file("both.md", now, 6),
+            file("both_equal.md", now + Duration::seconds(30), 3).with_hash("abc".into()),
+            // 远程状态没有哈希，大小相同也无法确认内容一致
+            file("both_unknown.md", now, 3),
+            file("deleted.md", then, 1),
+        ]);
+
+        let merged = merge_states(&base, &local, &base, &remote, &DiffConfig::default())?;
+        let kind = |path: &str| {
+            merged
+                .iter()
+                .find(|entry| entry.path == Path::new(path))
+                .map(|entry| (entry.merge_type, entry.local_change.clone()))
+                .unwrap()
+        };
+
+        assert_eq!(merged.len(), 8);
+        assert_eq!(kind("same.md").0, MergeType::Same);
+        assert_eq!(
+            kind("local.md"),
+            (MergeType::LocalChanged, DiffType::Modified)
+        );
+        assert_eq!(
+            kind("remote.md"),
+            (MergeType::RemoteChanged, DiffType::Unchanged)
+        );
+        assert_eq!(kind("both.md").0, MergeType::Conflict);
+        assert_eq!(kind("both_equal.md").0, MergeType::Same);
+        assert_eq!(kind("both_unknown.md").0, MergeType::Conflict);
+        assert_eq!(kind("new.md"), (MergeType::LocalChanged, DiffType::Added));
+        assert_eq!(
+            kind("deleted.md"),
+            (MergeType::LocalChanged, DiffType::Deleted)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_states_without_base() -> Result<()> {
+        // 场景：首次同步，两端都存在的文件按大小与哈希判断是否一致，哈希未知时视为冲突
+        let now = Utc::now();
+        let base = FileSystemState::new();
+        let local = create_state(vec![
+            EntryState::new_directory("dir".into(), now),
+            EntryState::new_file("dir/equal.md".into(), now, 10).with_hash("abc".into()),
+            EntryState::new_file("dir/differ.md".into(), now, 10),
+            EntryState::new_file("dir/unknown.md".into(), now, 10).with_hash("def".into()),
+        ]);
+        let remote = create_state(vec![
+            EntryState::new_directory("dir".into(), now + Duration::seconds(90)),
+            EntryState::new_file("dir/equal.md".into(), now + Duration::seconds(90), 10)
+                .with_hash("abc".into()),
+            EntryState::new_file("dir/differ.md".into(), now, 11),
+            EntryState::new_file("dir/remote.md".into(), now, 12),
+            EntryState::new_file("dir/unknown.md".into(), now, 10),
+        ]);
+
+        let merged = merge_states(&base, &local, &base, &remote, &DiffConfig::default())?;
+        let types: Vec<(String, MergeType)> = merged
+            .iter()
+            .map(|entry| (entry.path.display().to_string(), entry.merge_type))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("dir".to_string(), MergeType::Same),
+                ("dir/differ.md".to_string(), MergeType::Conflict),
+                ("dir/equal.md".to_string(), MergeType::Same),
+                ("dir/remote.md".to_string(), MergeType::RemoteChanged),
+                ("dir/unknown.md".to_string(), MergeType::Conflict),
+            ]
+        );
+
+        Ok(())
+    }
+}
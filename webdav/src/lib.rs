@@ -1,6 +1,9 @@
+pub mod caldav;
 pub mod model;
 mod state;
 pub mod sync_operations;
+pub mod tombstone;
 pub mod webdav;
 mod manager;
-mod diff;
\ No newline at end of file
+mod diff;
+mod xml;
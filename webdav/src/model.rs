@@ -223,6 +223,38 @@ impl DiffResult {
     }
 }
 
+/// 三方比较中一个路径的分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeType {
+    /// 仅本地有变化
+    LocalChanged,
+    /// 仅远程有变化
+    RemoteChanged,
+    /// 两端都有变化且结果不同
+    Conflict,
+    /// 两端都没有变化，或变化后的结果相同
+    Same,
+}
+
+/// 一个路径在本地、远程与上次同步状态之间的比较结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeEntry {
+    /// 条目路径
+    pub path: PathBuf,
+    /// 条目类型
+    pub entry_type: EntryType,
+    /// 分类
+    pub merge_type: MergeType,
+    /// 本地相对上次同步的变化
+    pub local_change: DiffType,
+    /// 远程相对上次同步的变化
+    pub remote_change: DiffType,
+    /// 本地状态
+    pub local_state: Option<EntryState>,
+    /// 远程状态
+    pub remote_state: Option<EntryState>,
+}
+
 /// 同步冲突解决策略
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ConflictStrategy {
@@ -231,11 +263,26 @@ pub enum ConflictStrategy {
     /// 优先使用远程版本
     PreferRemote,
     /// 保留两者（重命名本地版本）
+    ///
+    /// 另存的副本只是普通文件，不会作为事件出现在应用中。应用的同步记录
+    /// 不另存副本，而是使用写入该记录的设备的版本。
     KeepBoth,
     /// 跳过冲突文件
     Skip,
 }
 
+impl ConflictStrategy {
+    /// 按配置中的名称取得策略，未知名称按 PreferLocal 处理
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "PreferRemote" => ConflictStrategy::PreferRemote,
+            "KeepBoth" => ConflictStrategy::KeepBoth,
+            "Skip" => ConflictStrategy::Skip,
+            _ => ConflictStrategy::PreferLocal,
+        }
+    }
+}
+
 /// 同步操作的类型
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SyncOperationType {
@@ -251,6 +298,8 @@ pub enum SyncOperationType {
     CreateLocalDirectory,
     /// 创建远程目录
     CreateRemoteDirectory,
+    /// 将本地文件复制到目标路径
+    CopyLocal,
     /// 跳过（不操作）
     Skip,
 }
@@ -268,6 +317,9 @@ pub struct SyncOperation {
     pub status: SyncOperationStatus,
     /// 错误信息（如果有）
     pub error: Option<String>,
+    /// 复制的目标路径（仅对 CopyLocal 有效）
+    #[serde(default)]
+    pub target: Option<PathBuf>,
 }
 
 // 定义一个宏，根据 SyncOperationType 的变体名自动生成对应的工厂函数
@@ -277,14 +329,8 @@ macro_rules! define_sync_operations {
         // 使用 paste! 宏来将变体名转换为小写蛇形命名法的函数名
         ::paste::paste! {
             $(
-                pub fn [<$variant:snake>](entry: &DiffEntry) -> Self {
-                    Self {
-                        operation_type: SyncOperationType::$variant,
-                        path: entry.path.clone(),
-                        entry_type: entry.entry_type.clone(),
-                        status: SyncOperationStatus::Pending,
-                        error: None,
-                    }
+                pub fn [<$variant:snake>](entry: &MergeEntry) -> Self {
+                    Self::new(SyncOperationType::$variant, entry.path.clone(), entry.entry_type.clone())
                 }
             )*
         }
@@ -292,6 +338,17 @@ macro_rules! define_sync_operations {
 }
 
 impl SyncOperation {
+    fn new(operation_type: SyncOperationType, path: PathBuf, entry_type: EntryType) -> Self {
+        Self {
+            operation_type,
+            path,
+            entry_type,
+            status: SyncOperationStatus::Pending,
+            error: None,
+            target: None,
+        }
+    }
+
     define_sync_operations!(
         Upload,
         Download,
@@ -301,13 +358,32 @@ impl SyncOperation {
         CreateRemoteDirectory
     );
 
-    pub fn skip(entry: &DiffEntry) -> Self {
+    pub fn skip(entry: &MergeEntry) -> Self {
         Self {
-            operation_type: SyncOperationType::Skip,
-            path: entry.path.clone(),
-            entry_type: entry.entry_type.clone(),
             status: SyncOperationStatus::Skipped,
             error: Some("冲突文件已跳过".to_string()),
+            ..Self::new(
+                SyncOperationType::Skip,
+                entry.path.clone(),
+                entry.entry_type.clone(),
+            )
+        }
+    }
+
+    /// 上传指定路径的本地文件
+    pub fn upload_path(path: PathBuf) -> Self {
+        Self::new(SyncOperationType::Upload, path, EntryType::File)
+    }
+
+    /// 将本地文件复制为另一路径，用于保留冲突的本地版本
+    pub fn copy_local(entry: &MergeEntry, target: PathBuf) -> Self {
+        Self {
+            target: Some(target),
+            ..Self::new(
+                SyncOperationType::CopyLocal,
+                entry.path.clone(),
+                entry.entry_type.clone(),
+            )
         }
     }
 }
@@ -404,13 +480,30 @@ impl SyncSession {
     }
 
     /// 中止会话
-    #[allow(dead_code)]
     pub fn abort(&mut self, reason: String) {
         self.status = SyncSessionStatus::Aborted;
         self.error = Some(reason);
         self.end_time = Some(Utc::now());
     }
 
+    /// 本次会话成功下载到本地的文件路径
+    pub fn downloaded_paths(&self) -> Vec<PathBuf> {
+        self.operations
+            .iter()
+            .filter(|op| {
+                op.status == SyncOperationStatus::Completed
+                    && op.operation_type == SyncOperationType::Download
+            })
+            .map(|op| {
+                let relative_path = op.path.strip_prefix("/").unwrap_or(&op.path);
+                let local_relative_path = relative_path
+                    .strip_prefix("ToDoPulse/")
+                    .unwrap_or(relative_path);
+                self.local_dir.join(local_relative_path)
+            })
+            .collect()
+    }
+
     /// 获取会话的统计信息
     pub fn get_stats(&self) -> SyncSessionStats {
         let completed = self
@@ -5,13 +5,26 @@ use log::{debug, error, warn};
 use reqwest_dav::Client;
 use std::path::{Path, PathBuf};
 
-use crate::diff::{DiffConfig, compare_states};
+use crate::diff::{DiffConfig, merge_states};
 use crate::manager::{with_app_path, with_config};
 use crate::model::*;
 use crate::state::{StateCollectionConfig, collect_local_state, load_state, save_state};
+use crate::tombstone::{TOMBSTONE_FILE, Tombstones};
 use crate::webdav::*;
 use crate::{with_config_if_enabled, without_config_enabled};
 
+/// 不参与文件同步的路径
+///
+/// 数据库在应用运行时一直处于打开状态，整体传输既无法合并两端的修改，
+/// 下载时还可能损坏正在使用的文件，其中的记录由应用逐条导出后再同步。
+const UNSYNCED: &[&str] = &["ToDoPulse/data.db"];
+
+/// 数据目录下存放同步记录的目录，随文件同步一起传输
+///
+/// 每台设备只写入以自己的设备标识命名的子目录，文件同步因此不会产生冲突；
+/// 应用合并时读取所有设备的目录，逐字段取最后的修改。
+pub const RECORD_DIR: &str = ".records";
+
 pub trait PathExt {
     fn contains(&self, pattern: &str) -> bool;
 }
@@ -71,7 +84,7 @@ pub async fn collect_states(
 
     // 收集本地状态
     let state_config = StateCollectionConfig::default();
-    let local_state = collect_local_state(&session.local_dir, &state_config).await?;
+    let mut local_state = collect_local_state(&session.local_dir, &state_config).await?;
 
     // 收集远程状态
     let webdav_remote_state = collect_remote_state(&client, &session.remote_dir).await?;
@@ -81,6 +94,16 @@ pub async fn collect_states(
     for (webdav_path, webdav_entry) in &webdav_remote_state.entries {
         path_resolve(webdav_path, webdav_entry, &mut remote_state);
     }
+    // 同步根目录本身不参与比较，本地状态中也没有它
+    remote_state.entries.remove(Path::new("ToDoPulse"));
+    // 删除记录单独读写，不作为普通文件同步
+    remote_state
+        .entries
+        .remove(&Path::new("ToDoPulse").join(TOMBSTONE_FILE));
+    for path in UNSYNCED {
+        local_state.entries.remove(Path::new(path));
+        remote_state.entries.remove(Path::new(path));
+    }
 
     // 最终验证
     if let Some((path, _)) = remote_state
@@ -214,6 +237,65 @@ pub async fn execute_sync_operations(
                     }
                 }
             }
+            SyncOperationType::CopyLocal => {
+                let local_path = session.local_dir.join(local_relative_path);
+                let Some(target) = &operation.target else {
+                    operation.status = SyncOperationStatus::Failed;
+                    operation.error = Some("缺少复制的目标路径".to_string());
+                    continue;
+                };
+                let target = target.strip_prefix("/").unwrap_or(target);
+                let target_path = session
+                    .local_dir
+                    .join(target.strip_prefix("ToDoPulse/").unwrap_or(target));
+
+                match tokio::fs::copy(&local_path, &target_path).await {
+                    Ok(_) => {
+                        operation.status = SyncOperationStatus::Completed;
+                    }
+                    Err(e) => {
+                        operation.status = SyncOperationStatus::Failed;
+                        operation.error = Some(format!("复制失败: {}", e));
+                    }
+                }
+            }
+            SyncOperationType::DeleteLocal => {
+                let local_path = session.local_dir.join(local_relative_path);
+                let result = if operation.entry_type == EntryType::Directory {
+                    tokio::fs::remove_dir_all(&local_path).await
+                } else {
+                    tokio::fs::remove_file(&local_path).await
+                };
+
+                match result {
+                    Ok(_) => {
+                        operation.status = SyncOperationStatus::Completed;
+                    }
+                    // 已不存在（如随所在目录一起删除）时视为完成
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        operation.status = SyncOperationStatus::Completed;
+                    }
+                    Err(e) => {
+                        operation.status = SyncOperationStatus::Failed;
+                        operation.error = Some(format!("删除失败: {}", e));
+                        error!("删除本地条目失败 {}: {}", relative_path.display(), e);
+                    }
+                }
+            }
+            SyncOperationType::DeleteRemote => {
+                let remote_path = Path::new("/").join(relative_path);
+
+                match delete_remote(&client, &remote_path).await {
+                    Ok(_) => {
+                        operation.status = SyncOperationStatus::Completed;
+                    }
+                    Err(e) => {
+                        operation.status = SyncOperationStatus::Failed;
+                        operation.error = Some(format!("删除失败: {}", e));
+                        error!("删除远程条目失败 {}: {}", relative_path.display(), e);
+                    }
+                }
+            }
             _ => {
                 operation.status = SyncOperationStatus::Skipped;
             }
@@ -240,59 +322,224 @@ pub async fn execute_sync_operations(
 }
 
 /// 执行完整的同步流程
-pub async fn perform_sync() -> Result<SyncSession> {
+///
+/// 以上次同步后保存的状态为基准做三方比较，两端都修改过的文件按
+/// `conflict_strategy` 处理，一端删除的条目在另一端也删除并留下删除记录。
+/// 同步完成后重新收集两端状态作为下次的基准。
+pub async fn perform_sync(conflict_strategy: ConflictStrategy) -> Result<SyncSession> {
     let client = client().await?;
     // 创建同步会话
     let mut session = create_sync_session()?;
+    let state_dir = with_app_path(|app| app.config_dir().join("sync_state"));
 
     // 尝试加载上一次的状态
-    let states = load_sync_state()
-        .await
-        .map_err(|e| {
-            warn!("无法加载上一次的同步状态: {}", e);
-            anyhow!("同步状态加载失败")
-        })
-        .unwrap_or((None, None));
-
-    let (saved_local_state, saved_remote_state) = states;
+    let (saved_local_state, saved_remote_state) = load_sync_state().await.unwrap_or_else(|e| {
+        warn!("无法加载上一次的同步状态: {}", e);
+        (None, None)
+    });
+    let (mut tombstones, remote_tombstones) =
+        load_tombstones(&client, &session.remote_dir, &state_dir).await;
 
     // 收集当前状态
     let (local_state, remote_state) = collect_states(&mut session).await?;
 
-    // 保存当前状态供下次使用
-    if let Err(e) = save_sync_state(&local_state, &remote_state).await {
-        warn!("保存同步状态失败: {}", e);
+    // 比较差异，没有基准时以空状态为基准
+    session.status = SyncSessionStatus::Diffing;
+    let empty = FileSystemState::new();
+    let merged = merge_states(
+        saved_local_state.as_ref().unwrap_or(&empty),
+        &local_state,
+        saved_remote_state.as_ref().unwrap_or(&empty),
+        &remote_state,
+        &DiffConfig::default(),
+    )?;
+
+    // 有同步基准时只在一端出现的条目都是新建的，丢失基准时才按删除记录识别残留
+    let no_tombstones = Tombstones::default();
+    let known_deletions = match (&saved_local_state, &saved_remote_state) {
+        (Some(_), Some(_)) => &no_tombstones,
+        _ => &tombstones,
+    };
+    let device = device_id().await;
+    plan_sync_operations(
+        &mut session,
+        &merged,
+        conflict_strategy,
+        known_deletions,
+        &device,
+    )?;
+    // 中止时不执行任何操作，也不更新基准
+    if session.status == SyncSessionStatus::Aborted {
+        return Ok(session);
     }
 
-    // 比较差异
-    session.status = SyncSessionStatus::Diffing;
-    let diff_config = DiffConfig::default();
+    // 如果没有操作需要执行，直接完成
+    let session = if session.operations.is_empty() {
+        session.status = SyncSessionStatus::Completed;
+        session.end_time = Some(Utc::now());
+        session
+    } else {
+        execute_sync_operations(session, &client).await?
+    };
+
+    // 更新删除记录
+    update_tombstones(&mut tombstones, &session, &merged, &device);
+    save_tombstones(
+        &client,
+        &tombstones,
+        remote_tombstones.as_ref(),
+        &session.remote_dir,
+        &state_dir,
+    )
+    .await;
 
-    let diff =
-        if let (Some(saved_local), Some(saved_remote)) = (saved_local_state, saved_remote_state) {
-            let local_changes = compare_states(&saved_local, &local_state, &diff_config)?;
+    // 保存同步后的状态供下次使用
+    let unfinished: Vec<&Path> = session
+        .operations
+        .iter()
+        .filter(|op| op.status != SyncOperationStatus::Completed)
+        .map(|op| op.path.as_path())
+        .collect();
+    let collected = if session.operations.is_empty() {
+        Ok((local_state, remote_state))
+    } else {
+        collect_states(&mut session.clone()).await
+    };
+    match collected {
+        Ok((local_state, remote_state)) => {
+            let local_state = next_base(saved_local_state.as_ref(), local_state, &unfinished);
+            let remote_state = next_base(saved_remote_state.as_ref(), remote_state, &unfinished);
+            if let Err(e) = save_sync_state(&local_state, &remote_state).await {
+                warn!("保存同步状态失败: {}", e);
+            }
+        }
+        // 不更新基准，下次同步时重新比较
+        Err(e) => warn!("收集同步后的状态失败: {}", e),
+    }
 
-            let remote_changes = compare_states(&saved_remote, &remote_state, &diff_config)?;
+    Ok(session)
+}
 
-            let mut combined_diff = local_changes;
-            combined_diff
-                .entries
-                .extend(remote_changes.entries.iter().cloned());
-            combined_diff
-        } else {
-            compare_states(&local_state, &remote_state, &diff_config)?
-        };
+/// 本设备的标识，首次使用时生成并保存
+pub async fn device_id() -> String {
+    let state_dir = with_app_path(|app| app.config_dir().join("sync_state"));
+    let path = state_dir.join("device_id");
+    match tokio::fs::read_to_string(&path).await {
+        Ok(id) if !id.trim().is_empty() => return id.trim().to_string(),
+        _ => {}
+    }
 
-    plan_sync_operations(&mut session, &diff, ConflictStrategy::PreferLocal)?;
+    let host = std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_| "device".to_string());
+    let id = format!(
+        "{}-{}",
+        host,
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    );
+    if let Err(e) = tokio::fs::create_dir_all(&state_dir).await {
+        warn!("创建同步状态目录失败: {}", e);
+    } else if let Err(e) = tokio::fs::write(&path, &id).await {
+        warn!("保存设备标识失败: {}", e);
+    }
+    id
+}
 
-    // 如果没有操作需要执行，直接完成
-    if session.operations.is_empty() {
-        session.status = SyncSessionStatus::Completed;
-        session.end_time = Some(Utc::now());
-        return Ok(session);
+/// 读取本地保存的删除记录并合并远程的记录
+///
+/// 同时返回远程原有的记录，用于判断同步后是否需要重新上传。
+async fn load_tombstones(
+    client: &Client,
+    remote_dir: &Path,
+    state_dir: &Path,
+) -> (Tombstones, Option<Tombstones>) {
+    let mut tombstones = Tombstones::load(&state_dir.join("tombstones.json")).await;
+
+    if let Err(e) = tokio::fs::create_dir_all(state_dir).await {
+        warn!("创建同步状态目录失败: {}", e);
+    }
+    let temp = state_dir.join("remote_tombstones.json");
+    let remote = match download_file(client, &remote_dir.join(TOMBSTONE_FILE), &temp).await {
+        Ok(_) => {
+            let remote = Tombstones::load(&temp).await;
+            let _ = tokio::fs::remove_file(&temp).await;
+            Some(remote)
+        }
+        Err(e) => {
+            debug!("未读取到远程删除记录: {}", e);
+            None
+        }
+    };
+    if let Some(remote) = &remote {
+        tombstones.merge(remote.clone());
+    }
+
+    (tombstones, remote)
+}
+
+/// 按执行结果更新删除记录
+///
+/// 本机发起的删除记为本设备，同步自远程的删除保留原有的记录；重新创建或
+/// 上传、下载过的路径移除其删除记录。删除记录保存被删除版本的大小与哈希。
+fn update_tombstones(
+    tombstones: &mut Tombstones,
+    session: &SyncSession,
+    entries: &[MergeEntry],
+    device: &str,
+) {
+    let now = Utc::now();
+    let state = |path: &Path, local: bool| {
+        let entry = entries.iter().find(|entry| entry.path == path)?;
+        if local {
+            entry.local_state.as_ref()
+        } else {
+            entry.remote_state.as_ref()
+        }
+    };
+    for operation in &session.operations {
+        if operation.status != SyncOperationStatus::Completed {
+            continue;
+        }
+        match operation.operation_type {
+            SyncOperationType::DeleteRemote => {
+                if let Some(deleted) = state(&operation.path, false) {
+                    tombstones.record(&operation.path, device, now, deleted);
+                }
+            }
+            SyncOperationType::DeleteLocal if !tombstones.entries.contains_key(&operation.path) => {
+                if let Some(deleted) = state(&operation.path, true) {
+                    tombstones.record(&operation.path, device, now, deleted);
+                }
+            }
+            SyncOperationType::Upload
+            | SyncOperationType::Download
+            | SyncOperationType::CreateLocalDirectory
+            | SyncOperationType::CreateRemoteDirectory => tombstones.revive(&operation.path),
+            _ => {}
+        }
     }
+    tombstones.prune(now);
+}
 
-    execute_sync_operations(session, &client).await
+/// 保存删除记录，与远程不一致时上传
+async fn save_tombstones(
+    client: &Client,
+    tombstones: &Tombstones,
+    remote: Option<&Tombstones>,
+    remote_dir: &Path,
+    state_dir: &Path,
+) {
+    let path = state_dir.join("tombstones.json");
+    if let Err(e) = tombstones.save(&path).await {
+        warn!("保存删除记录失败: {}", e);
+        return;
+    }
+    if remote.map(|remote| &remote.entries) == Some(&tombstones.entries) {
+        return;
+    }
+    if let Err(e) = upload_file(client, &path, &remote_dir.join(TOMBSTONE_FILE)).await {
+        warn!("上传删除记录失败: {}", e);
+    }
 }
 
 /// 保存和加载同步状态
@@ -399,115 +646,307 @@ pub async fn load_sync_state() -> Result<(Option<FileSystemState>, Option<FileSy
     Ok((local_state, remote_state))
 }
 
-/// 根据差异结果计划同步操作
+/// 一次同步删除的文件或记录超过全部的该比例时视为异常（如数据库被重置），不执行删除
+pub const MAX_DELETE_RATIO: f64 = 0.5;
+/// 删除的数量不超过该值时不检查比例
+pub const MIN_DELETE_CHECK: usize = 10;
+
+/// 根据三方比较的结果计划同步操作
+///
+/// 没有同步基准的一端新出现的条目若与删除记录中的版本相同，视为删除前
+/// 残留的旧版本而删除。删除操作排在最后并先删除子项。计划删除的文件过多时
+/// 中止会话，避免基准丢失或目录误删后清空另一端。`device` 为本设备的标识，
+/// 用于判断冲突的同步记录属于哪台设备。
 pub fn plan_sync_operations(
     session: &mut SyncSession,
-    diff: &DiffResult,
+    entries: &[MergeEntry],
     conflict_strategy: ConflictStrategy,
+    tombstones: &Tombstones,
+    device: &str,
 ) -> Result<()> {
     session.status = SyncSessionStatus::Planning;
 
-    // 首先处理目录操作，确保目录结构存在
-    diff.entries
-        .iter()
-        .for_each(|entry| match entry.entry_type {
-            EntryType::Directory => do_when_dir(session, entry),
-            EntryType::File => do_when_file(session, entry, conflict_strategy),
-        });
+    // 按路径排序后父目录总在其内容之前，确保目录结构先于文件创建
+    for entry in entries {
+        match entry.merge_type {
+            MergeType::Same => {}
+            MergeType::LocalChanged if is_stale(entry, true, tombstones) => {
+                session.add_operation(SyncOperation::delete_local(entry));
+            }
+            MergeType::RemoteChanged if is_stale(entry, false, tombstones) => {
+                session.add_operation(SyncOperation::delete_remote(entry));
+            }
+            MergeType::LocalChanged => take_local(session, entry),
+            MergeType::RemoteChanged => take_remote(session, entry),
+            MergeType::Conflict => resolve_conflict(session, entry, conflict_strategy, device),
+        }
+    }
+
+    arrange_deletions(session, entries);
+
+    if let Some(reason) = check_deletions(session, entries) {
+        warn!("{}", reason);
+        session.abort(reason);
+    }
 
     Ok(())
 }
 
-// 辅助函数
-fn do_when_dir(session: &mut SyncSession, entry: &DiffEntry) {
-    // 处理目录的同步操作
-    match entry.diff_type {
-        DiffType::Added => {
-            if entry.local_state.is_some() && entry.remote_state.is_none() {
+// 辅助函数：只在一端新出现的条目与已删除的版本相同
+fn is_stale(entry: &MergeEntry, local: bool, tombstones: &Tombstones) -> bool {
+    let (state, change, other) = if local {
+        (&entry.local_state, &entry.local_change, &entry.remote_state)
+    } else {
+        (
+            &entry.remote_state,
+            &entry.remote_change,
+            &entry.local_state,
+        )
+    };
+    match (state, other) {
+        (Some(state), None) => {
+            *change == DiffType::Added && tombstones.is_deleted(&entry.path, state)
+        }
+        _ => false,
+    }
+}
+
+// 辅助函数：以本地版本为准
+fn take_local(session: &mut SyncSession, entry: &MergeEntry) {
+    match &entry.local_state {
+        Some(local) if local.is_directory() => {
+            if entry.remote_state.is_none() {
                 session.add_operation(SyncOperation::create_remote_directory(entry));
-            } else if entry.remote_state.is_some() && entry.local_state.is_none() {
+            }
+        }
+        Some(_) => session.add_operation(SyncOperation::upload(entry)),
+        None => {
+            if entry.remote_state.is_some() {
+                session.add_operation(SyncOperation::delete_remote(entry));
+            }
+        }
+    }
+}
+
+// 辅助函数：以远程版本为准
+fn take_remote(session: &mut SyncSession, entry: &MergeEntry) {
+    match &entry.remote_state {
+        Some(remote) if remote.is_directory() => {
+            if entry.local_state.is_none() {
                 session.add_operation(SyncOperation::create_local_directory(entry));
             }
         }
-        DiffType::Deleted => {
-            // 临时禁用删除操作
+        Some(_) => session.add_operation(SyncOperation::download(entry)),
+        None => {
+            if entry.local_state.is_some() {
+                session.add_operation(SyncOperation::delete_local(entry));
+            }
         }
-        _ => {} // 对于目录，其他差异类型不需要特殊处理
     }
 }
 
-fn do_when_file(session: &mut SyncSession, entry: &DiffEntry, conflict_strategy: ConflictStrategy) {
-    match entry.diff_type {
-        DiffType::Added => match (&entry.local_state, &entry.remote_state) {
-            (Some(_), None) => {
-                session.add_operation(SyncOperation::upload(entry));
+// 辅助函数：一端已删除时用另一端的版本恢复
+fn restore(session: &mut SyncSession, entry: &MergeEntry) {
+    match (&entry.local_state, &entry.remote_state) {
+        (Some(_), None) => take_local(session, entry),
+        (None, Some(_)) => take_remote(session, entry),
+        _ => {}
+    }
+}
+
+// 辅助函数：同步记录所属的设备，记录路径形如 `.records/<设备>/events/<id>.json`
+fn record_owner(path: &Path) -> Option<&str> {
+    let mut names = path.iter().filter_map(|name| name.to_str());
+    names.find(|name| *name == RECORD_DIR)?;
+    names.next()
+}
+
+// 辅助函数：按冲突策略处理两端都修改过的条目
+fn resolve_conflict(
+    session: &mut SyncSession,
+    entry: &MergeEntry,
+    strategy: ConflictStrategy,
+    device: &str,
+) {
+    // 一端删除而另一端修改时保留修改后的版本，不因冲突丢失内容
+    if strategy != ConflictStrategy::Skip
+        && entry.local_state.is_some() != entry.remote_state.is_some()
+    {
+        warn!(
+            "冲突，一端已删除，保留另一端修改后的版本: {}",
+            entry.path.display()
+        );
+        restore(session, entry);
+        return;
+    }
+    match strategy {
+        ConflictStrategy::PreferLocal => {
+            warn!("冲突，使用本地版本: {}", entry.path.display());
+            take_local(session, entry);
+        }
+        ConflictStrategy::PreferRemote => {
+            warn!("冲突，使用远程版本: {}", entry.path.display());
+            take_remote(session, entry);
+        }
+        // 另存的同步记录会被应用当作多余的记录读取，因此使用写入该记录的设备的版本
+        ConflictStrategy::KeepBoth => match record_owner(&entry.path) {
+            Some(owner) if owner == device => {
+                warn!("同步记录冲突，使用本设备的版本: {}", entry.path.display());
+                take_local(session, entry);
             }
-            (None, Some(_)) => {
-                session.add_operation(SyncOperation::download(entry));
+            Some(_) => {
+                warn!("同步记录冲突，使用远程版本: {}", entry.path.display());
+                take_remote(session, entry);
             }
-            _ => {}
+            None => keep_both(session, entry),
         },
-        DiffType::Deleted => {
-            // 检测到删除文件，但需要检查这个文件是真的被删除了还是被错误分类了
+        ConflictStrategy::Skip => {
+            warn!("跳过冲突文件: {}", entry.path.display());
+            session.add_operation(SyncOperation::skip(entry));
+        }
+    }
+}
 
-            // 如果远程存在但本地不存在，这应该是一个新的远程文件，需要下载而不是删除
-            match (&entry.local_state, &entry.remote_state) {
-                (None, Some(_)) => {
-                    session.add_operation(SyncOperation::download(entry));
-                }
-                (Some(_), None) => {
-                    session.add_operation(SyncOperation::upload(entry));
-                }
-                _ => {
-                    warn!("无法确定删除操作的正确性，跳过: {}", entry.path.display());
-                }
+// 辅助函数：本地版本另存为冲突副本后下载远程版本
+fn keep_both(session: &mut SyncSession, entry: &MergeEntry) {
+    match (&entry.local_state, &entry.remote_state) {
+        (Some(local), Some(remote)) if local.is_file() && remote.is_file() => {
+            let copy = conflict_copy_path(&entry.path, Utc::now().timestamp());
+            warn!("冲突，保留两者: 本地版本另存为 {}", copy.display());
+            session.add_operation(SyncOperation::copy_local(entry, copy.clone()));
+            session.add_operation(SyncOperation::upload_path(copy));
+            session.add_operation(SyncOperation::download(entry));
+        }
+        _ => {
+            warn!(
+                "冲突条目类型不同，无法保留两者，跳过: {}",
+                entry.path.display()
+            );
+            session.add_operation(SyncOperation::skip(entry));
+        }
+    }
+}
+
+fn is_deletion(operation: &SyncOperation) -> bool {
+    matches!(
+        operation.operation_type,
+        SyncOperationType::DeleteLocal | SyncOperationType::DeleteRemote
+    )
+}
+
+/// 整理删除操作
+///
+/// 目录下仍有保留的条目时不删除该目录，改为在另一端重新创建；其余删除移到
+/// 所有操作之后并按路径逆序排列，使子项先于所在目录删除。
+fn arrange_deletions(session: &mut SyncSession, entries: &[MergeEntry]) {
+    let deleted = |operation_type: SyncOperationType| -> Vec<PathBuf> {
+        session
+            .operations
+            .iter()
+            .filter(|op| op.operation_type == operation_type)
+            .map(|op| op.path.clone())
+            .collect()
+    };
+    let deleted_local = deleted(SyncOperationType::DeleteLocal);
+    let deleted_remote = deleted(SyncOperationType::DeleteRemote);
+    let kept = |dir: &Path, local: bool| {
+        entries.iter().any(|entry| {
+            if entry.path == dir || !entry.path.starts_with(dir) {
+                return false;
             }
+            if local {
+                entry.local_state.is_some() && !deleted_local.contains(&entry.path)
+            } else {
+                entry.remote_state.is_some() && !deleted_remote.contains(&entry.path)
+            }
+        })
+    };
+
+    for operation in &mut session.operations {
+        if operation.entry_type != EntryType::Directory {
+            continue;
         }
-        DiffType::Modified => {
-            // 文件被修改，根据冲突策略决定操作
-            match conflict_strategy {
-                ConflictStrategy::PreferLocal => {
-                    // 优先使用本地版本，上传
-                    warn!("计划上传本地修改的文件: {}", entry.path.display());
-                    session.add_operation(SyncOperation::upload(entry));
-                }
-                ConflictStrategy::PreferRemote => {
-                    // 优先使用远程版本，下载
-                    warn!("计划下载远程修改的文件: {}", entry.path.display());
-                    session.add_operation(SyncOperation::download(entry));
-                }
-                ConflictStrategy::KeepBoth => {
-                    let path = Path::new(&entry.path);
-                    let timestamp = Utc::now().timestamp();
-
-                    let stem = format!(
-                        "{}_remote_{}",
-                        path.file_stem().and_then(|s| s.to_str()).unwrap_or(""),
-                        timestamp
-                    );
-
-                    let mut path_buf = path.with_file_name(stem);
-                    if let Some(ext) = path.extension() {
-                        path_buf.set_extension(ext);
-                    }
+        match operation.operation_type {
+            SyncOperationType::DeleteLocal if kept(&operation.path, true) => {
+                warn!(
+                    "目录中仍有保留的条目，恢复目录: {}",
+                    operation.path.display()
+                );
+                operation.operation_type = SyncOperationType::CreateRemoteDirectory;
+            }
+            SyncOperationType::DeleteRemote if kept(&operation.path, false) => {
+                warn!(
+                    "目录中仍有保留的条目，恢复目录: {}",
+                    operation.path.display()
+                );
+                operation.operation_type = SyncOperationType::CreateLocalDirectory;
+            }
+            _ => {}
+        }
+    }
 
-                    warn!(
-                        "计划下载远程版本并重命名: {} -> {}",
-                        entry.path.display(),
-                        path_buf.display()
-                    );
-                    // 用新路径下载远程文件
-                    session.add_operation(SyncOperation::download(entry));
-                }
-                ConflictStrategy::Skip => {
-                    // 跳过冲突文件
-                    warn!("跳过冲突文件: {}", entry.path.display());
-                    session.add_operation(SyncOperation::skip(entry));
-                }
+    let (mut deletions, others): (Vec<_>, Vec<_>) =
+        session.operations.drain(..).partition(is_deletion);
+    deletions.reverse();
+    session.operations = others;
+    session.operations.extend(deletions);
+}
+
+/// 计划删除的文件占比超过阈值时返回中止的原因
+fn check_deletions(session: &SyncSession, entries: &[MergeEntry]) -> Option<String> {
+    let deletions = session
+        .operations
+        .iter()
+        .filter(|op| is_deletion(op) && op.entry_type == EntryType::File)
+        .count();
+    if deletions <= MIN_DELETE_CHECK {
+        return None;
+    }
+    let files = entries
+        .iter()
+        .filter(|entry| entry.entry_type == EntryType::File)
+        .count();
+    if deletions as f64 > files as f64 * MAX_DELETE_RATIO {
+        Some(format!(
+            "本次同步将删除 {}/{} 个文件，超过安全阈值，已中止同步",
+            deletions, files
+        ))
+    } else {
+        None
+    }
+}
+
+/// 冲突时本地版本另存的路径，如 `a/b.md` -> `a/b_local_1700000000.md`
+fn conflict_copy_path(path: &Path, timestamp: i64) -> PathBuf {
+    let stem = format!(
+        "{}_local_{}",
+        path.file_stem().and_then(|s| s.to_str()).unwrap_or(""),
+        timestamp
+    );
+    let mut path_buf = path.with_file_name(stem);
+    if let Some(ext) = path.extension() {
+        path_buf.set_extension(ext);
+    }
+    path_buf
+}
+
+/// 同步后作为下次比较基准的状态
+///
+/// 失败或跳过的路径沿用旧的基准，使其下次仍被识别为变化。
+fn next_base(
+    previous: Option<&FileSystemState>,
+    mut current: FileSystemState,
+    unfinished: &[&Path],
+) -> FileSystemState {
+    for path in unfinished {
+        match previous.and_then(|state| state.entries.get(*path)) {
+            Some(entry) => current.add_entry(entry.clone()),
+            None => {
+                current.entries.remove(*path);
             }
         }
-        _ => {} // 其他情况不需要处理
     }
+    current
 }
 
 /// 从完整的WebDAV路径提取标准化路径
@@ -605,7 +1044,14 @@ mod tests {
     use std::io::Write;
     use tempfile::tempdir;
 
-    use crate::model::{DiffEntry, DiffResult, DiffType, EntryState, EntryType, FileSystemState};
+    use super::{conflict_copy_path, next_base, plan_sync_operations, update_tombstones};
+    use crate::model::{
+        ConflictStrategy, DiffEntry, DiffResult, DiffType, EntryState, EntryType, FileSystemState,
+        MergeEntry, MergeType, SyncOperationStatus, SyncOperationType, SyncSession,
+        SyncSessionStatus,
+    };
+    use crate::tombstone::Tombstones;
+    use std::path::{Path, PathBuf};
 
     /// 创建测试用FileSystemState
     async fn create_test_state() -> Result<(FileSystemState, tempfile::TempDir)> {
@@ -688,4 +1134,358 @@ mod tests {
 
         diff
     }
+
+    fn merge_entry(
+        path: &str,
+        merge_type: MergeType,
+        local: Option<u64>,
+        remote: Option<u64>,
+    ) -> MergeEntry {
+        let now = Utc::now();
+        let change = |size: Option<u64>| match size {
+            Some(_) => DiffType::Modified,
+            None => DiffType::Deleted,
+        };
+        MergeEntry {
+            path: path.into(),
+            entry_type: EntryType::File,
+            merge_type,
+            local_change: change(local),
+            remote_change: change(remote),
+            local_state: local.map(|size| EntryState::new_file(path.into(), now, size)),
+            remote_state: remote.map(|size| EntryState::new_file(path.into(), now, size)),
+        }
+    }
+
+    fn plan(
+        entries: &[MergeEntry],
+        strategy: ConflictStrategy,
+    ) -> Vec<(SyncOperationType, PathBuf)> {
+        plan_with(entries, strategy, &Tombstones::default())
+    }
+
+    fn plan_with(
+        entries: &[MergeEntry],
+        strategy: ConflictStrategy,
+        tombstones: &Tombstones,
+    ) -> Vec<(SyncOperationType, PathBuf)> {
+        let mut session = SyncSession::new("/local".into(), "/ToDoPulse".into());
+        plan_sync_operations(&mut session, entries, strategy, tombstones, "laptop").unwrap();
+        session
+            .operations
+            .into_iter()
+            .map(|op| (op.operation_type, op.path))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_changes() {
+        let entries = [
+            merge_entry("ToDoPulse/same.md", MergeType::Same, Some(1), Some(1)),
+            merge_entry(
+                "ToDoPulse/local.md",
+                MergeType::LocalChanged,
+                Some(2),
+                Some(1),
+            ),
+            merge_entry(
+                "ToDoPulse/remote.md",
+                MergeType::RemoteChanged,
+                Some(1),
+                Some(2),
+            ),
+            merge_entry("ToDoPulse/gone.md", MergeType::LocalChanged, None, Some(1)),
+            merge_entry("ToDoPulse/lost.md", MergeType::RemoteChanged, Some(1), None),
+        ];
+        assert_eq!(
+            plan(&entries, ConflictStrategy::PreferLocal),
+            vec![
+                (SyncOperationType::Upload, "ToDoPulse/local.md".into()),
+                (SyncOperationType::Download, "ToDoPulse/remote.md".into()),
+                (SyncOperationType::DeleteLocal, "ToDoPulse/lost.md".into()),
+                (SyncOperationType::DeleteRemote, "ToDoPulse/gone.md".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_conflict_strategies() {
+        let conflict = [merge_entry(
+            "ToDoPulse/a/b.md",
+            MergeType::Conflict,
+            Some(2),
+            Some(3),
+        )];
+        let path = PathBuf::from("ToDoPulse/a/b.md");
+
+        assert_eq!(
+            plan(&conflict, ConflictStrategy::PreferLocal),
+            vec![(SyncOperationType::Upload, path.clone())]
+        );
+        assert_eq!(
+            plan(&conflict, ConflictStrategy::PreferRemote),
+            vec![(SyncOperationType::Download, path.clone())]
+        );
+        assert_eq!(
+            plan(&conflict, ConflictStrategy::Skip),
+            vec![(SyncOperationType::Skip, path.clone())]
+        );
+
+        let mut session = SyncSession::new("/local".into(), "/ToDoPulse".into());
+        plan_sync_operations(
+            &mut session,
+            &conflict,
+            ConflictStrategy::KeepBoth,
+            &Tombstones::default(),
+            "laptop",
+        )
+        .unwrap();
+        let ops = &session.operations;
+        assert_eq!(ops.len(), 3);
+        assert_eq!(ops[0].operation_type, SyncOperationType::CopyLocal);
+        let copy = ops[0].target.clone().unwrap();
+        assert!(copy.to_string_lossy().starts_with("ToDoPulse/a/b_local_"));
+        assert_eq!(ops[1].operation_type, SyncOperationType::Upload);
+        assert_eq!(ops[1].path, copy);
+        assert_eq!(ops[2].operation_type, SyncOperationType::Download);
+        assert_eq!(ops[2].path, path);
+
+        // 一端已删除时保留仍存在的版本
+        let deleted = [merge_entry(
+            "ToDoPulse/c.md",
+            MergeType::Conflict,
+            None,
+            Some(3),
+        )];
+        assert_eq!(
+            plan(&deleted, ConflictStrategy::KeepBoth),
+            vec![(SyncOperationType::Download, "ToDoPulse/c.md".into())]
+        );
+        assert_eq!(
+            plan(&deleted, ConflictStrategy::PreferLocal),
+            vec![(SyncOperationType::Download, "ToDoPulse/c.md".into())]
+        );
+
+        // 同步记录不另存副本，使用写入记录的设备的版本
+        let own = "ToDoPulse/.records/laptop/events/1.json";
+        let other = "ToDoPulse/.records/desktop/events/1.json";
+        let records = [
+            merge_entry(other, MergeType::Conflict, Some(2), Some(3)),
+            merge_entry(own, MergeType::Conflict, Some(2), Some(3)),
+        ];
+        assert_eq!(
+            plan(&records, ConflictStrategy::KeepBoth),
+            vec![
+                (SyncOperationType::Download, other.into()),
+                (SyncOperationType::Upload, own.into()),
+            ]
+        );
+    }
+
+    fn dir_entry(path: &str, merge_type: MergeType, local: bool, remote: bool) -> MergeEntry {
+        let now = Utc::now();
+        MergeEntry {
+            path: path.into(),
+            entry_type: EntryType::Directory,
+            merge_type,
+            local_change: DiffType::Unchanged,
+            remote_change: DiffType::Unchanged,
+            local_state: local.then(|| EntryState::new_directory(path.into(), now)),
+            remote_state: remote.then(|| EntryState::new_directory(path.into(), now)),
+        }
+    }
+
+    #[test]
+    fn test_plan_directory_deletions() {
+        let entries = [
+            dir_entry("ToDoPulse/a", MergeType::LocalChanged, false, true),
+            merge_entry("ToDoPulse/a/1.md", MergeType::LocalChanged, None, Some(1)),
+            dir_entry("ToDoPulse/a/b", MergeType::LocalChanged, false, true),
+            merge_entry("ToDoPulse/a/b/2.md", MergeType::LocalChanged, None, Some(1)),
+            // 远程删除了目录，但本地在其中新建了文件
+            dir_entry("ToDoPulse/c", MergeType::RemoteChanged, true, false),
+            merge_entry("ToDoPulse/c/3.md", MergeType::RemoteChanged, Some(1), None),
+            merge_entry("ToDoPulse/c/4.md", MergeType::LocalChanged, Some(1), None),
+        ];
+        assert_eq!(
+            plan(&entries, ConflictStrategy::PreferLocal),
+            vec![
+                (
+                    SyncOperationType::CreateRemoteDirectory,
+                    "ToDoPulse/c".into()
+                ),
+                (SyncOperationType::Upload, "ToDoPulse/c/4.md".into()),
+                (SyncOperationType::DeleteLocal, "ToDoPulse/c/3.md".into()),
+                (SyncOperationType::DeleteRemote, "ToDoPulse/a/b/2.md".into()),
+                (SyncOperationType::DeleteRemote, "ToDoPulse/a/b".into()),
+                (SyncOperationType::DeleteRemote, "ToDoPulse/a/1.md".into()),
+                (SyncOperationType::DeleteRemote, "ToDoPulse/a".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_stale_with_tombstones() {
+        let now = Utc::now();
+        let mut stale = merge_entry("ToDoPulse/stale.md", MergeType::LocalChanged, Some(1), None);
+        stale.local_change = DiffType::Added;
+        // 修改时间来自另一台设备的时钟，晚于删除时间也不影响判断
+        stale.local_state = Some(EntryState::new_file(
+            "ToDoPulse/stale.md".into(),
+            now + chrono::Duration::days(1),
+            1,
+        ));
+        let mut edited = merge_entry(
+            "ToDoPulse/edited.md",
+            MergeType::LocalChanged,
+            Some(2),
+            None,
+        );
+        edited.local_change = DiffType::Added;
+
+        let mut tombstones = Tombstones::default();
+        for path in ["ToDoPulse/stale.md", "ToDoPulse/edited.md"] {
+            tombstones.record(
+                Path::new(path),
+                "desktop",
+                now - chrono::Duration::days(1),
+                &EntryState::new_file(path.into(), now - chrono::Duration::days(3), 1),
+            );
+        }
+
+        // 与删除的版本不同的文件重新上传，其余视为残留而删除
+        assert_eq!(
+            plan_with(&[edited, stale], ConflictStrategy::PreferLocal, &tombstones),
+            vec![
+                (SyncOperationType::Upload, "ToDoPulse/edited.md".into()),
+                (SyncOperationType::DeleteLocal, "ToDoPulse/stale.md".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_aborts_mass_deletion() {
+        let entries: Vec<MergeEntry> = (0..30)
+            .map(|i| {
+                let path = format!("ToDoPulse/{}.md", i);
+                if i < 11 {
+                    merge_entry(&path, MergeType::LocalChanged, None, Some(1))
+                } else {
+                    merge_entry(&path, MergeType::Same, Some(1), Some(1))
+                }
+            })
+            .collect();
+        let plan_status = |entries: &[MergeEntry]| {
+            let mut session = SyncSession::new("/local".into(), "/ToDoPulse".into());
+            plan_sync_operations(
+                &mut session,
+                entries,
+                ConflictStrategy::PreferLocal,
+                &Tombstones::default(),
+                "laptop",
+            )
+            .unwrap();
+            session
+        };
+
+        let aborted = plan_status(&entries[..20]);
+        assert_eq!(aborted.status, SyncSessionStatus::Aborted);
+        assert!(aborted.error.is_some());
+        // 删除比例未超过阈值，或删除数量较少时不中止
+        assert_eq!(plan_status(&entries).status, SyncSessionStatus::Planning);
+        assert_eq!(
+            plan_status(&entries[1..12]).status,
+            SyncSessionStatus::Planning
+        );
+    }
+
+    #[test]
+    fn test_update_tombstones() {
+        let entries = [
+            merge_entry("ToDoPulse/mine.md", MergeType::LocalChanged, None, Some(1)),
+            merge_entry(
+                "ToDoPulse/theirs.md",
+                MergeType::RemoteChanged,
+                Some(1),
+                None,
+            ),
+            merge_entry("ToDoPulse/back.md", MergeType::LocalChanged, Some(1), None),
+        ];
+        let mut session = SyncSession::new("/local".into(), "/ToDoPulse".into());
+        plan_sync_operations(
+            &mut session,
+            &entries,
+            ConflictStrategy::PreferLocal,
+            &Tombstones::default(),
+            "laptop",
+        )
+        .unwrap();
+        for operation in &mut session.operations {
+            operation.status = SyncOperationStatus::Completed;
+        }
+
+        let then = Utc::now() - chrono::Duration::days(1);
+        let mut tombstones = Tombstones::default();
+        for path in ["ToDoPulse/theirs.md", "ToDoPulse/back.md"] {
+            tombstones.record(
+                Path::new(path),
+                "desktop",
+                then,
+                &EntryState::new_file(path.into(), then, 1),
+            );
+        }
+        update_tombstones(&mut tombstones, &session, &entries, "laptop");
+
+        assert_eq!(tombstones.entries.len(), 2);
+        let mine = &tombstones.entries[Path::new("ToDoPulse/mine.md")];
+        assert_eq!(mine.device, "laptop");
+        assert_eq!(mine.size, Some(1), "记录被删除的远程版本");
+        assert_eq!(
+            tombstones.entries[Path::new("ToDoPulse/theirs.md")].device,
+            "desktop"
+        );
+    }
+
+    #[test]
+    fn test_conflict_strategy_from_name() {
+        assert_eq!(
+            ConflictStrategy::from_name("KeepBoth"),
+            ConflictStrategy::KeepBoth
+        );
+        assert_eq!(ConflictStrategy::from_name("Skip"), ConflictStrategy::Skip);
+        assert_eq!(
+            ConflictStrategy::from_name("unknown"),
+            ConflictStrategy::PreferLocal
+        );
+    }
+
+    #[test]
+    fn test_conflict_copy_path() {
+        assert_eq!(
+            conflict_copy_path(Path::new("ToDoPulse/data.db"), 7),
+            PathBuf::from("ToDoPulse/data_local_7.db")
+        );
+        assert_eq!(
+            conflict_copy_path(Path::new("ToDoPulse/notes"), 7),
+            PathBuf::from("ToDoPulse/notes_local_7")
+        );
+    }
+
+    #[test]
+    fn test_next_base() {
+        let then = Utc::now() - chrono::Duration::hours(1);
+        let now = Utc::now();
+        let mut previous = FileSystemState::new();
+        previous.add_entry(EntryState::new_file("failed.md".into(), then, 1));
+        let mut current = FileSystemState::new();
+        current.add_entry(EntryState::new_file("failed.md".into(), now, 2));
+        current.add_entry(EntryState::new_file("done.md".into(), now, 3));
+        current.add_entry(EntryState::new_file("new_failed.md".into(), now, 4));
+
+        let unfinished = [Path::new("failed.md"), Path::new("new_failed.md")];
+        let base = next_base(Some(&previous), current, &unfinished);
+        assert_eq!(base.entry_count(), 2);
+        assert_eq!(base.entries[Path::new("failed.md")].size, Some(1));
+        assert_eq!(base.entries[Path::new("done.md")].size, Some(3));
+    }
 }
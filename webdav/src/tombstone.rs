@@ -0,0 +1,221 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::model::EntryState;
+
+/// 远程目录中保存删除记录的文件名，不参与文件同步
+pub const TOMBSTONE_FILE: &str = ".tombstones.json";
+
+/// 删除记录的保留天数，超过后清除
+const KEEP_DAYS: i64 = 90;
+
+/// 一条删除记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// 删除被同步的时间
+    pub deleted_at: DateTime<Utc>,
+    /// 发起删除的设备
+    pub device: String,
+    /// 被删除版本的大小，目录为 None
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// 被删除版本的内容哈希（如果有）
+    #[serde(default)]
+    pub content_hash: Option<String>,
+}
+
+/// 已删除路径的记录，键为相对路径
+///
+/// 各设备共享同一份记录，没有同步基准的设备据此识别出本地残留的
+/// 已删除文件，而不是把它们重新上传。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tombstones {
+    pub entries: HashMap<PathBuf, Tombstone>,
+}
+
+impl Tombstones {
+    /// 读取删除记录，文件不存在或损坏时从空记录开始
+    pub async fn load(path: &Path) -> Self {
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                warn!("删除记录损坏，已忽略: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// 先写入临时文件再替换，避免中断时留下不完整的记录
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let temp = path.with_extension("json.tmp");
+        tokio::fs::write(&temp, serde_json::to_string_pretty(self)?).await?;
+        tokio::fs::rename(&temp, path).await?;
+        Ok(())
+    }
+
+    /// 记录路径被删除，同时保存被删除版本的大小与哈希
+    pub fn record(
+        &mut self,
+        path: &Path,
+        device: &str,
+        deleted_at: DateTime<Utc>,
+        deleted: &EntryState,
+    ) {
+        self.entries.insert(
+            path.to_path_buf(),
+            Tombstone {
+                deleted_at,
+                device: device.to_string(),
+                size: deleted.size.filter(|_| !deleted.is_directory()),
+                content_hash: deleted.content_hash.clone(),
+            },
+        );
+    }
+
+    /// 路径被重新创建后移除其删除记录
+    pub fn revive(&mut self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    /// 合并另一设备的记录，同一路径保留较晚的删除
+    pub fn merge(&mut self, other: Tombstones) {
+        for (path, tombstone) in other.entries {
+            match self.entries.get(&path) {
+                Some(current) if current.deleted_at >= tombstone.deleted_at => {}
+                _ => {
+                    self.entries.insert(path, tombstone);
+                }
+            }
+        }
+    }
+
+    /// 条目与被删除的版本相同
+    ///
+    /// 删除时间来自发起删除的设备，与文件的修改时间不可比较，因此按大小与
+    /// 哈希判断。双方都有哈希时须相同，目录只要曾被删除即视为相同。
+    pub fn is_deleted(&self, path: &Path, state: &EntryState) -> bool {
+        let Some(tombstone) = self.entries.get(path) else {
+            return false;
+        };
+        match (state.is_directory(), tombstone.size) {
+            (true, None) => true,
+            (false, Some(size)) => {
+                state.size == Some(size)
+                    && match (&state.content_hash, &tombstone.content_hash) {
+                        (Some(h1), Some(h2)) => h1 == h2,
+                        _ => true,
+                    }
+            }
+            _ => false,
+        }
+    }
+
+    /// 清除过期的记录
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::days(KEEP_DAYS);
+        self.entries
+            .retain(|_, tombstone| tombstone.deleted_at >= cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size: u64) -> EntryState {
+        EntryState::new_file(path.into(), Utc::now(), size)
+    }
+
+    #[test]
+    fn test_merge_and_prune() {
+        let now = Utc::now();
+        let path = Path::new("ToDoPulse/a.md");
+        let deleted = file("ToDoPulse/a.md", 10);
+
+        let mut local = Tombstones::default();
+        local.record(path, "laptop", now - Duration::days(2), &deleted);
+        local.record(
+            Path::new("ToDoPulse/old.md"),
+            "laptop",
+            now - Duration::days(100),
+            &file("ToDoPulse/old.md", 1),
+        );
+
+        let mut remote = Tombstones::default();
+        remote.record(path, "desktop", now - Duration::days(1), &deleted);
+        remote.record(
+            Path::new("ToDoPulse/b.md"),
+            "desktop",
+            now,
+            &file("ToDoPulse/b.md", 1),
+        );
+
+        local.merge(remote);
+        assert_eq!(local.entries.len(), 3);
+        assert_eq!(local.entries[path].device, "desktop");
+
+        local.prune(now);
+        assert_eq!(local.entries.len(), 2);
+        local.revive(path);
+        assert_eq!(local.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_is_deleted() {
+        let now = Utc::now();
+        let path = Path::new("ToDoPulse/a.md");
+        let mut tombstones = Tombstones::default();
+        tombstones.record(path, "desktop", now, &file("ToDoPulse/a.md", 10));
+
+        // 与修改时间无关，只比较内容
+        let mut leftover = file("ToDoPulse/a.md", 10);
+        leftover.modified = now + Duration::days(1);
+        assert!(tombstones.is_deleted(path, &leftover));
+        assert!(!tombstones.is_deleted(path, &file("ToDoPulse/a.md", 11)));
+        assert!(!tombstones.is_deleted(Path::new("ToDoPulse/b.md"), &leftover));
+        assert!(!tombstones.is_deleted(path, &EntryState::new_directory(path.into(), now)));
+
+        // 双方都有哈希时须相同
+        tombstones.record(
+            path,
+            "desktop",
+            now,
+            &file("ToDoPulse/a.md", 10).with_hash("abc".to_string()),
+        );
+        assert!(tombstones.is_deleted(path, &leftover));
+        assert!(!tombstones.is_deleted(path, &leftover.with_hash("def".to_string())));
+
+        let dir = Path::new("ToDoPulse/old");
+        let dir_state = EntryState::new_directory(dir.into(), now);
+        tombstones.record(dir, "desktop", now, &dir_state);
+        assert!(tombstones.is_deleted(dir, &dir_state));
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("sync_state").join("tombstones.json");
+        assert!(Tombstones::load(&path).await.entries.is_empty());
+
+        let mut tombstones = Tombstones::default();
+        tombstones.record(
+            Path::new("ToDoPulse/a.md"),
+            "laptop",
+            Utc::now(),
+            &file("ToDoPulse/a.md", 1),
+        );
+        tombstones.save(&path).await?;
+        assert_eq!(Tombstones::load(&path).await.entries, tombstones.entries);
+
+        tokio::fs::write(&path, "not json").await?;
+        assert!(Tombstones::load(&path).await.entries.is_empty());
+        Ok(())
+    }
+}
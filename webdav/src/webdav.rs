@@ -123,6 +123,17 @@ pub async fn download_file(client: &Client, remote_path: &Path, local_path: &Pat
     Ok(())
 }
 
+/// 删除WebDAV服务器上的文件或目录，目录连同其内容一起删除
+pub async fn delete_remote(client: &Client, remote_path: &Path) -> Result<()> {
+    debug!("删除远程条目: {}", remote_path.display());
+
+    let remote_path = &remote_path.as_os_str().to_string_lossy();
+    client.delete(remote_path).await?;
+
+    debug!("远程条目删除成功");
+    Ok(())
+}
+
 /// 收集远程文件系统状态
 pub async fn collect_remote_state(client: &Client, remote_dir: &Path) -> Result<FileSystemState> {
     info!("收集远程文件系统状态: {}", remote_dir.display());
@@ -0,0 +1,238 @@
+use anyhow::{Result, anyhow};
+
+/// 简化的 XML 元素，只保留本地名（去掉命名空间前缀）、属性、子元素与文本
+///
+/// 仅用于解析 WebDAV/CalDAV 的 multistatus 响应，不校验命名空间。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// 第一个指定名称的子元素
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    /// 所有指定名称的子元素
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// 去掉首尾空白的文本内容
+    pub fn text(&self) -> &str {
+        self.text.trim()
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// 反转义实体引用
+pub fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 转义文本与属性值中的特殊字符
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 开始标签的结束位置，属性值中的 `>` 不算
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 解析开始标签中的名称与属性，如 `c:comp name="VTODO"`
+fn open_tag(inner: &str) -> Element {
+    let inner = inner.trim();
+    let name_end = inner.find(char::is_whitespace).unwrap_or(inner.len());
+    let mut element = Element {
+        name: local_name(&inner[..name_end]).to_string(),
+        ..Default::default()
+    };
+    let mut rest = inner[name_end..].trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let value = rest[eq + 1..].trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            break;
+        };
+        let Some(end) = value[1..].find(quote) else {
+            break;
+        };
+        // 命名空间声明不作为属性保留
+        if key != "xmlns" && !key.starts_with("xmlns:") {
+            element
+                .attrs
+                .push((local_name(key).to_string(), unescape(&value[1..end + 1])));
+        }
+        rest = value[end + 2..].trim_start();
+    }
+    element
+}
+
+/// 解析 XML 文本，返回根元素
+pub fn parse(xml: &str) -> Result<Element> {
+    // 栈底是虚拟的文档节点
+    let mut stack = vec![Element::default()];
+    let mut rest = xml;
+    let unclosed = || anyhow!("XML 格式错误: 标记未闭合");
+
+    while !rest.is_empty() {
+        let start = rest.find('<').unwrap_or(rest.len());
+        if start > 0 {
+            let text = unescape(&rest[..start]);
+            stack.last_mut().unwrap().text.push_str(&text);
+            rest = &rest[start..];
+            continue;
+        }
+
+        if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or_else(unclosed)?;
+            stack.last_mut().unwrap().text.push_str(&after[..end]);
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or_else(unclosed)?;
+            rest = &after[end + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = rest.find('>').ok_or_else(unclosed)?;
+            rest = &rest[end + 1..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').ok_or_else(unclosed)?;
+            let name = local_name(after[..end].trim());
+            if stack.len() < 2 || stack.last().unwrap().name != name {
+                return Err(anyhow!("XML 格式错误: 多余的结束标记 </{}>", name));
+            }
+            let element = stack.pop().unwrap();
+            stack.last_mut().unwrap().children.push(element);
+            rest = &after[end + 1..];
+        } else {
+            let end = tag_end(rest).ok_or_else(unclosed)?;
+            let inner = &rest[1..end];
+            match inner.strip_suffix('/') {
+                Some(inner) => {
+                    let element = open_tag(inner);
+                    stack.last_mut().unwrap().children.push(element);
+                }
+                None => stack.push(open_tag(inner)),
+            }
+            rest = &rest[end + 1..];
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err(unclosed());
+    }
+    stack
+        .pop()
+        .and_then(|document| document.children.into_iter().next())
+        .ok_or_else(|| anyhow!("XML 格式错误: 没有根元素"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- multistatus -->
+<d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:response>
+    <d:href>/cal/a%20b.ics</d:href>
+    <d:propstat>
+      <d:prop>
+        <d:getetag>"1-&amp;"</d:getetag>
+        <c:comp name='VTODO' />
+        <c:calendar-data><![CDATA[BEGIN:VCALENDAR
+SUMMARY:<x>]]></c:calendar-data>
+      </d:prop>
+      <d:status>HTTP/1.1 200 OK</d:status>
+    </d:propstat>
+  </d:response>
+</d:multistatus>"#;
+        let root = parse(xml).unwrap();
+        assert_eq!(root.name, "multistatus");
+        assert!(root.attrs.is_empty());
+        let response = root.child("response").unwrap();
+        assert_eq!(response.child("href").unwrap().text(), "/cal/a%20b.ics");
+        let prop = response.child("propstat").unwrap().child("prop").unwrap();
+        assert_eq!(prop.child("getetag").unwrap().text(), "\"1-&\"");
+        assert_eq!(prop.child("comp").unwrap().attr("name"), Some("VTODO"));
+        assert_eq!(
+            prop.child("calendar-data").unwrap().text(),
+            "BEGIN:VCALENDAR\nSUMMARY:<x>"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("<a><b></a>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("text only").is_err());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(unescape("&lt;&#65;&#x42;&unknown;"), "<AB&unknown;");
+        assert_eq!(unescape(&escape("a<b & \"c\"")), "a<b & \"c\"");
+    }
+}